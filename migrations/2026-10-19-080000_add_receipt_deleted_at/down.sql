@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "receipts_deleted_at_idx";
+ALTER TABLE "receipts" DROP COLUMN IF EXISTS "deleted_at";
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "receipts" ADD COLUMN IF NOT EXISTS "deleted_at" TIMESTAMP;
+CREATE INDEX IF NOT EXISTS "receipts_deleted_at_idx" ON "receipts" ("deleted_at");
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "inventories" DROP COLUMN IF EXISTS "version";
+ALTER TABLE "receipts" DROP COLUMN IF EXISTS "version";
+ALTER TABLE "products" DROP COLUMN IF EXISTS "version";
+ALTER TABLE "stores" DROP COLUMN IF EXISTS "version";
+ALTER TABLE "currencies" DROP COLUMN IF EXISTS "version";
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE "currencies" ADD COLUMN IF NOT EXISTS "version" INT4 NOT NULL DEFAULT 1;
+ALTER TABLE "stores" ADD COLUMN IF NOT EXISTS "version" INT4 NOT NULL DEFAULT 1;
+ALTER TABLE "products" ADD COLUMN IF NOT EXISTS "version" INT4 NOT NULL DEFAULT 1;
+ALTER TABLE "receipts" ADD COLUMN IF NOT EXISTS "version" INT4 NOT NULL DEFAULT 1;
+ALTER TABLE "inventories" ADD COLUMN IF NOT EXISTS "version" INT4 NOT NULL DEFAULT 1;
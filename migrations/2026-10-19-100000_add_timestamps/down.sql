@@ -0,0 +1,20 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS set_updated_at ON "inventories";
+ALTER TABLE "inventories" DROP COLUMN IF EXISTS "updated_at";
+ALTER TABLE "inventories" DROP COLUMN IF EXISTS "created_at";
+
+DROP TRIGGER IF EXISTS set_updated_at ON "receipts";
+ALTER TABLE "receipts" DROP COLUMN IF EXISTS "updated_at";
+ALTER TABLE "receipts" DROP COLUMN IF EXISTS "created_at";
+
+DROP TRIGGER IF EXISTS set_updated_at ON "products";
+ALTER TABLE "products" DROP COLUMN IF EXISTS "updated_at";
+ALTER TABLE "products" DROP COLUMN IF EXISTS "created_at";
+
+DROP TRIGGER IF EXISTS set_updated_at ON "stores";
+ALTER TABLE "stores" DROP COLUMN IF EXISTS "updated_at";
+ALTER TABLE "stores" DROP COLUMN IF EXISTS "created_at";
+
+DROP TRIGGER IF EXISTS set_updated_at ON "currencies";
+ALTER TABLE "currencies" DROP COLUMN IF EXISTS "updated_at";
+ALTER TABLE "currencies" DROP COLUMN IF EXISTS "created_at";
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+ALTER TABLE "currencies" ADD COLUMN IF NOT EXISTS "created_at" TIMESTAMP NOT NULL DEFAULT NOW();
+ALTER TABLE "currencies" ADD COLUMN IF NOT EXISTS "updated_at" TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('currencies');
+
+ALTER TABLE "stores" ADD COLUMN IF NOT EXISTS "created_at" TIMESTAMP NOT NULL DEFAULT NOW();
+ALTER TABLE "stores" ADD COLUMN IF NOT EXISTS "updated_at" TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('stores');
+
+ALTER TABLE "products" ADD COLUMN IF NOT EXISTS "created_at" TIMESTAMP NOT NULL DEFAULT NOW();
+ALTER TABLE "products" ADD COLUMN IF NOT EXISTS "updated_at" TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('products');
+
+ALTER TABLE "receipts" ADD COLUMN IF NOT EXISTS "created_at" TIMESTAMP NOT NULL DEFAULT NOW();
+ALTER TABLE "receipts" ADD COLUMN IF NOT EXISTS "updated_at" TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('receipts');
+
+ALTER TABLE "inventories" ADD COLUMN IF NOT EXISTS "created_at" TIMESTAMP NOT NULL DEFAULT NOW();
+ALTER TABLE "inventories" ADD COLUMN IF NOT EXISTS "updated_at" TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('inventories');
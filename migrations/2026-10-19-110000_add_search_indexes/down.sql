@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "stores_search_text_tsv_idx";
+DROP INDEX IF EXISTS "stores_search_text_trgm_idx";
+ALTER TABLE "stores" DROP COLUMN IF EXISTS "search_text";
+
+DROP INDEX IF EXISTS "products_search_text_tsv_idx";
+DROP INDEX IF EXISTS "products_search_text_trgm_idx";
+ALTER TABLE "products" DROP COLUMN IF EXISTS "search_text";
+
+DROP EXTENSION IF EXISTS pg_trgm;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+ALTER TABLE "products" ADD COLUMN IF NOT EXISTS "search_text" TEXT NOT NULL GENERATED ALWAYS AS (
+    "name" || ' ' || COALESCE("alias", '') || ' ' || COALESCE("brand", '') || ' ' || COALESCE("specification_unit", '') || ' ' || COALESCE("specification_others", '')
+) STORED;
+CREATE INDEX IF NOT EXISTS "products_search_text_trgm_idx" ON "products" USING GIN ("search_text" gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS "products_search_text_tsv_idx" ON "products" USING GIN (to_tsvector('simple', "search_text"));
+
+ALTER TABLE "stores" ADD COLUMN IF NOT EXISTS "search_text" TEXT NOT NULL GENERATED ALWAYS AS (
+    "name" || ' ' || COALESCE("alias", '') || ' ' || COALESCE("branch", '') || ' ' || COALESCE("address", '')
+) STORED;
+CREATE INDEX IF NOT EXISTS "stores_search_text_trgm_idx" ON "stores" USING GIN ("search_text" gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS "stores_search_text_tsv_idx" ON "stores" USING GIN (to_tsvector('simple', "search_text"));
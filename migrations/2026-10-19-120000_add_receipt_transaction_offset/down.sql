@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "receipts" ALTER COLUMN "transaction_date" TYPE TIMESTAMP USING ("transaction_date" AT TIME ZONE 'UTC') + "transaction_offset" * INTERVAL '1 second';
+ALTER TABLE "receipts" DROP COLUMN "transaction_offset";
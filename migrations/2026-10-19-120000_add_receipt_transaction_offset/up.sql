@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- existing transaction dates are the wall-clock time printed on the receipt without a zone, so they are read in the zone
+-- given by the receipt_repository.default_time_zone setting, which should match DEFAULT_TIME_ZONE, and in UTC when it is not set
+-- seconds east of UTC where the receipt was issued, used to show the local time printed on it
+ALTER TABLE "receipts" ADD COLUMN "transaction_offset" INTEGER NOT NULL DEFAULT 0;
+UPDATE "receipts" SET "transaction_offset" = EXTRACT(EPOCH FROM "transaction_date" - (("transaction_date" AT TIME ZONE COALESCE(NULLIF(current_setting('receipt_repository.default_time_zone', true), ''), 'UTC')) AT TIME ZONE 'UTC'))::INTEGER;
+ALTER TABLE "receipts" ALTER COLUMN "transaction_date" TYPE TIMESTAMPTZ USING "transaction_date" AT TIME ZONE COALESCE(NULLIF(current_setting('receipt_repository.default_time_zone', true), ''), 'UTC');
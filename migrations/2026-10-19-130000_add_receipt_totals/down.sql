@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "receipts" DROP COLUMN "printed_total";
+ALTER TABLE "receipts" DROP COLUMN "service_charge";
+ALTER TABLE "receipts" DROP COLUMN "discount";
+ALTER TABLE "receipts" DROP COLUMN "tax";
+ALTER TABLE "inventories" DROP COLUMN "discount";
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- discount taken off a single line, the line total is price * quantity - discount
+ALTER TABLE "inventories" ADD COLUMN "discount" NUMERIC NOT NULL DEFAULT 0;
+-- receipt level amounts, the subtotal and the grand total are computed from the lines
+ALTER TABLE "receipts" ADD COLUMN "tax" NUMERIC NOT NULL DEFAULT 0;
+ALTER TABLE "receipts" ADD COLUMN "discount" NUMERIC NOT NULL DEFAULT 0;
+ALTER TABLE "receipts" ADD COLUMN "service_charge" NUMERIC NOT NULL DEFAULT 0;
+-- the total printed on the paper receipt as entered by the client
+ALTER TABLE "receipts" ADD COLUMN "printed_total" NUMERIC;
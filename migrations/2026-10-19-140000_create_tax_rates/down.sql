@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "receipts" ADD COLUMN "is_inventory_taxed" BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE "receipts" ADD COLUMN "tax" NUMERIC NOT NULL DEFAULT 0;
+UPDATE "receipts" SET "tax" = COALESCE((SELECT SUM("post_tax_amount" - "pre_tax_amount") FROM "inventories" WHERE "inventories"."receipt_id" = "receipts"."id"), 0);
+-- tax charged on top of a line total means the prices of the receipt did not include it
+UPDATE "receipts" SET "is_inventory_taxed" = NOT EXISTS (SELECT 1 FROM "inventories" WHERE "inventories"."receipt_id" = "receipts"."id" AND "post_tax_amount" > "price" * "quantity" - "discount");
+ALTER TABLE "inventories" DROP COLUMN "post_tax_amount";
+ALTER TABLE "inventories" DROP COLUMN "pre_tax_amount";
+ALTER TABLE "inventories" DROP COLUMN "tax_rate_id";
+ALTER TABLE "stores" DROP COLUMN "tax_rate_id";
+DROP TABLE "tax_rates";
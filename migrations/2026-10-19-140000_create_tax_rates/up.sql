@@ -0,0 +1,53 @@
+-- Your SQL goes here
+CREATE TABLE "tax_rates" (
+  "id" SERIAL PRIMARY KEY,
+  "name" TEXT NOT NULL UNIQUE,
+  -- a fraction of the pre-tax amount, 0.05 is 5%
+  "rate" NUMERIC NOT NULL CHECK ("rate" >= 0),
+  -- an inclusive rate is already in the printed prices, an exclusive one is charged on top of them
+  "is_inclusive" BOOLEAN NOT NULL DEFAULT TRUE,
+  "version" INT4 NOT NULL DEFAULT 1,
+  "created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+SELECT diesel_manage_updated_at('tax_rates');
+
+INSERT INTO "tax_rates" ("name", "rate", "is_inclusive") VALUES ('Default', 0, TRUE);
+
+-- the rate of a store is copied to the lines of its new receipts unless a line overrides it,
+-- a line without any rate is exempt
+ALTER TABLE "stores" ADD COLUMN "tax_rate_id" INTEGER REFERENCES "tax_rates" ("id");
+ALTER TABLE "inventories" ADD COLUMN "tax_rate_id" INTEGER REFERENCES "tax_rates" ("id");
+-- amounts are computed when a line is written, so changing a rate later does not rewrite the history
+ALTER TABLE "inventories" ADD COLUMN "pre_tax_amount" NUMERIC NOT NULL DEFAULT 0;
+ALTER TABLE "inventories" ADD COLUMN "post_tax_amount" NUMERIC NOT NULL DEFAULT 0;
+
+UPDATE "stores" SET "tax_rate_id" = (SELECT "id" FROM "tax_rates" WHERE "name" = 'Default');
+UPDATE "inventories" SET 
+  "tax_rate_id" = (SELECT "id" FROM "tax_rates" WHERE "name" = 'Default'),
+  "pre_tax_amount" = "price" * "quantity" - "discount",
+  "post_tax_amount" = "price" * "quantity" - "discount";
+
+-- the tax entered on a receipt is spread over its lines by their share of the line totals, the last line takes the rounding remainder,
+-- it is already in the prices of a taxed receipt and charged on top of them otherwise, so every grand total stays the same
+UPDATE "inventories" i SET
+  "pre_tax_amount" = CASE WHEN s."is_inventory_taxed" THEN i."pre_tax_amount" - s."line_tax" ELSE i."pre_tax_amount" END,
+  "post_tax_amount" = CASE WHEN s."is_inventory_taxed" THEN i."post_tax_amount" ELSE i."post_tax_amount" + s."line_tax" END
+FROM (
+  SELECT l."id", l."is_inventory_taxed",
+    CASE WHEN l."line_number" = l."line_count" THEN l."tax" - SUM(l."share") OVER (PARTITION BY l."receipt_id") + l."share" ELSE l."share" END AS "line_tax"
+  FROM (
+    SELECT li."id", li."receipt_id", r."tax", r."is_inventory_taxed",
+      ROUND(COALESCE(r."tax" * li."pre_tax_amount" / NULLIF(SUM(li."pre_tax_amount") OVER (PARTITION BY li."receipt_id"), 0), 0), 4) AS "share",
+      ROW_NUMBER() OVER (PARTITION BY li."receipt_id" ORDER BY li."id") AS "line_number",
+      COUNT(*) OVER (PARTITION BY li."receipt_id") AS "line_count"
+    FROM "inventories" li
+    INNER JOIN "receipts" r ON r."id" = li."receipt_id"
+    WHERE r."tax" <> 0
+  ) l
+) s
+WHERE i."id" = s."id";
+
+-- the client entered tax and the taxed flag are replaced by the amounts computed from the lines
+ALTER TABLE "receipts" DROP COLUMN "tax";
+ALTER TABLE "receipts" DROP COLUMN "is_inventory_taxed";
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "tax_rates" ALTER COLUMN "rate" TYPE NUMERIC;
+ALTER TABLE "receipts" ALTER COLUMN "printed_total" TYPE NUMERIC;
+ALTER TABLE "receipts" ALTER COLUMN "service_charge" TYPE NUMERIC;
+ALTER TABLE "receipts" ALTER COLUMN "discount" TYPE NUMERIC;
+ALTER TABLE "inventories" ALTER COLUMN "post_tax_amount" TYPE NUMERIC;
+ALTER TABLE "inventories" ALTER COLUMN "pre_tax_amount" TYPE NUMERIC;
+ALTER TABLE "inventories" ALTER COLUMN "discount" TYPE NUMERIC;
+ALTER TABLE "inventories" ALTER COLUMN "price" TYPE NUMERIC;
+ALTER TABLE "currencies" DROP COLUMN "minor_units";
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- digits after the decimal point the currency allows, an amount with more of them is rejected
+ALTER TABLE "currencies" ADD COLUMN "minor_units" INTEGER NOT NULL DEFAULT 2 CHECK ("minor_units" BETWEEN 0 AND 4);
+
+-- 4 decimal places cover every ISO 4217 currency, amounts entered as binary floats before are rounded to them
+ALTER TABLE "inventories" ALTER COLUMN "price" TYPE NUMERIC(19, 4) USING ROUND("price", 4);
+ALTER TABLE "inventories" ALTER COLUMN "discount" TYPE NUMERIC(19, 4) USING ROUND("discount", 4);
+ALTER TABLE "inventories" ALTER COLUMN "pre_tax_amount" TYPE NUMERIC(19, 4) USING ROUND("pre_tax_amount", 4);
+ALTER TABLE "inventories" ALTER COLUMN "post_tax_amount" TYPE NUMERIC(19, 4) USING ROUND("post_tax_amount", 4);
+ALTER TABLE "receipts" ALTER COLUMN "discount" TYPE NUMERIC(19, 4) USING ROUND("discount", 4);
+ALTER TABLE "receipts" ALTER COLUMN "service_charge" TYPE NUMERIC(19, 4) USING ROUND("service_charge", 4);
+ALTER TABLE "receipts" ALTER COLUMN "printed_total" TYPE NUMERIC(19, 4) USING ROUND("printed_total", 4);
+ALTER TABLE "tax_rates" ALTER COLUMN "rate" TYPE NUMERIC(9, 6) USING ROUND("rate", 6);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+-- merged currencies are not split again, the seeded ones without any receipt are removed
+DROP TABLE "currency_aliases";
+DELETE FROM "currencies" c WHERE c."code" IS NOT NULL AND NOT EXISTS (SELECT 1 FROM "receipts" r WHERE r."currency_id" = c."id");
+ALTER TABLE "currencies" DROP COLUMN "display_name";
+ALTER TABLE "currencies" DROP COLUMN "symbol";
+ALTER TABLE "currencies" DROP COLUMN "code";
@@ -0,0 +1,240 @@
+-- Your SQL goes here
+-- a currency typed in by a user has no code, the ISO 4217 ones are seeded below
+ALTER TABLE "currencies" ADD COLUMN "code" TEXT UNIQUE CHECK ("code" ~ '^[A-Z]{3}$');
+ALTER TABLE "currencies" ADD COLUMN "symbol" TEXT;
+ALTER TABLE "currencies" ADD COLUMN "display_name" TEXT;
+
+CREATE TABLE "currency_aliases" (
+  "id" SERIAL PRIMARY KEY,
+  "currency_id" INTEGER NOT NULL REFERENCES "currencies"("id") ON DELETE CASCADE,
+  "alias" TEXT NOT NULL
+);
+CREATE UNIQUE INDEX "currency_aliases_alias_index" ON "currency_aliases" (LOWER("alias"));
+
+CREATE TEMPORARY TABLE "iso_currencies" ("code", "display_name", "symbol", "minor_units") AS VALUES
+    ('AED', 'UAE Dirham', 'د.إ', 2),
+    ('AFN', 'Afghani', '؋', 2),
+    ('ALL', 'Lek', 'L', 2),
+    ('AMD', 'Armenian Dram', '֏', 2),
+    ('ANG', 'Netherlands Antillean Guilder', 'ƒ', 2),
+    ('AOA', 'Kwanza', 'Kz', 2),
+    ('ARS', 'Argentine Peso', '$', 2),
+    ('AUD', 'Australian Dollar', 'A$', 2),
+    ('AWG', 'Aruban Florin', 'ƒ', 2),
+    ('AZN', 'Azerbaijan Manat', '₼', 2),
+    ('BAM', 'Convertible Mark', 'KM', 2),
+    ('BBD', 'Barbados Dollar', '$', 2),
+    ('BDT', 'Taka', '৳', 2),
+    ('BGN', 'Bulgarian Lev', 'лв', 2),
+    ('BHD', 'Bahraini Dinar', '.د.ب', 3),
+    ('BIF', 'Burundi Franc', 'FBu', 0),
+    ('BMD', 'Bermudian Dollar', '$', 2),
+    ('BND', 'Brunei Dollar', '$', 2),
+    ('BOB', 'Boliviano', 'Bs', 2),
+    ('BRL', 'Brazilian Real', 'R$', 2),
+    ('BSD', 'Bahamian Dollar', '$', 2),
+    ('BTN', 'Ngultrum', 'Nu.', 2),
+    ('BWP', 'Pula', 'P', 2),
+    ('BYN', 'Belarusian Ruble', 'Br', 2),
+    ('BZD', 'Belize Dollar', '$', 2),
+    ('CAD', 'Canadian Dollar', 'CA$', 2),
+    ('CDF', 'Congolese Franc', 'FC', 2),
+    ('CHF', 'Swiss Franc', 'CHF', 2),
+    ('CLF', 'Unidad de Fomento', 'UF', 4),
+    ('CLP', 'Chilean Peso', '$', 0),
+    ('CNY', 'Yuan Renminbi', '¥', 2),
+    ('COP', 'Colombian Peso', '$', 2),
+    ('CRC', 'Costa Rican Colon', '₡', 2),
+    ('CUP', 'Cuban Peso', '$', 2),
+    ('CVE', 'Cabo Verde Escudo', '$', 2),
+    ('CZK', 'Czech Koruna', 'Kč', 2),
+    ('DJF', 'Djibouti Franc', 'Fdj', 0),
+    ('DKK', 'Danish Krone', 'kr', 2),
+    ('DOP', 'Dominican Peso', '$', 2),
+    ('DZD', 'Algerian Dinar', 'دج', 2),
+    ('EGP', 'Egyptian Pound', 'E£', 2),
+    ('ERN', 'Nakfa', 'Nfk', 2),
+    ('ETB', 'Ethiopian Birr', 'Br', 2),
+    ('EUR', 'Euro', '€', 2),
+    ('FJD', 'Fiji Dollar', '$', 2),
+    ('FKP', 'Falkland Islands Pound', '£', 2),
+    ('GBP', 'Pound Sterling', '£', 2),
+    ('GEL', 'Lari', '₾', 2),
+    ('GHS', 'Ghana Cedi', '₵', 2),
+    ('GIP', 'Gibraltar Pound', '£', 2),
+    ('GMD', 'Dalasi', 'D', 2),
+    ('GNF', 'Guinean Franc', 'FG', 0),
+    ('GTQ', 'Quetzal', 'Q', 2),
+    ('GYD', 'Guyana Dollar', '$', 2),
+    ('HKD', 'Hong Kong Dollar', 'HK$', 2),
+    ('HNL', 'Lempira', 'L', 2),
+    ('HTG', 'Gourde', 'G', 2),
+    ('HUF', 'Forint', 'Ft', 2),
+    ('IDR', 'Rupiah', 'Rp', 2),
+    ('ILS', 'New Israeli Sheqel', '₪', 2),
+    ('INR', 'Indian Rupee', '₹', 2),
+    ('IQD', 'Iraqi Dinar', 'ع.د', 3),
+    ('IRR', 'Iranian Rial', '﷼', 2),
+    ('ISK', 'Iceland Krona', 'kr', 0),
+    ('JMD', 'Jamaican Dollar', '$', 2),
+    ('JOD', 'Jordanian Dinar', 'د.ا', 3),
+    ('JPY', 'Yen', '¥', 0),
+    ('KES', 'Kenyan Shilling', 'KSh', 2),
+    ('KGS', 'Som', 'с', 2),
+    ('KHR', 'Riel', '៛', 2),
+    ('KMF', 'Comorian Franc', 'CF', 0),
+    ('KPW', 'North Korean Won', '₩', 2),
+    ('KRW', 'Won', '₩', 0),
+    ('KWD', 'Kuwaiti Dinar', 'د.ك', 3),
+    ('KYD', 'Cayman Islands Dollar', '$', 2),
+    ('KZT', 'Tenge', '₸', 2),
+    ('LAK', 'Lao Kip', '₭', 2),
+    ('LBP', 'Lebanese Pound', 'ل.ل', 2),
+    ('LKR', 'Sri Lanka Rupee', 'Rs', 2),
+    ('LRD', 'Liberian Dollar', '$', 2),
+    ('LSL', 'Loti', 'L', 2),
+    ('LYD', 'Libyan Dinar', 'ل.د', 3),
+    ('MAD', 'Moroccan Dirham', 'د.م.', 2),
+    ('MDL', 'Moldovan Leu', 'L', 2),
+    ('MGA', 'Malagasy Ariary', 'Ar', 2),
+    ('MKD', 'Denar', 'ден', 2),
+    ('MMK', 'Kyat', 'K', 2),
+    ('MNT', 'Tugrik', '₮', 2),
+    ('MOP', 'Pataca', 'MOP$', 2),
+    ('MRU', 'Ouguiya', 'UM', 2),
+    ('MUR', 'Mauritius Rupee', '₨', 2),
+    ('MVR', 'Rufiyaa', 'Rf', 2),
+    ('MWK', 'Malawi Kwacha', 'MK', 2),
+    ('MXN', 'Mexican Peso', 'MX$', 2),
+    ('MYR', 'Malaysian Ringgit', 'RM', 2),
+    ('MZN', 'Mozambique Metical', 'MT', 2),
+    ('NAD', 'Namibia Dollar', '$', 2),
+    ('NGN', 'Naira', '₦', 2),
+    ('NIO', 'Cordoba Oro', 'C$', 2),
+    ('NOK', 'Norwegian Krone', 'kr', 2),
+    ('NPR', 'Nepalese Rupee', '₨', 2),
+    ('NZD', 'New Zealand Dollar', 'NZ$', 2),
+    ('OMR', 'Rial Omani', 'ر.ع.', 3),
+    ('PAB', 'Balboa', 'B/.', 2),
+    ('PEN', 'Sol', 'S/', 2),
+    ('PGK', 'Kina', 'K', 2),
+    ('PHP', 'Philippine Peso', '₱', 2),
+    ('PKR', 'Pakistan Rupee', '₨', 2),
+    ('PLN', 'Zloty', 'zł', 2),
+    ('PYG', 'Guarani', '₲', 0),
+    ('QAR', 'Qatari Rial', 'ر.ق', 2),
+    ('RON', 'Romanian Leu', 'lei', 2),
+    ('RSD', 'Serbian Dinar', 'дин.', 2),
+    ('RUB', 'Russian Ruble', '₽', 2),
+    ('RWF', 'Rwanda Franc', 'FRw', 0),
+    ('SAR', 'Saudi Riyal', 'ر.س', 2),
+    ('SBD', 'Solomon Islands Dollar', '$', 2),
+    ('SCR', 'Seychelles Rupee', '₨', 2),
+    ('SDG', 'Sudanese Pound', 'ج.س.', 2),
+    ('SEK', 'Swedish Krona', 'kr', 2),
+    ('SGD', 'Singapore Dollar', 'S$', 2),
+    ('SHP', 'Saint Helena Pound', '£', 2),
+    ('SLE', 'Leone', 'Le', 2),
+    ('SOS', 'Somali Shilling', 'Sh', 2),
+    ('SRD', 'Surinam Dollar', '$', 2),
+    ('SSP', 'South Sudanese Pound', '£', 2),
+    ('STN', 'Dobra', 'Db', 2),
+    ('SVC', 'El Salvador Colon', '₡', 2),
+    ('SYP', 'Syrian Pound', '£', 2),
+    ('SZL', 'Lilangeni', 'E', 2),
+    ('THB', 'Baht', '฿', 2),
+    ('TJS', 'Somoni', 'SM', 2),
+    ('TMT', 'Turkmenistan New Manat', 'm', 2),
+    ('TND', 'Tunisian Dinar', 'د.ت', 3),
+    ('TOP', 'Pa''anga', 'T$', 2),
+    ('TRY', 'Turkish Lira', '₺', 2),
+    ('TTD', 'Trinidad and Tobago Dollar', 'TT$', 2),
+    ('TWD', 'New Taiwan Dollar', 'NT$', 2),
+    ('TZS', 'Tanzanian Shilling', 'TSh', 2),
+    ('UAH', 'Hryvnia', '₴', 2),
+    ('UGX', 'Uganda Shilling', 'USh', 0),
+    ('USD', 'US Dollar', '$', 2),
+    ('UYU', 'Peso Uruguayo', '$U', 2),
+    ('UYW', 'Unidad Previsional', 'UP', 4),
+    ('UZS', 'Uzbekistan Sum', 'so''m', 2),
+    ('VED', 'Bolivar Soberano', 'Bs.D', 2),
+    ('VES', 'Bolivar Soberano', 'Bs.S', 2),
+    ('VND', 'Dong', '₫', 0),
+    ('VUV', 'Vatu', 'VT', 0),
+    ('WST', 'Tala', 'WS$', 2),
+    ('XAF', 'CFA Franc BEAC', 'FCFA', 0),
+    ('XCD', 'East Caribbean Dollar', 'EC$', 2),
+    ('XCG', 'Caribbean Guilder', 'Cg', 2),
+    ('XOF', 'CFA Franc BCEAO', 'CFA', 0),
+    ('XPF', 'CFP Franc', '₣', 0),
+    ('YER', 'Yemeni Rial', '﷼', 2),
+    ('ZAR', 'Rand', 'R', 2),
+    ('ZMW', 'Zambian Kwacha', 'ZK', 2),
+    ('ZWG', 'Zimbabwe Gold', 'ZiG', 2);
+
+-- the oldest currency named after a code becomes that currency, the rest are merged into it below
+UPDATE "currencies" c SET "code" = i."code", "symbol" = i."symbol", "display_name" = i."display_name", "minor_units" = i."minor_units"
+FROM "iso_currencies" i
+WHERE c."id" = (SELECT MIN(o."id") FROM "currencies" o WHERE UPPER(TRIM(o."name")) = i."code");
+
+INSERT INTO "currencies" ("name", "code", "symbol", "display_name", "minor_units")
+SELECT i."code", i."code", i."symbol", i."display_name", i."minor_units"
+FROM "iso_currencies" i
+WHERE NOT EXISTS (SELECT 1 FROM "currencies" c WHERE c."code" = i."code");
+
+INSERT INTO "currency_aliases" ("currency_id", "alias")
+SELECT c."id", a."alias"
+FROM (VALUES
+    ('TWD', 'NTD'),
+    ('TWD', 'NT'),
+    ('TWD', 'NT$'),
+    ('TWD', '新台幣'),
+    ('TWD', '新臺幣'),
+    ('TWD', '台幣'),
+    ('TWD', '臺幣'),
+    ('USD', 'US$'),
+    ('USD', '美元'),
+    ('USD', '美金'),
+    ('JPY', '日圓'),
+    ('JPY', '日元'),
+    ('JPY', '日幣'),
+    ('JPY', '円'),
+    ('CNY', 'RMB'),
+    ('CNY', '人民幣'),
+    ('CNY', '人民币'),
+    ('HKD', 'HK$'),
+    ('HKD', '港幣'),
+    ('HKD', '港币'),
+    ('EUR', '€'),
+    ('EUR', '歐元'),
+    ('EUR', '欧元'),
+    ('GBP', '英鎊'),
+    ('GBP', '英镑'),
+    ('KRW', '韓元'),
+    ('KRW', '韩元'),
+    ('KRW', '원'),
+    ('MOP', '澳門幣'),
+    ('MOP', '澳门币'),
+    ('SGD', '新加坡幣'),
+    ('SGD', '新币'),
+    ('THB', '泰銖'),
+    ('THB', '泰铢'),
+    ('AUD', '澳幣'),
+    ('AUD', '澳元')
+) a ("code", "alias")
+INNER JOIN "currencies" c ON c."code" = a."code";
+
+-- receipts of a currency typed in as a code or a known alias are moved to the ISO currency, so "TWD" and "NTD" are no longer two currencies
+CREATE TEMPORARY TABLE "merged_currencies" AS
+SELECT legacy."id" AS "legacy_id", iso."id" AS "iso_id"
+FROM "currencies" legacy
+INNER JOIN "currencies" iso ON iso."code" IS NOT NULL
+WHERE legacy."code" IS NULL
+    AND (UPPER(TRIM(legacy."name")) = iso."code"
+        OR EXISTS (SELECT 1 FROM "currency_aliases" ca WHERE ca."currency_id" = iso."id" AND LOWER(ca."alias") = LOWER(TRIM(legacy."name"))));
+
+UPDATE "receipts" r SET "currency_id" = m."iso_id" FROM "merged_currencies" m WHERE r."currency_id" = m."legacy_id";
+DELETE FROM "currencies" c USING "merged_currencies" m WHERE c."id" = m."legacy_id";
+
+DROP TABLE "merged_currencies";
+DROP TABLE "iso_currencies";
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP FUNCTION "exchange_rate"(INTEGER, INTEGER, DATE);
+DROP TABLE "user_settings";
+DROP TABLE "exchange_rates";
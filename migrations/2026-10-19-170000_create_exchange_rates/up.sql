@@ -0,0 +1,47 @@
+-- Your SQL goes here
+-- 1 unit of the from currency is worth rate units of the to currency on the rate date, imported rates and settings keep their currencies alive
+CREATE TABLE "exchange_rates" (
+  "id" SERIAL PRIMARY KEY,
+  "rate_date" DATE NOT NULL,
+  "from_currency_id" INTEGER NOT NULL REFERENCES "currencies"("id") ON DELETE RESTRICT,
+  "to_currency_id" INTEGER NOT NULL REFERENCES "currencies"("id") ON DELETE RESTRICT,
+  "rate" NUMERIC(24, 12) NOT NULL CHECK ("rate" > 0),
+  "created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE ("rate_date", "from_currency_id", "to_currency_id"),
+  CHECK ("from_currency_id" <> "to_currency_id")
+);
+SELECT diesel_manage_updated_at('exchange_rates');
+CREATE INDEX "exchange_rates_to_currency_index" ON "exchange_rates" ("to_currency_id", "rate_date");
+
+CREATE TABLE "user_settings" (
+  "username" TEXT PRIMARY KEY,
+  "base_currency_id" INTEGER REFERENCES "currencies"("id") ON DELETE RESTRICT,
+  "version" INT4 NOT NULL DEFAULT 1,
+  "created_at" TIMESTAMP NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+SELECT diesel_manage_updated_at('user_settings');
+
+-- the latest rate on or before the date, taken as quoted, inverted, or crossed through a currency both are quoted against on the same date (the euro of the ECB rates)
+CREATE FUNCTION "exchange_rate"("from_id" INTEGER, "to_id" INTEGER, "effective_date" DATE) RETURNS NUMERIC AS $$
+    SELECT CASE WHEN "from_id" = "to_id" THEN 1 ELSE (
+        SELECT candidates."rate"
+        FROM (
+            SELECT e."rate", e."rate_date", 0 AS "hops"
+            FROM "exchange_rates" e
+            WHERE e."from_currency_id" = "from_id" AND e."to_currency_id" = "to_id" AND e."rate_date" <= "effective_date"
+            UNION ALL
+            SELECT 1 / e."rate", e."rate_date", 0 AS "hops"
+            FROM "exchange_rates" e
+            WHERE e."from_currency_id" = "to_id" AND e."to_currency_id" = "from_id" AND e."rate_date" <= "effective_date"
+            UNION ALL
+            SELECT t."rate" / f."rate", f."rate_date", 1 AS "hops"
+            FROM "exchange_rates" f
+            INNER JOIN "exchange_rates" t ON t."from_currency_id" = f."from_currency_id" AND t."rate_date" = f."rate_date"
+            WHERE f."to_currency_id" = "from_id" AND t."to_currency_id" = "to_id" AND f."rate_date" <= "effective_date"
+        ) candidates
+        ORDER BY candidates."rate_date" DESC, candidates."hops"
+        LIMIT 1
+    ) END
+$$ LANGUAGE SQL STABLE;
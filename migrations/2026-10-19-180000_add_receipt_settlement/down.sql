@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "receipts" DROP CONSTRAINT "receipts_settlement_check";
+ALTER TABLE "receipts" DROP COLUMN "settlement_fee";
+ALTER TABLE "receipts" DROP COLUMN "settled_amount";
+ALTER TABLE "receipts" DROP COLUMN "settlement_currency_id";
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- what the card was actually charged for a receipt paid in a foreign currency, the fee is charged on top of the settled amount
+ALTER TABLE "receipts" ADD COLUMN "settlement_currency_id" INTEGER REFERENCES "currencies"("id");
+ALTER TABLE "receipts" ADD COLUMN "settled_amount" NUMERIC(19, 4) CHECK ("settled_amount" >= 0);
+ALTER TABLE "receipts" ADD COLUMN "settlement_fee" NUMERIC(19, 4) CHECK ("settlement_fee" >= 0);
+ALTER TABLE "receipts" ADD CONSTRAINT "receipts_settlement_check" CHECK (
+  ("settlement_currency_id" IS NULL AND "settled_amount" IS NULL AND "settlement_fee" IS NULL)
+  OR ("settlement_currency_id" IS NOT NULL AND "settled_amount" IS NOT NULL AND "settlement_fee" IS NOT NULL)
+);
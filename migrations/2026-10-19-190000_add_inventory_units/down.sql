@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "inventories" DROP COLUMN "line_price";
+ALTER TABLE "inventories" DROP COLUMN "unit";
+ALTER TABLE "inventories" ALTER COLUMN "quantity" TYPE INT4 USING CEIL("quantity")::INT4;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- 0.45 kg of bananas or 1.5 L of fuel, the existing whole quantities are pieces
+ALTER TABLE "inventories" ALTER COLUMN "quantity" TYPE NUMERIC(12, 3) USING "quantity"::NUMERIC(12, 3);
+ALTER TABLE "inventories" ADD COLUMN "unit" TEXT NOT NULL DEFAULT 'piece' CHECK ("unit" IN ('piece', 'kg', 'g', 'L', 'mL'));
+-- price is the unit price, the line price is the amount printed for the line before its discount
+ALTER TABLE "inventories" ADD COLUMN "line_price" NUMERIC(19, 4);
+UPDATE "inventories" SET "line_price" = "price" * "quantity";
+ALTER TABLE "inventories" ALTER COLUMN "line_price" SET NOT NULL;
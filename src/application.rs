@@ -3,7 +3,7 @@ use std::str::FromStr;
 
 use axum_server::tls_rustls::RustlsConfig;
 use http::{HeaderValue, Method};
-use http::header::{ACCEPT, CONTENT_TYPE, COOKIE};
+use http::header::{ACCEPT, CONTENT_TYPE, COOKIE, ETAG, IF_MATCH, LINK};
 use tower_http::cors::CorsLayer;
 
 use crate::router::AppRouter;
@@ -29,9 +29,9 @@ impl Application {
         let allow_origin_header_values = allow_origins.into_iter().map(|o| { o.parse::<HeaderValue>().unwrap() }).collect::<Vec<HeaderValue>>();
         let cors = 
             CorsLayer::new()
-            .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE, Method::OPTIONS])
-            .expose_headers([CONTENT_TYPE])
-            .allow_headers([CONTENT_TYPE, ACCEPT, COOKIE])
+            .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::PUT, Method::DELETE, Method::OPTIONS])
+            .expose_headers([CONTENT_TYPE, ETAG, LINK])
+            .allow_headers([CONTENT_TYPE, ACCEPT, COOKIE, IF_MATCH])
             .allow_credentials(true)
             .allow_origin(allow_origin_header_values);
 
@@ -1,4 +1,5 @@
-use std::{env, sync::OnceLock};
+use std::{env, str::FromStr, sync::OnceLock, time::Duration};
+use chrono_tz::Tz;
 use dotenvy::dotenv;
 use crate::error::Error;
 
@@ -24,7 +25,10 @@ pub struct AppConfig {
     allow_origins: Vec<String>,
     tls_pem_folder_name: String,
     tls_cert_name: String,
-    tls_key_name: String
+    tls_key_name: String,
+    trash_retention_days: i64,
+    trash_purge_interval_seconds: u64,
+    default_time_zone: Tz
 }
 
 impl AppConfig {
@@ -42,7 +46,12 @@ impl AppConfig {
             allow_origins: (|| {get_env("ALLOW_ORIGINS").unwrap().split(",").map(|o| { o.to_string() }).collect::<Vec<String>>() } )(),
             tls_pem_folder_name: get_env("TLS_PEM_FILES_FOLDER")?,
             tls_cert_name: get_env("TLS_CERT_FILE_NAME")?,
-            tls_key_name: get_env("TLS_KEY_FILE_NAME")?
+            tls_key_name: get_env("TLS_KEY_FILE_NAME")?,
+            trash_retention_days: parse_env("TRASH_RETENTION_DAYS", get_env_or("TRASH_RETENTION_DAYS", "30"))?,
+            trash_purge_interval_seconds: parse_env("TRASH_PURGE_INTERVAL_SECONDS", get_env_or("TRASH_PURGE_INTERVAL_SECONDS", "3600"))
+                // a zero interval would make the purge ticker panic
+                .and_then(|seconds: u64| if seconds < 1 { Err(Error::ConfigInvalidEnv("TRASH_PURGE_INTERVAL_SECONDS", seconds.to_string())) } else { Ok(seconds) })?,
+            default_time_zone: parse_env("DEFAULT_TIME_ZONE", get_env_or("DEFAULT_TIME_ZONE", "UTC"))?
         })
     }
 
@@ -89,8 +98,28 @@ impl AppConfig {
     pub fn get_tls_key_name(&self) -> &str {
         self.tls_key_name.as_ref()
     }
+
+    pub fn get_trash_retention_days(&self) -> i64 {
+        self.trash_retention_days
+    }
+
+    pub fn get_trash_purge_interval(&self) -> Duration {
+        Duration::from_secs(self.trash_purge_interval_seconds)
+    }
+
+    pub fn get_default_time_zone(&self) -> Tz {
+        self.default_time_zone
+    }
 }
 
 fn get_env(name: &'static str) -> Result<String, Error> {
     env::var(name).map_err(|_| Error::ConfigMissingEnv(name))
+}
+
+fn get_env_or(name: &'static str, default: &str) -> String {
+    env::var(name).unwrap_or_else(|_| default.to_string())
+}
+
+fn parse_env<T: FromStr>(name: &'static str, value: String) -> Result<T, Error> {
+    value.parse().map_err(|_| Error::ConfigInvalidEnv(name, value))
 }
\ No newline at end of file
@@ -8,6 +8,7 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[derive(Debug, PartialEq)]
 pub enum Error {
     ConfigMissingEnv(&'static str),
+    ConfigInvalidEnv(&'static str, String),
     LoginFailed,
     AuthFailNoAuthTokenCookie
 }
@@ -1,22 +1,28 @@
-use axum::{extract::{rejection::{JsonRejection, PathRejection}, Path, Query, State}, http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::{rejection::{JsonRejection, PathRejection, QueryRejection}, OriginalUri, Path, Query, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Json};
 
-use crate::{models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::patch_payload::PatchCurrencyPayload, parameters::{pagination::Pagination, query_filters::KeywordFilters}, responses::response_currency::{ResponseCurrenciesPayload, ResponseCurrencyPayload}}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, currencies::currencies_service::CurrencyService}, share_state::HandlerState};
+use crate::{models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::patch_payload::PatchCurrencyPayload, parameters::{field_selection::FieldSelection, listing::Listing, query_filters::{IdFilters, KeywordFilters}}, responses::{response_collection::ResponseCollectionPayload, response_currency::ResponseCurrencyPayload}}, services::v1::{batches::batches_service::BatchService, converters::api_error_converter_service::ApiErrorConventerService, fields::fields_service::FieldService, links::links_service::LinkService, preconditions::preconditions_service::PreconditionService, currencies::currencies_service::CurrencyService}, share_state::HandlerState};
 
 pub struct  CurrenciesHandlers {
 }
 
 impl CurrenciesHandlers {
-    pub async fn get_currency(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+    pub async fn get_currency(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, field_selection: Option<Query<FieldSelection>>) -> impl IntoResponse {
+        let field_selection = field_selection.unwrap_or_default().0;
+        let field_service = FieldService::new();
         let service = CurrencyService::new(&handler_state.repository);
         if let Ok(c_id) = id {
-            let response_currency = service.get_currency(c_id.0 as i32).await;
+            let response_currency = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_currency(c_id.0 as i32).await.map(|r| (r.version, field_service.select_fields(&r, &fields))),
+                Err(e) => Err(e)
+            };
             match response_currency {
-                Ok(response) => {
+                Ok((version, response)) => {
+                    let headers = PreconditionService::new().get_etag_headers(version);
                     let payload = ResponseCurrencyPayload {
                         data: Some(response),
                         error: None
                     };
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
@@ -26,7 +32,7 @@ impl CurrenciesHandlers {
                         data: None,
                         error: Some(e)
                     };
-                    (http_status_code, Json(payload))
+                    (http_status_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
@@ -36,46 +42,85 @@ impl CurrenciesHandlers {
                 error: Some(ApiError::InvalidParameter)
             };
 
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
-    pub async fn get_currencies(State(handler_state): State<HandlerState>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_currencies(State(handler_state): State<HandlerState>, OriginalUri(uri): OriginalUri, listing: Listing, id_filters: Result<Query<IdFilters>, QueryRejection>) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
+        let fields = field_service.get_fields(&field_selection);
         let service = CurrencyService::new(&handler_state.repository);
-        let currencies_collection = service.get_currencies(&pagination.unwrap_or_default().0).await;
+        let batch_service = BatchService::new();
+        if let Some(ids) = batch_service.get_requested_ids(id_filters) {
+            let batch = match (ids, fields) {
+                (Ok(ids), Ok(fields)) => service.get_currencies_by_ids(&ids).await.map(|(responses, not_found)| {
+                    (responses.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>(), not_found)
+                }),
+                (Err(e), _) | (_, Err(e)) => Err(e)
+            };
+            return batch_service.get_batch_response(batch);
+        }
+        let currencies_collection = match fields {
+            Ok(fields) => service.get_currencies(&pagination, &timestamp_filters, &sorting).await.map(|responses| (responses, fields)),
+            Err(e) => Err(e)
+        };
         match currencies_collection {
-            Ok(responses) => {
-                let payload = ResponseCurrenciesPayload {
-                    data: Some(responses.partial_collection),
-                    total: Some(responses.total_count),
+            Ok((responses, fields)) => {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let payload = ResponseCollectionPayload {
+                    data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
                     error: None
                 };
-                (StatusCode::OK, Json(payload))
+                (StatusCode::OK, headers, Json(payload))
             },
             Err(e) => {
                 let api_error_converter_service = ApiErrorConventerService::new();
                 let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                let payload = ResponseCurrenciesPayload {
+                let payload = ResponseCollectionPayload {
                     data: None,
                     total: None,
+                    pagination: None,
+                    not_found: None,
                     error: Some(e)
                 };
-                (http_return_code, Json(payload))
+                (http_return_code, HeaderMap::new(), Json(payload))
             }
         }
     }
 
-    pub async fn patch_currency(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchCurrencyPayload>, JsonRejection>) -> impl IntoResponse {
+    pub async fn patch_currency(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchCurrencyPayload>, JsonRejection>) -> impl IntoResponse {
         if id.is_ok() && payload.is_ok() {
             let c_id = id.expect("id should be ok after we have checked").0;
             let c_payload = payload.expect("payload should be ok after we have checked").0;
-            let patch_command = WriterCommand::PatchCurrency(c_id as i32, c_payload);
-            let _ = handler_state.sender.send(patch_command).await;
-            let response = ResponseCurrencyPayload {
-                data: None,
-                error: None
-            };
-            (StatusCode::ACCEPTED, Json(response))
+            let service = CurrencyService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_currency_version(c_id as i32).await;
+            match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => {
+                    let patch_command = WriterCommand::PatchCurrency(c_id as i32, c_payload, expected_version);
+                    let _ = handler_state.sender.send(patch_command).await;
+                    let response = ResponseCurrencyPayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseCurrencyPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
         }
         else {
             let payload = ResponseCurrencyPayload {
@@ -89,19 +134,15 @@ impl CurrenciesHandlers {
 
     pub async fn autocomplete_currencies(State(handler_state): State<HandlerState>, kw: Option<Query<KeywordFilters>>) -> impl IntoResponse {
         let service = CurrencyService::new(&handler_state.repository);
-        let currencies_collection;
-        if let Some(keyword) = kw {
-            currencies_collection = service.autocomplete_currencies(&keyword.0.keyword).await;
-        }
-        else {
-            currencies_collection = service.autocomplete_currencies(&None::<String>).await;
-        }
+        let currencies_collection = service.autocomplete_currencies(&kw.unwrap_or_default().0).await;
 
         match currencies_collection {
             Ok(responses) => {
-                let payload = ResponseCurrenciesPayload {
+                let payload = ResponseCollectionPayload {
                     data: Some(responses.partial_collection),
-                    total: Some(responses.total_count),
+                    total: responses.total_count,
+                    pagination: None,
+                    not_found: None,
                     error: None
                 };
                 (StatusCode::OK, Json(payload))
@@ -109,9 +150,11 @@ impl CurrenciesHandlers {
             Err(e) => {
                 let api_error_converter_service = ApiErrorConventerService::new();
                 let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                let payload = ResponseCurrenciesPayload {
+                let payload = ResponseCollectionPayload {
                     data: None,
                     total: None,
+                    pagination: None,
+                    not_found: None,
                     error: Some(e)
                 };
                 (http_return_code, Json(payload))
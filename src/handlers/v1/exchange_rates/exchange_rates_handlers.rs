@@ -0,0 +1,81 @@
+use axum::{extract::{rejection::StringRejection, OriginalUri, Query, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Json};
+
+use crate::{models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, parameters::{pagination::Pagination, query_filters::{ExchangeRateFilters, ExchangeRateImportFilters}}, responses::{response_collection::ResponseCollectionPayload, response_exchange_rate::{ResponseExchangeRateImport, ResponseExchangeRateImportPayload}}}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, exchange_rates::exchange_rates_service::ExchangeRateService, links::links_service::LinkService}, share_state::HandlerState};
+
+pub struct ExchangeRatesHandlers {
+}
+
+impl ExchangeRatesHandlers {
+    pub async fn get_exchange_rates(State(handler_state): State<HandlerState>, OriginalUri(uri): OriginalUri, pagination: Option<Query<Pagination>>, exchange_rate_filters: Option<Query<ExchangeRateFilters>>) -> impl IntoResponse {
+        let pagination = pagination.unwrap_or_default().0;
+        let service = ExchangeRateService::new(&handler_state.repository);
+        let exchange_rates_collection = service.get_exchange_rates(&pagination, &exchange_rate_filters.unwrap_or_default().0).await;
+        match exchange_rates_collection {
+            Ok(responses) => {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let payload = ResponseCollectionPayload {
+                    data: Some(responses.partial_collection),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
+                    error: None
+                };
+                (StatusCode::OK, headers, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, HeaderMap::new(), Json(payload))
+            }
+        }
+    }
+
+    // the body is the content of a CSV or ECB XML file
+    pub async fn import_exchange_rates(State(handler_state): State<HandlerState>, import_filters: Option<Query<ExchangeRateImportFilters>>, file: Result<String, StringRejection>) -> impl IntoResponse {
+        if let Ok(file) = file {
+            let service = ExchangeRateService::new(&handler_state.repository);
+            match service.parse_exchange_rates(&file, &import_filters.unwrap_or_default().0).await {
+                Ok((new_exchange_rates, skipped)) => {
+                    let accepted = new_exchange_rates.len();
+                    if accepted > 0 {
+                        let import_command = WriterCommand::ImportExchangeRates(new_exchange_rates);
+                        let _ = handler_state.sender.send(import_command).await;
+                    }
+                    let response = ResponseExchangeRateImportPayload {
+                        data: Some(ResponseExchangeRateImport {
+                            accepted,
+                            skipped
+                        }),
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseExchangeRateImportPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseExchangeRateImportPayload {
+                data: None,
+                error: Some(ApiError::InvalidExchangeRateFile)
+            };
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod exchange_rates_handlers;
\ No newline at end of file
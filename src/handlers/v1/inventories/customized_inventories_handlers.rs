@@ -1,25 +1,31 @@
-use axum::{extract::{rejection::PathRejection, Path, Query, State}, http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::{rejection::{PathRejection, QueryRejection}, OriginalUri, Path, Query, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Json};
 
 use crate::{
-    models::v1::{errors::api_error::ApiError, parameters::{pagination::Pagination, query_filters::QueryFilters}, responses::response_inventory::{ResponseCustomizedInventoriesPayload, ResponseCustomizedInventoryPayload}}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, inventories::customized_inventories_service::CustomizedInventoryService}, share_state::HandlerState
+    models::v1::{errors::api_error::ApiError, parameters::{conversion::Conversion, field_selection::FieldSelection, listing::Listing, query_filters::QueryFilters}, responses::{response_collection::ResponseCollectionPayload, response_inventory::ResponseCustomizedInventoryPayload}}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, fields::fields_service::FieldService, links::links_service::LinkService, preconditions::preconditions_service::PreconditionService, inventories::customized_inventories_service::CustomizedInventoryService}, share_state::HandlerState
 };
 
 pub struct CustomizedInventoriesHandlers {
 }
 
 impl CustomizedInventoriesHandlers {
-    pub async fn get_customized_inventory(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+    pub async fn get_customized_inventory(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, field_selection: Option<Query<FieldSelection>>, conversion: Conversion) -> impl IntoResponse {
+        let field_selection = field_selection.unwrap_or_default().0;
+        let field_service = FieldService::new();
         let service = CustomizedInventoryService::new(&handler_state.repository);
         if let Ok(i_id) = id {
-            let response_inventory = service.get_customized_inventory(i_id.0 as i32).await;
+            let response_inventory = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_customized_inventory(i_id.0 as i32, &conversion).await.map(|r| (r.version, field_service.select_fields(&r, &fields))),
+                Err(e) => Err(e)
+            };
             match response_inventory {
-                Ok(response) => {
+                Ok((version, response)) => {
+                    let headers = PreconditionService::new().get_etag_headers(version);
                     let payload = ResponseCustomizedInventoryPayload {
                         data: Some(response),
                         error: None
                     };
             
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
@@ -29,7 +35,7 @@ impl CustomizedInventoriesHandlers {
                         data: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
@@ -38,172 +44,241 @@ impl CustomizedInventoriesHandlers {
                 data: None,
                 error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
-    pub async fn get_customized_inventories(State(handler_state): State<HandlerState>, pagination: Option<Query<Pagination>>, query_filters: Option<Query<QueryFilters>>) -> impl IntoResponse {
+    pub async fn get_customized_inventories(State(handler_state): State<HandlerState>, conversion: Conversion, OriginalUri(uri): OriginalUri, listing: Listing, query_filters: Result<Query<QueryFilters>, QueryRejection>) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
         let service = CustomizedInventoryService::new(&handler_state.repository);
-        let inventories_collection = service.get_customized_inventories(&pagination.unwrap_or_default().0, &query_filters.unwrap_or_default().0).await;
+        let inventories_collection = match (query_filters, field_service.get_fields(&field_selection)) {
+            (Ok(query_filters), Ok(fields)) => service.get_customized_inventories(&pagination, &query_filters.0, &timestamp_filters, &sorting, &conversion).await.map(|responses| (responses, fields)),
+            (Err(_), _) => Err(ApiError::InvalidParameter),
+            (_, Err(e)) => Err(e)
+        };
         match inventories_collection {
-            Ok(responses) => {
-                let payload = ResponseCustomizedInventoriesPayload {
-                    data: Some(responses.partial_collection),
-                    total: Some(responses.total_count),
+            Ok((responses, fields)) => {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let payload = ResponseCollectionPayload {
+                    data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
                     error: None
                 };
-                (StatusCode::OK, Json(payload))
+                (StatusCode::OK, headers, Json(payload))
             },
             Err(e) => {
                 let api_error_converter_service = ApiErrorConventerService::new();
                 let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                let payload = ResponseCustomizedInventoriesPayload {
+                let payload = ResponseCollectionPayload {
                     data: None,
                     total: None,
+                    pagination: None,
+                    not_found: None,
                     error: Some(e)
                 };
-                (http_return_code, Json(payload))
+                (http_return_code, HeaderMap::new(), Json(payload))
             }
         }
     }
 
-    pub async fn get_customized_inventories_by_product_id(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_customized_inventories_by_product_id(State(handler_state): State<HandlerState>, conversion: Conversion, OriginalUri(uri): OriginalUri, id: Result<Path<u32>, PathRejection>, listing: Listing, query_filters: Result<Query<QueryFilters>, QueryRejection>) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
         let service = CustomizedInventoryService::new(&handler_state.repository);
-        if let Ok(p_id) = id {
-            let inventories_collection = service.get_customized_inventories_by_product_id(p_id.0 as i32, &pagination.unwrap_or_default().0).await;
+        if let (Ok(p_id), Ok(query_filters)) = (id, query_filters) {
+            let inventories_collection = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_customized_inventories_by_product_id(p_id.0 as i32, &pagination, &query_filters.0, &timestamp_filters, &sorting, &conversion).await.map(|responses| (responses, fields)),
+                Err(e) => Err(e)
+            };
             match inventories_collection {
-                Ok(responses) => {
-                    let payload = ResponseCustomizedInventoriesPayload {
-                        data: Some(responses.partial_collection),
-                        total: Some(responses.total_count),
+                Ok((responses, fields)) => {
+                    let link_service = LinkService::new();
+                    let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                    let headers = link_service.get_link_headers(&response_pagination);
+                    let payload = ResponseCollectionPayload {
+                        data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                        total: responses.total_count,
+                        pagination: Some(response_pagination),
+                        not_found: None,
                         error: None
                     };
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
                     let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                    let payload = ResponseCustomizedInventoriesPayload {
+                    let payload = ResponseCollectionPayload {
                         data: None,
                         total: None,
+                        pagination: None,
+                        not_found: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
         else {
-            let payload = ResponseCustomizedInventoriesPayload {
+            let payload = ResponseCollectionPayload {
                 data: None,
                 total: None,
+                pagination: None,
+                not_found: None,
                 error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
-    pub async fn get_customized_inventories_by_receipt_id(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_customized_inventories_by_receipt_id(State(handler_state): State<HandlerState>, conversion: Conversion, OriginalUri(uri): OriginalUri, id: Result<Path<u32>, PathRejection>, listing: Listing, query_filters: Result<Query<QueryFilters>, QueryRejection>) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
         let service = CustomizedInventoryService::new(&handler_state.repository);
-        if let Ok(r_id) = id {
-            let inventories_collection = service.get_customized_inventories_by_receipt_id(r_id.0 as i32, &pagination.unwrap_or_default().0).await;
+        if let (Ok(r_id), Ok(query_filters)) = (id, query_filters) {
+            let inventories_collection = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_customized_inventories_by_receipt_id(r_id.0 as i32, &pagination, &query_filters.0, &timestamp_filters, &sorting, &conversion).await.map(|responses| (responses, fields)),
+                Err(e) => Err(e)
+            };
             match inventories_collection {
-                Ok(responses) => {
-                    let payload = ResponseCustomizedInventoriesPayload {
-                        data: Some(responses.partial_collection),
-                        total: Some(responses.total_count),
+                Ok((responses, fields)) => {
+                    let link_service = LinkService::new();
+                    let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                    let headers = link_service.get_link_headers(&response_pagination);
+                    let payload = ResponseCollectionPayload {
+                        data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                        total: responses.total_count,
+                        pagination: Some(response_pagination),
+                        not_found: None,
                         error: None
                     };
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
                     let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                    let payload = ResponseCustomizedInventoriesPayload {
+                    let payload = ResponseCollectionPayload {
                         data: None,
                         total: None,
+                        pagination: None,
+                        not_found: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
         else {
-            let payload = ResponseCustomizedInventoriesPayload {
+            let payload = ResponseCollectionPayload {
                 data: None,
                 total: None,
+                pagination: None,
+                not_found: None,
                 error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
-    pub async fn get_customized_inventories_by_store_id(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_customized_inventories_by_store_id(State(handler_state): State<HandlerState>, conversion: Conversion, OriginalUri(uri): OriginalUri, id: Result<Path<u32>, PathRejection>, listing: Listing, query_filters: Result<Query<QueryFilters>, QueryRejection>) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
         let service = CustomizedInventoryService::new(&handler_state.repository);
-        if let Ok(s_id) = id {
-            let inventories_collection = service.get_customized_inventories_by_store_id(s_id.0 as i32, &pagination.unwrap_or_default().0).await;
+        if let (Ok(s_id), Ok(query_filters)) = (id, query_filters) {
+            let inventories_collection = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_customized_inventories_by_store_id(s_id.0 as i32, &pagination, &query_filters.0, &timestamp_filters, &sorting, &conversion).await.map(|responses| (responses, fields)),
+                Err(e) => Err(e)
+            };
             match inventories_collection {
-                Ok(responses) => {
-                    let payload = ResponseCustomizedInventoriesPayload {
-                        data: Some(responses.partial_collection),
-                        total: Some(responses.total_count),
+                Ok((responses, fields)) => {
+                    let link_service = LinkService::new();
+                    let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                    let headers = link_service.get_link_headers(&response_pagination);
+                    let payload = ResponseCollectionPayload {
+                        data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                        total: responses.total_count,
+                        pagination: Some(response_pagination),
+                        not_found: None,
                         error: None
                     };
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
                     let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                    let payload = ResponseCustomizedInventoriesPayload {
+                    let payload = ResponseCollectionPayload {
                         data: None,
                         total: None,
+                        pagination: None,
+                        not_found: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
         else {
-            let payload = ResponseCustomizedInventoriesPayload {
+            let payload = ResponseCollectionPayload {
                 data: None,
                 total: None,
+                pagination: None,
+                not_found: None,
                 error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
-    pub async fn get_customized_inventories_by_currency_id(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_customized_inventories_by_currency_id(State(handler_state): State<HandlerState>, conversion: Conversion, OriginalUri(uri): OriginalUri, id: Result<Path<u32>, PathRejection>, listing: Listing, query_filters: Result<Query<QueryFilters>, QueryRejection>) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
         let service = CustomizedInventoryService::new(&handler_state.repository);
-        if let Ok(c_id) = id {
-            let inventories_collection = service.get_customized_inventories_by_currency_id(c_id.0 as i32, &pagination.unwrap_or_default().0).await;
+        if let (Ok(c_id), Ok(query_filters)) = (id, query_filters) {
+            let inventories_collection = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_customized_inventories_by_currency_id(c_id.0 as i32, &pagination, &query_filters.0, &timestamp_filters, &sorting, &conversion).await.map(|responses| (responses, fields)),
+                Err(e) => Err(e)
+            };
             match inventories_collection {
-                Ok(responses) => {
-                    let payload = ResponseCustomizedInventoriesPayload {
-                        data: Some(responses.partial_collection),
-                        total: Some(responses.total_count),
+                Ok((responses, fields)) => {
+                    let link_service = LinkService::new();
+                    let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                    let headers = link_service.get_link_headers(&response_pagination);
+                    let payload = ResponseCollectionPayload {
+                        data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                        total: responses.total_count,
+                        pagination: Some(response_pagination),
+                        not_found: None,
                         error: None
                     };
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
                     let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                    let payload = ResponseCustomizedInventoriesPayload {
+                    let payload = ResponseCollectionPayload {
                         data: None,
                         total: None,
+                        pagination: None,
+                        not_found: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
         else {
-            let payload = ResponseCustomizedInventoriesPayload {
+            let payload = ResponseCollectionPayload {
                 data: None,
                 total: None,
+                pagination: None,
+                not_found: None,
                 error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 }
\ No newline at end of file
@@ -1,25 +1,31 @@
-use axum::{extract::{rejection::{JsonRejection, PathRejection}, Path, Query, State}, http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::{rejection::{JsonRejection, PathRejection, QueryRejection}, OriginalUri, Path, Query, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Json};
 
 use crate::{
-    models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::patch_payload::PatchInventoryPayload, parameters::pagination::Pagination, responses::response_inventory::{ResponseInventoriesPayload, ResponseInventoryPayload}}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, inventories::inventories_service::InventoryService}, share_state::HandlerState
+    models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::patch_payload::PatchInventoryPayload, parameters::{conversion::Conversion, field_selection::FieldSelection, listing::Listing, query_filters::IdFilters}, responses::{response_collection::ResponseCollectionPayload, response_inventory::ResponseInventoryPayload}}, services::v1::{batches::batches_service::BatchService, converters::api_error_converter_service::ApiErrorConventerService, fields::fields_service::FieldService, links::links_service::LinkService, preconditions::preconditions_service::PreconditionService, inventories::inventories_service::InventoryService}, share_state::HandlerState
 };
 
 pub struct InventoriesHandlers {
 }
 
 impl InventoriesHandlers {
-    pub async fn get_inventory(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+    pub async fn get_inventory(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, field_selection: Option<Query<FieldSelection>>, conversion: Conversion) -> impl IntoResponse {
+        let field_selection = field_selection.unwrap_or_default().0;
+        let field_service = FieldService::new();
         let service = InventoryService::new(&handler_state.repository);
         if let Ok(i_id) = id {
-            let response_inventory = service.get_inventory(i_id.0 as i32).await;
+            let response_inventory = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_inventory(i_id.0 as i32, &conversion).await.map(|r| (r.version, field_service.select_fields(&r, &fields))),
+                Err(e) => Err(e)
+            };
             match response_inventory {
-                Ok(response) => {
+                Ok((version, response)) => {
+                    let headers = PreconditionService::new().get_etag_headers(version);
                     let payload = ResponseInventoryPayload {
                         data: Some(response),
                         error: None
                     };
             
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
@@ -29,7 +35,7 @@ impl InventoriesHandlers {
                         data: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
@@ -38,46 +44,89 @@ impl InventoriesHandlers {
                 data: None,
                 error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
-    pub async fn get_inventories(State(handler_state): State<HandlerState>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_inventories(State(handler_state): State<HandlerState>, OriginalUri(uri): OriginalUri, listing: Listing, id_filters: Result<Query<IdFilters>, QueryRejection>, conversion: Conversion) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
+        let fields = field_service.get_fields(&field_selection);
         let service = InventoryService::new(&handler_state.repository);
-        let inventory_collection = service.get_inventories(&pagination.unwrap_or_default().0).await;
+        let batch_service = BatchService::new();
+        if let Some(ids) = batch_service.get_requested_ids(id_filters) {
+            let batch = match (ids, fields) {
+                (Ok(ids), Ok(fields)) => service.get_inventories_by_ids(&ids, &conversion).await.map(|(responses, not_found)| {
+                    (responses.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>(), not_found)
+                }),
+                (Err(e), _) | (_, Err(e)) => Err(e)
+            };
+            return batch_service.get_batch_response(batch);
+        }
+        let inventory_collection = match fields {
+            Ok(fields) => service.get_inventories(&pagination, &timestamp_filters, &sorting, &conversion).await.map(|responses| (responses, fields)),
+            Err(e) => Err(e)
+        };
         match inventory_collection {
-            Ok(responses) => {
-                let payload = ResponseInventoriesPayload {
-                    data: Some(responses.partial_collection),
-                    total: Some(responses.total_count),
+            Ok((responses, fields)) => {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let payload = ResponseCollectionPayload {
+                    data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
                     error: None
                 };
-                (StatusCode::OK, Json(payload))
+                (StatusCode::OK, headers, Json(payload))
             },
             Err(e) => {
                 let api_error_converter_service = ApiErrorConventerService::new();
                 let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                let payload = ResponseInventoriesPayload {
+                let payload = ResponseCollectionPayload {
                     data: None,
                     total: None,
+                    pagination: None,
+                    not_found: None,
                     error: Some(e)
                 };
-                (http_return_code, Json(payload))
+                (http_return_code, HeaderMap::new(), Json(payload))
             }
         }
     }
 
-    pub async fn patch_inventory(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchInventoryPayload>, JsonRejection>) -> impl IntoResponse {
+    pub async fn patch_inventory(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchInventoryPayload>, JsonRejection>) -> impl IntoResponse {
         if id.is_ok() && payload.is_ok() {
             let i_id = id.expect("id should be ok after we have checked").0;
             let i_payload = payload.expect("payload should be ok after we have checked").0;
-            let patch_command = WriterCommand::PatchInventory(i_id as i32, i_payload);
-            let _ = handler_state.sender.send(patch_command).await;
-            let response = ResponseInventoryPayload {
-                data: None,
-                error: None
+            let service = InventoryService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_inventory_version(i_id as i32).await;
+            let expected_version = match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => service.validate_patch_inventory(i_id as i32, &i_payload).await.map(|_| expected_version),
+                Err(e) => Err(e)
             };
-            (StatusCode::ACCEPTED, Json(response))
+            match expected_version {
+                Ok(expected_version) => {
+                    let patch_command = WriterCommand::PatchInventory(i_id as i32, i_payload, expected_version);
+                    let _ = handler_state.sender.send(patch_command).await;
+                    let response = ResponseInventoryPayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseInventoryPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
         }
         else {
             let payload = ResponseInventoryPayload {
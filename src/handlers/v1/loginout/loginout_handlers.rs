@@ -5,6 +5,7 @@ use tower_cookies::cookie::time::Duration;
 use tower_cookies::cookie::SameSite;
 use tower_cookies::{Cookie, Cookies};
 use crate::error::Error;
+use crate::mw_auth::LOGIN_USERNAME;
 use crate::models::v1::loginout::login_payload::{LoginPayload, LoginResponse};
 use crate::share_state::HandlerState;
 
@@ -17,7 +18,7 @@ pub struct LoginoutHandlers {
 impl LoginoutHandlers {
     pub async fn api_login(cookies: Cookies, State(_handler_state): State<HandlerState>, payload: Json<LoginPayload>) -> Result<Json<LoginResponse>, Error>{
         // TODO: authenciate with username/password in DB later
-        if payload.0.username != "test1" || payload.0.pwd != "hello" {
+        if payload.0.username != LOGIN_USERNAME || payload.0.pwd != "hello" {
             return Err(Error::LoginFailed);
         }
 
@@ -4,3 +4,9 @@ pub mod currencies;
 pub mod products;
 pub mod inventories;
 pub mod loginout;
+pub mod search;
+
+pub mod tax_rates;
+pub mod reports;
+pub mod exchange_rates;
+pub mod user_settings;
\ No newline at end of file
@@ -1,23 +1,29 @@
-use axum::{extract::{rejection::{JsonRejection, PathRejection}, Path, Query, State}, http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::{rejection::{JsonRejection, PathRejection, QueryRejection}, OriginalUri, Path, Query, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Json};
 
-use crate::{models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::patch_payload::PatchProductPayload, parameters::{pagination::Pagination, query_filters::KeywordFilters}, responses::response_product::{ResponseProductPayload, ResponseProductsPayload}}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, products::products_service::ProductService}, share_state::HandlerState};
+use crate::{models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::patch_payload::PatchProductPayload, parameters::{conversion::Conversion, field_selection::FieldSelection, listing::Listing, query_filters::{IdFilters, KeywordFilters, ReportFilters}}, responses::{response_collection::ResponseCollectionPayload, response_product::ResponseProductPayload, response_report::ResponsePriceHistory}}, services::v1::{batches::batches_service::BatchService, converters::api_error_converter_service::ApiErrorConventerService, fields::fields_service::FieldService, links::links_service::LinkService, preconditions::preconditions_service::PreconditionService, products::products_service::ProductService, reports::reports_service::ReportService}, share_state::HandlerState};
 
 
 pub struct ProductsHandlers {   
 }
 
 impl ProductsHandlers {
-    pub async fn get_product(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+    pub async fn get_product(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, field_selection: Option<Query<FieldSelection>>) -> impl IntoResponse {
+        let field_selection = field_selection.unwrap_or_default().0;
+        let field_service = FieldService::new();
         let service = ProductService::new(&handler_state.repository);
         if let Ok(s_id) = id {
-            let response_product = service.get_product(s_id.0 as i32).await;
+            let response_product = match field_service.get_fields(&field_selection) {
+                Ok(fields) => service.get_product(s_id.0 as i32).await.map(|r| (r.version, field_service.select_fields(&r, &fields))),
+                Err(e) => Err(e)
+            };
             match response_product {
-                Ok(response) => {
+                Ok((version, response)) => {
+                    let headers = PreconditionService::new().get_etag_headers(version);
                     let payload: ResponseProductPayload = ResponseProductPayload {
                         data: Some(response),
                         error: None
                     };
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
@@ -27,7 +33,7 @@ impl ProductsHandlers {
                         data: None,
                         error: Some(e)
                     };
-                    (http_status_code, Json(payload))
+                    (http_status_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
@@ -37,46 +43,85 @@ impl ProductsHandlers {
                 error: Some(ApiError::InvalidParameter)
             };
 
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
-    pub async fn get_products(State(handler_state): State<HandlerState>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_products(State(handler_state): State<HandlerState>, OriginalUri(uri): OriginalUri, listing: Listing, id_filters: Result<Query<IdFilters>, QueryRejection>) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
+        let fields = field_service.get_fields(&field_selection);
         let service = ProductService::new(&handler_state.repository);
-        let product_collection = service.get_products(&pagination.unwrap_or_default().0).await;
+        let batch_service = BatchService::new();
+        if let Some(ids) = batch_service.get_requested_ids(id_filters) {
+            let batch = match (ids, fields) {
+                (Ok(ids), Ok(fields)) => service.get_products_by_ids(&ids).await.map(|(responses, not_found)| {
+                    (responses.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>(), not_found)
+                }),
+                (Err(e), _) | (_, Err(e)) => Err(e)
+            };
+            return batch_service.get_batch_response(batch);
+        }
+        let product_collection = match fields {
+            Ok(fields) => service.get_products(&pagination, &timestamp_filters, &sorting).await.map(|responses| (responses, fields)),
+            Err(e) => Err(e)
+        };
         match product_collection {
-            Ok(responses) => {
-                let payload = ResponseProductsPayload {
-                    data: Some(responses.partial_collection),
-                    total: Some(responses.total_count),
+            Ok((responses, fields)) => {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let payload = ResponseCollectionPayload {
+                    data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
                     error: None
                 };
-                (StatusCode::OK, Json(payload))
+                (StatusCode::OK, headers, Json(payload))
             },
             Err(e) => {
                 let api_error_converter_service = ApiErrorConventerService::new();
                 let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                let payload = ResponseProductsPayload {
+                let payload = ResponseCollectionPayload {
                     data: None,
                     total: None,
+                    pagination: None,
+                    not_found: None,
                     error: Some(e)
                 };
-                (http_return_code, Json(payload))
+                (http_return_code, HeaderMap::new(), Json(payload))
             }
         }
     }
 
-    pub async fn patch_product(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchProductPayload>, JsonRejection>) -> impl IntoResponse {
+    pub async fn patch_product(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchProductPayload>, JsonRejection>) -> impl IntoResponse {
         if id.is_ok() && payload.is_ok() {
             let p_id = id.expect("id should be ok after we have checked").0;
             let p_payload = payload.expect("payload should be ok after we have checked").0;
-            let patch_command = WriterCommand::PatchProduct(p_id as i32, p_payload);
-            let _ = handler_state.sender.send(patch_command).await;
-            let response = ResponseProductPayload {
-                data: None,
-                error: None
-            };
-            (StatusCode::ACCEPTED, Json(response))
+            let service = ProductService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_product_version(p_id as i32).await;
+            match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => {
+                    let patch_command = WriterCommand::PatchProduct(p_id as i32, p_payload, expected_version);
+                    let _ = handler_state.sender.send(patch_command).await;
+                    let response = ResponseProductPayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseProductPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
         }
         else {
             let payload = ResponseProductPayload {
@@ -90,19 +135,54 @@ impl ProductsHandlers {
 
     pub async fn autocomplete_products(State(handler_state): State<HandlerState>, kw: Option<Query<KeywordFilters>>) -> impl IntoResponse {
         let service = ProductService::new(&handler_state.repository);
-        let products_collection;
-        if let Some(keyword) = kw {
-            products_collection = service.autocomplete_products(&keyword.0.keyword).await;
-        }
-        else {
-            products_collection = service.autocomplete_products(&None::<String>).await;
-        }
+        let products_collection = service.autocomplete_products(&kw.unwrap_or_default().0).await;
 
         match products_collection {
             Ok(responses) => {
-                let payload = ResponseProductsPayload {
+                let payload = ResponseCollectionPayload {
                     data: Some(responses.partial_collection),
-                    total: Some(responses.total_count),
+                    total: responses.total_count,
+                    pagination: None,
+                    not_found: None,
+                    error: None
+                };
+                (StatusCode::OK, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, Json(payload))
+            }
+        }
+    }
+
+    pub async fn get_price_history(State(handler_state): State<HandlerState>, conversion: Conversion, id: Result<Path<u32>, PathRejection>, report_filters: Option<Query<ReportFilters>>) -> impl IntoResponse {
+        let Ok(s_id) = id else {
+            let payload: ResponseCollectionPayload<ResponsePriceHistory> = ResponseCollectionPayload {
+                data: None,
+                total: None,
+                pagination: None,
+                not_found: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+            return (StatusCode::BAD_REQUEST, Json(payload));
+        };
+
+        let service = ReportService::new(&handler_state.repository);
+        match service.get_price_history(s_id.0 as i32, &report_filters.unwrap_or_default().0, &conversion).await {
+            Ok(histories) => {
+                let payload = ResponseCollectionPayload {
+                    total: Some(histories.len() as i64),
+                    data: Some(histories),
+                    pagination: None,
+                    not_found: None,
                     error: None
                 };
                 (StatusCode::OK, Json(payload))
@@ -110,9 +190,11 @@ impl ProductsHandlers {
             Err(e) => {
                 let api_error_converter_service = ApiErrorConventerService::new();
                 let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                let payload = ResponseProductsPayload {
+                let payload = ResponseCollectionPayload {
                     data: None,
                     total: None,
+                    pagination: None,
+                    not_found: None,
                     error: Some(e)
                 };
                 (http_return_code, Json(payload))
@@ -1,11 +1,12 @@
 use axum::{
     extract::{
+        OriginalUri,
         Path, 
         State,
         Query,
-        rejection::{PathRejection, JsonRejection}
+        rejection::{PathRejection, JsonRejection, QueryRejection}
     }, 
-    http::StatusCode, 
+    http::{HeaderMap, StatusCode}, 
     response::IntoResponse, Json
 };
 use uuid::Uuid;
@@ -18,13 +19,18 @@ use crate::{
             create_payload::CreateReceiptPayload, 
             patch_payload::PatchReceiptPayload
         }, 
-        parameters::pagination::Pagination, 
-        responses::response_receipt::{
-            ResponseCreateReceiptPayload, ResponseReceiptPayload, ResponseReceiptsPayload
+        parameters::{conversion::Conversion, field_selection::FieldSelection, listing::Listing, pagination::Pagination, query_filters::{IdFilters, ReceiptFilters}}, 
+        responses::{
+            response_collection::ResponseCollectionPayload, 
+            response_receipt::{ResponseCreateReceiptPayload, ResponseReceiptPayload}
         }
     }, 
     services::v1::{
+        batches::batches_service::BatchService, 
         converters::api_error_converter_service::ApiErrorConventerService, 
+        fields::fields_service::FieldService, 
+        links::links_service::LinkService, 
+        preconditions::preconditions_service::PreconditionService, 
         receipts::receipts_service::ReceiptService
     }, share_state::HandlerState
 };
@@ -33,18 +39,24 @@ pub struct ReceiptsHandlers {
 }
 
 impl ReceiptsHandlers {
-    pub async fn get_receipt(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+    pub async fn get_receipt(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>, field_selection: Option<Query<FieldSelection>>, conversion: Conversion) -> impl IntoResponse {
+        let field_selection = field_selection.unwrap_or_default().0;
+        let field_service = FieldService::new();
         let service = ReceiptService::new(&handler_state.repository);
         if let Ok(r_id) = id {
-            let response_receipt = service.get_receipt(r_id.0 as i32).await;
+            let response_receipt = match field_service.get_receipt_includes(&field_selection) {
+                Ok(includes) => service.get_receipt(r_id.0 as i32, &includes, &conversion).await,
+                Err(e) => Err(e)
+            };
             match response_receipt {
                 Ok(response) => {
+                    let headers = PreconditionService::new().get_etag_headers(response.version);
                     let payload = ResponseReceiptPayload {
-                        data: Some(response),
+                        data: Some(field_service.select_fields(&response, &field_service.parse_fields(&field_selection))),
                         error: None
                     };
             
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
@@ -54,7 +66,7 @@ impl ReceiptsHandlers {
                         data: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
@@ -63,47 +75,106 @@ impl ReceiptsHandlers {
                 data: None,
                 error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
+        }
+    }
+
+    pub async fn get_receipts(State(handler_state): State<HandlerState>, OriginalUri(uri): OriginalUri, listing: Listing, receipt_filters: Result<Query<ReceiptFilters>, QueryRejection>, id_filters: Result<Query<IdFilters>, QueryRejection>, conversion: Conversion) -> impl IntoResponse {
+        let Listing { pagination, timestamp_filters, sorting, field_selection } = listing;
+        let field_service = FieldService::new();
+        let service = ReceiptService::new(&handler_state.repository);
+        let batch_service = BatchService::new();
+        if let Some(ids) = batch_service.get_requested_ids(id_filters) {
+            let receipts_batch = match (ids, field_service.get_receipt_includes(&field_selection)) {
+                (Ok(ids), Ok(includes)) => service.get_receipts_by_ids(&ids, &includes, &conversion).await,
+                (Err(e), _) | (_, Err(e)) => Err(e)
+            };
+            let fields = field_service.parse_fields(&field_selection);
+            return batch_service.get_batch_response(receipts_batch.map(|(responses, not_found)| {
+                (responses.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>(), not_found)
+            }));
+        }
+        let receipt_collection = match (receipt_filters, field_service.get_receipt_includes(&field_selection)) {
+            (Ok(receipt_filters), Ok(includes)) => service.get_receipts(&pagination, &receipt_filters.0, &timestamp_filters, &sorting, &includes, &conversion).await,
+            (Err(_), _) => Err(ApiError::InvalidParameter),
+            (_, Err(e)) => Err(e)
+        };
+        match receipt_collection {
+            Ok(responses) => {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let fields = field_service.parse_fields(&field_selection);
+                let payload = ResponseCollectionPayload {
+                    data: Some(responses.partial_collection.iter().map(|r| field_service.select_fields(r, &fields)).collect::<Vec<serde_json::Value>>()),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
+                    error: None
+                };
+                (StatusCode::OK, headers, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, HeaderMap::new(), Json(payload))
+            }
         }
     }
 
-    pub async fn get_receipts(State(handler_state): State<HandlerState>, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+    pub async fn get_trashed_receipts(State(handler_state): State<HandlerState>, OriginalUri(uri): OriginalUri, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+        let pagination = pagination.unwrap_or_default().0;
         let service = ReceiptService::new(&handler_state.repository);
-        let receipt_collection = service.get_receipts(&pagination.unwrap_or_default().0).await;
+        let receipt_collection = service.get_trashed_receipts(&pagination).await;
         match receipt_collection {
             Ok(responses) => {
-                let payload = ResponseReceiptsPayload {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let payload = ResponseCollectionPayload {
                     data: Some(responses.partial_collection),
-                    total: Some(responses.total_count),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
                     error: None
                 };
-                (StatusCode::OK, Json(payload))
+                (StatusCode::OK, headers, Json(payload))
             },
             Err(e) => {
                 let api_error_converter_service = ApiErrorConventerService::new();
                 let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
-                let payload = ResponseReceiptsPayload {
+                let payload = ResponseCollectionPayload {
                     data: None,
                     total: None,
+                    pagination: None,
+                    not_found: None,
                     error: Some(e)
                 };
-                (http_return_code, Json(payload))
+                (http_return_code, HeaderMap::new(), Json(payload))
             }
         }
     }
 
-    pub async fn get_receipt_by_transaction_id(State(handler_state): State<HandlerState>, transaction_id: Result<Path<Uuid>, PathRejection>) -> impl IntoResponse {
+    pub async fn get_receipt_by_transaction_id(State(handler_state): State<HandlerState>, transaction_id: Result<Path<Uuid>, PathRejection>, conversion: Conversion) -> impl IntoResponse {
         if let Ok(t_id) = transaction_id {
             let service = ReceiptService::new(&handler_state.repository);
-            let response_receipt = service.get_receipt_by_transaction_id(t_id.0).await;
+            let response_receipt = service.get_receipt_by_transaction_id(t_id.0, &conversion).await;
             match response_receipt {
                 Ok(response) => {
+                    let headers = PreconditionService::new().get_etag_headers(response.version);
                     let payload = ResponseReceiptPayload {
-                        data: Some(response),
+                        data: Some(FieldService::new().select_fields(&response, &None)),
                         error: None
                     };
             
-                    (StatusCode::OK, Json(payload))
+                    (StatusCode::OK, headers, Json(payload))
                 },
                 Err(e) => {
                     let api_error_converter_service = ApiErrorConventerService::new();
@@ -113,7 +184,7 @@ impl ReceiptsHandlers {
                         data: None,
                         error: Some(e)
                     };
-                    (http_return_code, Json(payload))
+                    (http_return_code, HeaderMap::new(), Json(payload))
                 }
             }
         }
@@ -123,7 +194,7 @@ impl ReceiptsHandlers {
                 error: Some(ApiError::InvalidParameter)
             };
 
-            (StatusCode::BAD_REQUEST, Json(payload))
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
         }
     }
 
@@ -149,17 +220,106 @@ impl ReceiptsHandlers {
         }
     }
 
-    pub async fn patch_receipt(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchReceiptPayload>, JsonRejection>) -> impl IntoResponse {
+    pub async fn patch_receipt(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<PatchReceiptPayload>, JsonRejection>) -> impl IntoResponse {
         if id.is_ok() && payload.is_ok() {
             let r_id = id.expect("id should be ok after we have checked").0;
             let r_payload = payload.expect("payload should be ok after we have checked").0;
-            let patch_command = WriterCommand::PatchReceipt(r_id as i32, r_payload);
-            let _ = handler_state.sender.send(patch_command).await;
-            let response = ResponseReceiptPayload {
+            let service = ReceiptService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_receipt_version(r_id as i32).await;
+            match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => {
+                    let patch_command = WriterCommand::PatchReceipt(r_id as i32, r_payload, expected_version);
+                    let _ = handler_state.sender.send(patch_command).await;
+                    let response = ResponseReceiptPayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseReceiptPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseReceiptPayload {
                 data: None,
-                error: None
+                error: Some(ApiError::InvalidParameter)
             };
-            (StatusCode::ACCEPTED, Json(response))
+
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+
+    pub async fn put_receipt(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>,  payload: Result<Json<CreateReceiptPayload>, JsonRejection>) -> impl IntoResponse {
+        if let (Ok(r_id), Ok(r_payload)) = (id, payload) {
+            // transaction_id belongs to the receipt being replaced, so the one in the payload is ignored.
+            let service = ReceiptService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_receipt_version(r_id.0 as i32).await;
+            match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => {
+                    let put_command = WriterCommand::PutReceipt(r_id.0 as i32, r_payload.0, expected_version);
+                    let _ = handler_state.sender.send(put_command).await;
+                    let response = ResponseReceiptPayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseReceiptPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseReceiptPayload {
+                data: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+
+    pub async fn delete_receipt(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+        if let Ok(r_id) = id {
+            let service = ReceiptService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_receipt_version(r_id.0 as i32).await;
+            match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => {
+                    let delete_command = WriterCommand::DeleteReceipt(r_id.0 as i32, expected_version);
+                    let _ = handler_state.sender.send(delete_command).await;
+                    let response = ResponseReceiptPayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseReceiptPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
         }
         else {
             let payload = ResponseReceiptPayload {
@@ -171,10 +331,10 @@ impl ReceiptsHandlers {
         }
     }
 
-    pub async fn delete_receipt(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+    pub async fn restore_receipt(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
         if let Ok(r_id) = id {
-            let delete_command = WriterCommand::DeleteReceipt(r_id.0 as i32);
-            let _ = handler_state.sender.send(delete_command).await;
+            let restore_command = WriterCommand::RestoreReceipt(r_id.0 as i32);
+            let _ = handler_state.sender.send(restore_command).await;
             let response = ResponseReceiptPayload {
                 data: None,
                 error: None
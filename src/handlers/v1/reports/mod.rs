@@ -0,0 +1 @@
+pub mod reports_handlers;
\ No newline at end of file
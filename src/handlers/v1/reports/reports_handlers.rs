@@ -0,0 +1,92 @@
+use axum::{extract::{Query, State}, http::StatusCode, response::IntoResponse, Json};
+
+use crate::{models::v1::{parameters::{conversion::Conversion, query_filters::{BasketFilters, ReportFilters, UnitPriceReportFilters}}, responses::response_collection::ResponseCollectionPayload}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, reports::reports_service::ReportService}, share_state::HandlerState};
+
+pub struct ReportsHandlers {
+}
+
+impl ReportsHandlers {
+    pub async fn get_tax_report(State(handler_state): State<HandlerState>, conversion: Conversion, report_filters: Option<Query<ReportFilters>>) -> impl IntoResponse {
+        let service = ReportService::new(&handler_state.repository);
+        match service.get_tax_report(&report_filters.unwrap_or_default().0, &conversion).await {
+            Ok(rows) => {
+                let payload = ResponseCollectionPayload {
+                    total: Some(rows.len() as i64),
+                    data: Some(rows),
+                    pagination: None,
+                    not_found: None,
+                    error: None
+                };
+                (StatusCode::OK, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, Json(payload))
+            }
+        }
+    }
+
+    pub async fn get_unit_price_report(State(handler_state): State<HandlerState>, conversion: Conversion, report_filters: Option<Query<UnitPriceReportFilters>>) -> impl IntoResponse {
+        let service = ReportService::new(&handler_state.repository);
+        match service.get_unit_price_report(&report_filters.unwrap_or_default().0, &conversion).await {
+            Ok(rows) => {
+                let payload = ResponseCollectionPayload {
+                    total: Some(rows.len() as i64),
+                    data: Some(rows),
+                    pagination: None,
+                    not_found: None,
+                    error: None
+                };
+                (StatusCode::OK, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, Json(payload))
+            }
+        }
+    }
+
+    pub async fn get_cheapest_stores(State(handler_state): State<HandlerState>, conversion: Conversion, basket_filters: Option<Query<BasketFilters>>) -> impl IntoResponse {
+        let service = ReportService::new(&handler_state.repository);
+        match service.get_cheapest_stores(&basket_filters.unwrap_or_default().0, &conversion).await {
+            Ok(rows) => {
+                let payload = ResponseCollectionPayload {
+                    total: Some(rows.len() as i64),
+                    data: Some(rows),
+                    pagination: None,
+                    not_found: None,
+                    error: None
+                };
+                (StatusCode::OK, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, Json(payload))
+            }
+        }
+    }
+}
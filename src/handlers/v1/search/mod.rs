@@ -0,0 +1 @@
+pub mod search_handlers;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use axum::{extract::{Query, State}, http::StatusCode, response::IntoResponse, Json};
+
+use crate::{models::v1::{errors::api_error::ApiError, parameters::query_filters::SearchFilters, responses::response_collection::ResponseCollectionPayload}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, search::search_service::SearchService}, share_state::HandlerState};
+
+pub struct SearchHandlers {
+}
+
+impl SearchHandlers {
+    pub async fn search(State(handler_state): State<HandlerState>, search_filters: Option<Query<SearchFilters>>) -> impl IntoResponse {
+        let service = SearchService::new(&handler_state.repository);
+        if let Some(filters) = search_filters {
+            match service.search(&filters.0).await {
+                Ok(hits) => {
+                    let payload = ResponseCollectionPayload {
+                        total: Some(hits.len() as i64),
+                        data: Some(hits),
+                        pagination: None,
+                        not_found: None,
+                        error: None
+                    };
+                    (StatusCode::OK, Json(payload))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseCollectionPayload {
+                        data: None,
+                        total: None,
+                        pagination: None,
+                        not_found: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseCollectionPayload {
+                data: None,
+                total: None,
+                pagination: None,
+                not_found: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod tax_rates_handlers;
\ No newline at end of file
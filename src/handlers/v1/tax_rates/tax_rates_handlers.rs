@@ -0,0 +1,187 @@
+use axum::{extract::{rejection::{JsonRejection, PathRejection}, OriginalUri, Path, Query, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Json};
+
+use crate::{models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::{create_payload::CreateTaxRatePayload, patch_payload::PatchTaxRatePayload}, parameters::pagination::Pagination, responses::{response_collection::ResponseCollectionPayload, response_tax_rate::ResponseTaxRatePayload}}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, links::links_service::LinkService, preconditions::preconditions_service::PreconditionService, tax_rates::tax_rates_service::TaxRateService}, share_state::HandlerState};
+
+pub struct TaxRatesHandlers {
+}
+
+impl TaxRatesHandlers {
+    pub async fn get_tax_rate(State(handler_state): State<HandlerState>, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+        let service = TaxRateService::new(&handler_state.repository);
+        if let Ok(t_id) = id {
+            let response_tax_rate = service.get_tax_rate(t_id.0 as i32).await;
+            match response_tax_rate {
+                Ok(response) => {
+                    let headers = PreconditionService::new().get_etag_headers(response.version);
+                    let payload = ResponseTaxRatePayload {
+                        data: Some(response),
+                        error: None
+                    };
+                    (StatusCode::OK, headers, Json(payload))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_status_code = api_error_converter_service.get_http_status_from_api_error(&e);
+
+                    let payload = ResponseTaxRatePayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_status_code, HeaderMap::new(), Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseTaxRatePayload {
+                data: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+
+            (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(payload))
+        }
+    }
+
+    pub async fn get_tax_rates(State(handler_state): State<HandlerState>, OriginalUri(uri): OriginalUri, pagination: Option<Query<Pagination>>) -> impl IntoResponse {
+        let pagination = pagination.unwrap_or_default().0;
+        let service = TaxRateService::new(&handler_state.repository);
+        let tax_rates_collection = service.get_tax_rates(&pagination).await;
+        match tax_rates_collection {
+            Ok(responses) => {
+                let link_service = LinkService::new();
+                let response_pagination = link_service.get_pagination(&uri, &pagination, &responses);
+                let headers = link_service.get_link_headers(&response_pagination);
+                let payload = ResponseCollectionPayload {
+                    data: Some(responses.partial_collection),
+                    total: responses.total_count,
+                    pagination: Some(response_pagination),
+                    not_found: None,
+                    error: None
+                };
+                (StatusCode::OK, headers, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, HeaderMap::new(), Json(payload))
+            }
+        }
+    }
+
+    pub async fn post_tax_rate(State(handler_state): State<HandlerState>, payload: Result<Json<CreateTaxRatePayload>, JsonRejection>) -> impl IntoResponse {
+        if let Ok(t_payload) = payload {
+            let service = TaxRateService::new(&handler_state.repository);
+            match service.validate_tax_rate(&t_payload.0).await {
+                Ok(()) => {
+                    let create_command = WriterCommand::CreateTaxRate(t_payload.0);
+                    let _ = handler_state.sender.send(create_command).await;
+                    let response = ResponseTaxRatePayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseTaxRatePayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseTaxRatePayload {
+                data: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+
+    pub async fn patch_tax_rate(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>, payload: Result<Json<PatchTaxRatePayload>, JsonRejection>) -> impl IntoResponse {
+        if let (Ok(t_id), Ok(t_payload)) = (id, payload) {
+            let service = TaxRateService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_tax_rate_version(t_id.0 as i32).await;
+            let expected_version = match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => service.validate_patch_tax_rate(t_id.0 as i32, &t_payload.0).await.map(|_| expected_version),
+                Err(e) => Err(e)
+            };
+            match expected_version {
+                Ok(expected_version) => {
+                    let patch_command = WriterCommand::PatchTaxRate(t_id.0 as i32, t_payload.0, expected_version);
+                    let _ = handler_state.sender.send(patch_command).await;
+                    let response = ResponseTaxRatePayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseTaxRatePayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseTaxRatePayload {
+                data: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+
+    pub async fn delete_tax_rate(State(handler_state): State<HandlerState>, headers: HeaderMap, id: Result<Path<u32>, PathRejection>) -> impl IntoResponse {
+        if let Ok(t_id) = id {
+            let service = TaxRateService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_tax_rate_version(t_id.0 as i32).await;
+            let expected_version = match current_version.and_then(|v| precondition_service.check_if_match(&headers, v)) {
+                Ok(expected_version) => service.validate_delete_tax_rate(t_id.0 as i32).await.map(|_| expected_version),
+                Err(e) => Err(e)
+            };
+            match expected_version {
+                Ok(expected_version) => {
+                    let delete_command = WriterCommand::DeleteTaxRate(t_id.0 as i32, expected_version);
+                    let _ = handler_state.sender.send(delete_command).await;
+                    let response = ResponseTaxRatePayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseTaxRatePayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseTaxRatePayload {
+                data: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+}
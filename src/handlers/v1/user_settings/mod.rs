@@ -0,0 +1 @@
+pub mod user_settings_handlers;
\ No newline at end of file
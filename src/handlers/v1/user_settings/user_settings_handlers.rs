@@ -0,0 +1,67 @@
+use axum::{extract::{rejection::JsonRejection, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Extension, Json};
+
+use crate::{models::v1::{commands::writer_command::WriterCommand, errors::api_error::ApiError, forms::patch_payload::PatchUserSettingPayload, responses::response_user_setting::ResponseUserSettingPayload}, services::v1::{converters::api_error_converter_service::ApiErrorConventerService, preconditions::preconditions_service::PreconditionService, user_settings::user_settings_service::UserSettingService}, mw_auth::SessionUser, share_state::HandlerState};
+
+pub struct UserSettingsHandlers {
+}
+
+impl UserSettingsHandlers {
+    pub async fn get_user_setting(State(handler_state): State<HandlerState>, Extension(session_user): Extension<SessionUser>) -> impl IntoResponse {
+        let service = UserSettingService::new(&handler_state.repository);
+        match service.get_user_setting(&session_user.username).await {
+            Ok(response) => {
+                let headers = PreconditionService::new().get_etag_headers(response.version);
+                let payload = ResponseUserSettingPayload {
+                    data: Some(response),
+                    error: None
+                };
+                (StatusCode::OK, headers, Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_status_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseUserSettingPayload {
+                    data: None,
+                    error: Some(e)
+                };
+                (http_status_code, HeaderMap::new(), Json(payload))
+            }
+        }
+    }
+
+    pub async fn patch_user_setting(State(handler_state): State<HandlerState>, Extension(session_user): Extension<SessionUser>, headers: HeaderMap, payload: Result<Json<PatchUserSettingPayload>, JsonRejection>) -> impl IntoResponse {
+        if let Ok(u_payload) = payload {
+            let service = UserSettingService::new(&handler_state.repository);
+            let precondition_service = PreconditionService::new();
+            let current_version = service.get_user_setting_version(&session_user.username).await;
+            let expected_version = current_version.and_then(|v| precondition_service.check_if_match(&headers, v));
+            match service.validate_user_setting(&u_payload.0).await.and(expected_version) {
+                Ok(expected_version) => {
+                    let patch_command = WriterCommand::PatchUserSetting(session_user.username, u_payload.0, expected_version);
+                    let _ = handler_state.sender.send(patch_command).await;
+                    let response = ResponseUserSettingPayload {
+                        data: None,
+                        error: None
+                    };
+                    (StatusCode::ACCEPTED, Json(response))
+                },
+                Err(e) => {
+                    let api_error_converter_service = ApiErrorConventerService::new();
+                    let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                    let payload = ResponseUserSettingPayload {
+                        data: None,
+                        error: Some(e)
+                    };
+                    (http_return_code, Json(payload))
+                }
+            }
+        }
+        else {
+            let payload = ResponseUserSettingPayload {
+                data: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+            (StatusCode::BAD_REQUEST, Json(payload))
+        }
+    }
+}
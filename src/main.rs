@@ -1,6 +1,7 @@
 use std::env;
 use axum_server::tls_rustls::RustlsConfig;
 use receipt_repository_api::services::v1::commands::command_service::CommandService;
+use receipt_repository_api::services::v1::trash::trash_service::TrashService;
 use receipt_repository_api::share_state::HandlerState;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -34,6 +35,7 @@ async fn main() {
     
     let repository = DbRepository::new(config.get_db_url());
     let sender = CommandService::run(repository.clone(), config.get_writer_channel_buffer_size());
+    TrashService::run(sender.clone(), config.get_trash_retention_days(), config.get_trash_purge_interval());
     let handler_state = HandlerState::new(repository, sender);
     let router = AppRouter::new(handler_state);
 
@@ -1,5 +1,7 @@
 #[derive(Debug, PartialEq)]
 pub struct ServiceCollection<T> {
     pub partial_collection: Vec<T>,
-    pub total_count: i64
+    pub total_count: Option<i64>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>
 }
@@ -1,13 +1,22 @@
-use crate::models::v1::forms::create_payload::CreateReceiptPayload;
-use crate::models::v1::forms::patch_payload::{PatchReceiptPayload, PatchCurrencyPayload, PatchStorePayload, PatchProductPayload, PatchInventoryPayload};
+use crate::models::v1::entities::entity_exchange_rate::NewEntityExchangeRate;
+use crate::models::v1::forms::create_payload::{CreateReceiptPayload, CreateTaxRatePayload};
+use crate::models::v1::forms::patch_payload::{PatchReceiptPayload, PatchCurrencyPayload, PatchStorePayload, PatchProductPayload, PatchInventoryPayload, PatchTaxRatePayload, PatchUserSettingPayload};
 
 #[derive(Clone, Debug)]
 pub enum WriterCommand {
     CreateReceipt(CreateReceiptPayload),
-    DeleteReceipt(i32),
-    PatchReceipt(i32, PatchReceiptPayload),
-    PatchCurrency(i32, PatchCurrencyPayload),
-    PatchStore(i32, PatchStorePayload),
-    PatchProduct(i32, PatchProductPayload),
-    PatchInventory(i32, PatchInventoryPayload)
+    DeleteReceipt(i32, i32),
+    RestoreReceipt(i32),
+    PurgeTrashedReceipts(i64),
+    PatchReceipt(i32, PatchReceiptPayload, i32),
+    PutReceipt(i32, CreateReceiptPayload, i32),
+    PatchCurrency(i32, PatchCurrencyPayload, i32),
+    PatchStore(i32, PatchStorePayload, i32),
+    PatchProduct(i32, PatchProductPayload, i32),
+    PatchInventory(i32, PatchInventoryPayload, i32),
+    CreateTaxRate(CreateTaxRatePayload),
+    PatchTaxRate(i32, PatchTaxRatePayload, i32),
+    DeleteTaxRate(i32, i32),
+    ImportExchangeRates(Vec<NewEntityExchangeRate>),
+    PatchUserSetting(String, PatchUserSettingPayload, i32)
 }
\ No newline at end of file
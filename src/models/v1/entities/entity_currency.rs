@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
 #[derive(Queryable, Selectable, Identifiable, Debug, PartialEq, Clone)]
@@ -5,7 +6,14 @@ use diesel::prelude::*;
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct EntityCurrency {
     pub id: i32,
-    pub name: String
+    pub name: String,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub minor_units: i32,
+    pub code: Option<String>,
+    pub symbol: Option<String>,
+    pub display_name: Option<String>
 }
 
 #[derive(Insertable, Debug)]
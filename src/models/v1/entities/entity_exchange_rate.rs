@@ -0,0 +1,37 @@
+use std::collections::{HashMap, HashSet};
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+
+use super::entity_currency::EntityCurrency;
+
+#[derive(Queryable, Selectable, Identifiable, Debug, PartialEq, Clone)]
+#[diesel(table_name = crate::schema::exchange_rates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EntityExchangeRate {
+    pub id: i32,
+    pub rate_date: NaiveDate,
+    pub from_currency_id: i32,
+    pub to_currency_id: i32,
+    pub rate: BigDecimal,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = crate::schema::exchange_rates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewEntityExchangeRate {
+    pub rate_date: NaiveDate,
+    pub from_currency_id: i32,
+    pub to_currency_id: i32,
+    pub rate: BigDecimal
+}
+
+// the currency to convert to and the rate of each receipt, a receipt without any effective rate is left out
+#[derive(Debug, Clone)]
+pub struct EntityConversion {
+    pub currency: EntityCurrency,
+    pub rates: HashMap<i32, BigDecimal>,
+    pub settled_receipt_ids: HashSet<i32>
+}
@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use bigdecimal::BigDecimal;
 
@@ -9,12 +10,23 @@ use crate::models::v1::entities::entity_receipt::EntityReceipt;
 #[diesel(belongs_to(EntityProduct, foreign_key = product_id))]
 #[diesel(belongs_to(EntityReceipt, foreign_key = receipt_id))]
 #[diesel(check_for_backend(diesel::pg::Pg))]
+// a patch writes the whole loaded line, so a cleared tax rate is written as NULL
+#[diesel(treat_none_as_null = true)]
 pub struct EntityInventory {
     pub id: i32,
     pub price: BigDecimal,
-    pub quantity: i32,
+    pub quantity: BigDecimal,
     pub product_id: i32,
-    pub receipt_id: i32
+    pub receipt_id: i32,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub discount: BigDecimal,
+    pub tax_rate_id: Option<i32>,
+    pub pre_tax_amount: BigDecimal,
+    pub post_tax_amount: BigDecimal,
+    pub unit: String,
+    pub line_price: BigDecimal
 }
 
 #[derive(Insertable, Debug)]
@@ -24,7 +36,13 @@ pub struct EntityInventory {
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct NewEntityInventory {
     pub price: BigDecimal,
-    pub quantity: i32,
+    pub quantity: BigDecimal,
     pub product_id: i32,
-    pub receipt_id: i32
+    pub receipt_id: i32,
+    pub discount: BigDecimal,
+    pub tax_rate_id: Option<i32>,
+    pub pre_tax_amount: BigDecimal,
+    pub post_tax_amount: BigDecimal,
+    pub unit: String,
+    pub line_price: BigDecimal
 }
\ No newline at end of file
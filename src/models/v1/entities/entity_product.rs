@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
 #[derive(Queryable, Selectable, Identifiable, Debug, PartialEq, Clone)]
@@ -10,7 +11,10 @@ pub struct EntityProduct {
     pub brand: Option<String>,
     pub specification_amount: Option<i32>,
     pub specification_unit: Option<String>,
-    pub specification_others: Option<String>
+    pub specification_others: Option<String>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
 }
 
 #[derive(Insertable, Debug)]
@@ -1,4 +1,5 @@
-use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::prelude::*;
 
 use crate::models::v1::entities::entity_currency::EntityCurrency;
@@ -11,11 +12,21 @@ use crate::models::v1::entities::entity_store::EntityStore;
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct EntityReceipt {
     pub id: i32,
-    pub transaction_date: NaiveDateTime,
-    pub is_inventory_taxed: bool,
+    pub transaction_date: DateTime<Utc>,
     pub currency_id: i32,
     pub store_id: i32,
-    pub transaction_id: Option<uuid::Uuid>
+    pub transaction_id: Option<uuid::Uuid>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub transaction_offset: i32,
+    pub discount: BigDecimal,
+    pub service_charge: BigDecimal,
+    pub printed_total: Option<BigDecimal>,
+    pub settlement_currency_id: Option<i32>,
+    pub settled_amount: Option<BigDecimal>,
+    pub settlement_fee: Option<BigDecimal>
 }
 
 #[derive(Insertable, Debug)]
@@ -24,9 +35,30 @@ pub struct EntityReceipt {
 #[diesel(belongs_to(EntityStore, foreign_key = store_id))]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct NewEntityReceipt {
-    pub transaction_date: NaiveDateTime,
-    pub is_inventory_taxed: bool,
+    pub transaction_date: DateTime<Utc>,
+    pub transaction_offset: i32,
     pub currency_id: i32,
     pub store_id: i32,
-    pub transaction_id: Option<uuid::Uuid>
+    pub transaction_id: Option<uuid::Uuid>,
+    pub discount: BigDecimal,
+    pub service_charge: BigDecimal,
+    pub printed_total: Option<BigDecimal>,
+    pub settlement_currency_id: Option<i32>,
+    pub settled_amount: Option<BigDecimal>,
+    pub settlement_fee: Option<BigDecimal>
+}
+
+#[derive(AsChangeset, Identifiable, Debug)]
+#[diesel(table_name = crate::schema::receipts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UpdateEntityReceipt {
+    pub id: i32,
+    pub transaction_date: Option<DateTime<Utc>>,
+    pub transaction_offset: Option<i32>,
+    pub discount: Option<BigDecimal>,
+    pub service_charge: Option<BigDecimal>,
+    pub printed_total: Option<Option<BigDecimal>>,
+    pub settlement_currency_id: Option<Option<i32>>,
+    pub settled_amount: Option<Option<BigDecimal>>,
+    pub settlement_fee: Option<Option<BigDecimal>>
 }
\ No newline at end of file
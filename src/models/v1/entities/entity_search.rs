@@ -0,0 +1,15 @@
+use diesel::{prelude::*, sql_types::{Float4, Int4, Nullable, Text}};
+
+#[derive(QueryableByName, Debug, PartialEq)]
+pub struct EntitySearchHit {
+    #[diesel(sql_type = Text)]
+    pub kind: String,
+    #[diesel(sql_type = Int4)]
+    pub id: i32,
+    #[diesel(sql_type = Text)]
+    pub title: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub subtitle: Option<String>,
+    #[diesel(sql_type = Float4)]
+    pub score: f32
+}
@@ -1,6 +1,7 @@
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
-#[derive(Queryable, Selectable, Identifiable, Debug, PartialEq)]
+#[derive(Queryable, Selectable, Identifiable, Debug, PartialEq, Clone)]
 #[diesel(table_name = crate::schema::stores)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct EntityStore {
@@ -8,7 +9,11 @@ pub struct EntityStore {
     pub name: String,
     pub alias: Option<String>,
     pub branch: Option<String>,
-    pub address: Option<String>
+    pub address: Option<String>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub tax_rate_id: Option<i32>
 }
 
 #[derive(Insertable, Debug)]
@@ -18,7 +23,8 @@ pub struct NewEntityStore {
     pub name: String,
     pub alias: Option<String>,
     pub branch: Option<String>,
-    pub address: Option<String>
+    pub address: Option<String>,
+    pub tax_rate_id: Option<i32>
 }
 
 #[derive(AsChangeset, Identifiable, Debug)]
@@ -29,5 +35,6 @@ pub struct UpdateEntityStore<'a> {
     pub name: Option<&'a String>,
     pub alias: Option<Option<&'a String>>,
     pub branch: Option<Option<&'a String>>,
-    pub address: Option<Option<&'a String>>
+    pub address: Option<Option<&'a String>>,
+    pub tax_rate_id: Option<Option<i32>>
 }
\ No newline at end of file
@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use bigdecimal::BigDecimal;
+
+#[derive(Queryable, Selectable, Identifiable, Debug, PartialEq, Clone)]
+#[diesel(table_name = crate::schema::tax_rates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EntityTaxRate {
+    pub id: i32,
+    pub name: String,
+    pub rate: BigDecimal,
+    pub is_inclusive: bool,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::tax_rates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewEntityTaxRate {
+    pub name: String,
+    pub rate: BigDecimal,
+    pub is_inclusive: bool
+}
+
+
+#[derive(AsChangeset, Identifiable, Debug)]
+#[diesel(table_name = crate::schema::tax_rates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UpdateEntityTaxRate {
+    pub id: i32,
+    pub name: Option<String>,
+    pub rate: Option<BigDecimal>,
+    pub is_inclusive: Option<bool>
+}
\ No newline at end of file
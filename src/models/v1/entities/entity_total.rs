@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use diesel::{prelude::*, sql_types::{Date, Int4, Int8, Numeric, Text}};
+
+use crate::models::v1::entities::entity_exchange_rate::EntityConversion;
+
+// line totals summed up per receipt
+#[derive(Queryable, Debug, Clone, Default)]
+pub struct EntityReceiptSubtotal {
+    pub receipt_id: i32,
+    pub subtotal: BigDecimal,
+    pub pre_tax: BigDecimal,
+    pub post_tax: BigDecimal,
+    pub line_count: i64
+}
+
+// what the amounts of a receipt are formatted and converted with
+pub struct EntityReceiptAmounts<'a> {
+    pub subtotal: &'a EntityReceiptSubtotal,
+    pub minor_units: i32,
+    pub settlement_minor_units: Option<i32>,
+    pub conversion: Option<&'a EntityConversion>
+}
+
+// the same for a page of receipts, by receipt id
+pub struct EntityReceiptsAmounts<'a> {
+    pub subtotals: HashMap<i32, EntityReceiptSubtotal>,
+    pub minor_units: HashMap<i32, i32>,
+    pub settlement_minor_units: HashMap<i32, i32>,
+    pub conversion: Option<&'a EntityConversion>
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct EntityTaxReport {
+    #[diesel(sql_type = Date)]
+    pub period_start: NaiveDate,
+    #[diesel(sql_type = Int4)]
+    pub currency_id: i32,
+    #[diesel(sql_type = Text)]
+    pub currency: String,
+    #[diesel(sql_type = Int4)]
+    pub minor_units: i32,
+    #[diesel(sql_type = Int8)]
+    pub receipt_count: i64,
+    // receipts without any exchange rate, always 0 when nothing is converted
+    #[diesel(sql_type = Int8)]
+    pub unconverted_count: i64,
+    #[diesel(sql_type = Numeric)]
+    pub pre_tax: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    pub post_tax: BigDecimal
+}
@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, HashMap};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+use super::{entity_currency::EntityCurrency, entity_product::EntityProduct, entity_store::EntityStore};
+
+// what a unit price is compared by, mass in g, volume in mL and count in pieces
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StandardUnit {
+    Mass,
+    Volume,
+    Count
+}
+
+impl StandardUnit {
+    // a unit price is per 100 g, per litre or per piece
+    pub fn size(&self) -> i32 {
+        match self {
+            Self::Mass => 100,
+            Self::Volume => 1000,
+            Self::Count => 1
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mass => "100 g",
+            Self::Volume => "1 L",
+            Self::Count => "1 piece"
+        }
+    }
+}
+
+// the quantity of a line in the base unit of its standard unit, 2 packs of 500 g are 1000 g
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntityStandardQuantity {
+    pub amount: BigDecimal,
+    pub unit: StandardUnit
+}
+
+// a purchased line with its unit price, in the currency converted to when there is one
+#[derive(Clone, Debug)]
+pub struct EntityPricedLine {
+    pub product: EntityProduct,
+    pub store: EntityStore,
+    pub currency: EntityCurrency,
+    pub unit: StandardUnit,
+    pub unit_price: BigDecimal,
+    pub purchased_at: DateTime<FixedOffset>
+}
+
+// the latest price is the one of the last purchase
+#[derive(Clone, Debug)]
+pub struct EntityUnitPriceStats {
+    pub line_count: i64,
+    pub lowest: BigDecimal,
+    pub highest: BigDecimal,
+    pub total: BigDecimal,
+    pub latest: BigDecimal,
+    pub last_purchased_at: DateTime<FixedOffset>
+}
+
+impl EntityUnitPriceStats {
+    pub fn new(unit_price: &BigDecimal, purchased_at: DateTime<FixedOffset>) -> Self {
+        Self {
+            line_count: 1,
+            lowest: unit_price.clone(),
+            highest: unit_price.clone(),
+            total: unit_price.clone(),
+            latest: unit_price.clone(),
+            last_purchased_at: purchased_at
+        }
+    }
+
+    pub fn add(&mut self, unit_price: &BigDecimal, purchased_at: DateTime<FixedOffset>) {
+        self.line_count += 1;
+        self.total += unit_price;
+        if *unit_price < self.lowest {
+            self.lowest = unit_price.clone();
+        }
+        if *unit_price > self.highest {
+            self.highest = unit_price.clone();
+        }
+        if purchased_at >= self.last_purchased_at {
+            self.latest = unit_price.clone();
+            self.last_purchased_at = purchased_at;
+        }
+    }
+
+    pub fn average(&self) -> BigDecimal {
+        &self.total / BigDecimal::from(self.line_count)
+    }
+}
+
+// the unit prices of a product bought at a store, in one currency and one standard unit
+#[derive(Clone, Debug)]
+pub struct EntityUnitPriceComparison {
+    pub product: EntityProduct,
+    pub store: EntityStore,
+    pub currency: EntityCurrency,
+    pub unit: StandardUnit,
+    pub stats: EntityUnitPriceStats
+}
+
+// the unit prices of a product at a store per period, in one currency and one standard unit
+#[derive(Clone, Debug)]
+pub struct EntityPriceHistory {
+    pub store: EntityStore,
+    pub currency: EntityCurrency,
+    pub unit: StandardUnit,
+    pub points: BTreeMap<NaiveDate, EntityUnitPriceStats>
+}
+
+// an item of a basket, the quantity and the unit are as the client gave them
+#[derive(Clone, Debug)]
+pub struct EntityBasketItem {
+    pub product: EntityProduct,
+    pub quantity: BigDecimal,
+    pub unit: String,
+    pub standard_quantity: EntityStandardQuantity
+}
+
+// the unit prices of the basket items at a store by product id, an item never seen at the store has none
+#[derive(Clone, Debug)]
+pub struct EntityStoreBasket {
+    pub store: EntityStore,
+    pub currency: EntityCurrency,
+    pub prices: HashMap<i32, EntityUnitPriceStats>,
+    pub latest_total: BigDecimal,
+    pub average_total: BigDecimal
+}
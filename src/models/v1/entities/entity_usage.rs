@@ -0,0 +1,31 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use diesel::{prelude::*, sql_types::{Float8, Int4, Int8, Nullable, Numeric, Text, Timestamptz}};
+
+#[derive(QueryableByName, Debug, PartialEq)]
+pub struct EntityProductUsage {
+    #[diesel(sql_type = Int4)]
+    pub id: i32,
+    #[diesel(sql_type = Int8)]
+    pub purchase_count: i64,
+    #[diesel(sql_type = Float8)]
+    pub score: f64,
+    #[diesel(sql_type = Nullable<Numeric>)]
+    pub last_price: Option<BigDecimal>,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub last_currency: Option<String>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub last_minor_units: Option<i32>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub last_purchased_at: Option<DateTime<Utc>>
+}
+
+#[derive(QueryableByName, Debug, PartialEq)]
+pub struct EntityStoreUsage {
+    #[diesel(sql_type = Int4)]
+    pub id: i32,
+    #[diesel(sql_type = Int8)]
+    pub visit_count: i64,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub last_visited_at: Option<DateTime<Utc>>
+}
@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Debug, PartialEq, Clone)]
+#[diesel(table_name = crate::schema::user_settings)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EntityUserSetting {
+    pub username: String,
+    pub base_currency_id: Option<i32>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::user_settings)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewEntityUserSetting {
+    pub username: String,
+    pub base_currency_id: Option<i32>
+}
+
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = crate::schema::user_settings)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UpdateEntityUserSetting {
+    pub base_currency_id: Option<Option<i32>>
+}
\ No newline at end of file
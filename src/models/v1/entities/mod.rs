@@ -2,4 +2,11 @@ pub mod entity_currency;
 pub mod entity_store;
 pub mod entity_product;
 pub mod entity_receipt;
-pub mod entity_inventory;
\ No newline at end of file
+pub mod entity_inventory;
+pub mod entity_search;
+pub mod entity_usage;
+pub mod entity_tax_rate;
+pub mod entity_total;
+pub mod entity_exchange_rate;
+pub mod entity_user_setting;
+pub mod entity_unit;
\ No newline at end of file
@@ -56,5 +56,62 @@ pub enum ApiError {
     #[error("Delete a receipt failed")]
     DeleteReceiptEntryFailed,
     #[error("Delete data which is related to a receipt failed")]
-    DeleteReceiptRelatedEntryFailed
+    DeleteReceiptRelatedEntryFailed,
+    #[error("Replace a receipt whose id is not existed")]
+    ReplaceReceiptIdNotExisted,
+    #[error("Replace inventories of a receipt failed")]
+    ReplaceReceiptInventoriesFailed,
+    #[error("Restore a receipt which is not in trash")]
+    RestoreReceiptIdNotExisted,
+    #[error("Restore a receipt failed")]
+    RestoreReceiptFailed,
+    #[error("Purge a receipt in trash failed")]
+    PurgeReceiptFailed,
+    #[error("If-Match header is required")]
+    PreconditionRequired,
+    #[error("The record has been modified by others")]
+    PreconditionFailed,
+    #[error("Sort by an unknown field")]
+    InvalidSortField,
+    #[error("The cursor is invalid")]
+    InvalidCursor,
+    #[error("The date range is invalid")]
+    InvalidDateRange,
+    #[error("The field is invalid")]
+    InvalidField,
+    #[error("Tax rate id is not existed")]
+    TaxRateIdNotExisted,
+    #[error("Tax rate name is duplicated")]
+    TaxRateNameDuplicated,
+    #[error("Insert a new tax rate is failed")]
+    InsertTaxRateFailed,
+    #[error("Update a tax rate is failed")]
+    UpdateTaxRateFailed,
+    #[error("Tax rate is used by stores or inventories")]
+    TaxRateReferenced,
+    #[error("Delete a tax rate failed")]
+    DeleteTaxRateFailed,
+    #[error("The amount has more decimal places than the currency allows")]
+    InvalidAmountPrecision,
+    #[error("The quantity has to be positive with at most 3 decimal places")]
+    InvalidQuantity,
+    #[error("The discount is larger than the line price")]
+    DiscountExceedsLinePrice,
+    #[error("No exchange rate is effective on the transaction date")]
+    ExchangeRateNotExisted,
+    #[error("The exchange rate file is invalid")]
+    InvalidExchangeRateFile,
+    #[error("Import exchange rates failed")]
+    ImportExchangeRatesFailed,
+    #[error("Base currency is not set")]
+    BaseCurrencyNotSet,
+    #[error("Update user settings failed")]
+    UpdateUserSettingFailed
+}
+
+impl From<diesel::result::Error> for ApiError {
+    fn from(e: diesel::result::Error) -> Self {
+        tracing::error!("database transaction failed: {}", e);
+        ApiError::Generic
+    }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
-use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use chrono_tz::Tz;
 use serde::Deserialize;
 use uuid::Uuid;
 
@@ -29,7 +31,8 @@ pub struct CreateStoreInReceiptPayload {
     pub name: Option<String>,
     pub alias: Option<String>,
     pub branch: Option<String>,
-    pub address: Option<String>
+    pub address: Option<String>,
+    pub tax_rate_id: Option<i32>
 }
 
 impl FormRelationshipModelIdOrName for CreateStoreInReceiptPayload {
@@ -63,19 +66,92 @@ impl FormRelationshipModelIdOrName for CreateProductInReceiptPayload {
     }
 }
 
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum QuantityUnit {
+    #[default]
+    #[serde(rename = "piece")]
+    Piece,
+    #[serde(rename = "kg")]
+    Kilogram,
+    #[serde(rename = "g")]
+    Gram,
+    #[serde(rename = "L")]
+    Litre,
+    #[serde(rename = "mL")]
+    Millilitre
+}
+
+impl QuantityUnit {
+    // the value of the unit column
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Piece => "piece",
+            Self::Kilogram => "kg",
+            Self::Gram => "g",
+            Self::Litre => "L",
+            Self::Millilitre => "mL"
+        }
+    }
+}
+
+// amounts are read as written, "12.30" or 12.30, so they are never rounded by a binary float
+// price is the unit price, the line price is what is printed for the line, price * quantity rounded to the minor units when left out
 #[derive(Deserialize, Clone, Debug)]
 pub struct CreateInventoryInReceiptPayload {
-    pub price: f64,
-    pub quantity: i32,
+    pub price: BigDecimal,
+    pub quantity: BigDecimal,
+    #[serde(default)]
+    pub unit: QuantityUnit,
+    pub line_price: Option<BigDecimal>,
+    #[serde(default)]
+    pub discount: BigDecimal,
+    // left out to use the rate of the store, null for an exempt item
+    #[serde(
+        default, 
+        with = "::serde_with::rust::double_option",
+    )]
+    pub tax_rate_id: Option<Option<i32>>,
     pub product: CreateProductInReceiptPayload
 }
 
+// what the card was charged in its own currency for a receipt paid in a foreign one, the fee is charged on top of the amount
+#[derive(Deserialize, Clone, Debug)]
+pub struct CreateSettlementInReceiptPayload {
+    pub currency: CreateCurrencyInReceiptPayload,
+    pub amount: BigDecimal,
+    #[serde(default)]
+    pub fee: BigDecimal
+}
+
+// "2024-08-01T10:00:00+09:00" keeps its offset, "2024-08-01T10:00:00" is local time of the given or configured time zone
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TransactionDate {
+    Zoned(DateTime<FixedOffset>),
+    Local(NaiveDateTime)
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct CreateReceiptPayload {
     pub transaction_id: Option<Uuid>,
-    pub transaction_date: NaiveDateTime,
-    pub is_inventory_taxed: bool,
+    pub transaction_date: TransactionDate,
+    pub time_zone: Option<Tz>,
+    // accepted from older clients and ignored, whether a receipt is taxed follows the tax rates of its lines
+    pub is_inventory_taxed: Option<bool>,
+    #[serde(default)]
+    pub discount: BigDecimal,
+    #[serde(default)]
+    pub service_charge: BigDecimal,
+    pub printed_total: Option<BigDecimal>,
     pub currency: CreateCurrencyInReceiptPayload,
+    pub settlement: Option<CreateSettlementInReceiptPayload>,
     pub store: CreateStoreInReceiptPayload,
     pub inventories: Vec<CreateInventoryInReceiptPayload>
 }
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CreateTaxRatePayload {
+    pub name: String,
+    pub rate: BigDecimal,
+    pub is_inclusive: bool
+}
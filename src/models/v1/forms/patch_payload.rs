@@ -1,6 +1,9 @@
-use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+use chrono_tz::Tz;
 use serde::Deserialize;
 
+use super::create_payload::{CreateSettlementInReceiptPayload, QuantityUnit, TransactionDate};
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct PatchCurrencyPayload {
     pub name: String
@@ -23,7 +26,19 @@ pub struct PatchStorePayload {
         default, 
         with = "::serde_with::rust::double_option",
     )]
-    pub address: Option<Option<String>>
+    pub address: Option<Option<String>>,
+    #[serde(
+        default, 
+        with = "::serde_with::rust::double_option",
+    )]
+    pub tax_rate_id: Option<Option<i32>>
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PatchTaxRatePayload {
+    pub name: Option<String>,
+    pub rate: Option<BigDecimal>,
+    pub is_inclusive: Option<bool>
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -58,12 +73,44 @@ pub struct PatchProductPayload {
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct PatchInventoryPayload {
-    pub price: Option<f64>,
-    pub quantity: Option<i32>,
+    pub price: Option<BigDecimal>,
+    pub quantity: Option<BigDecimal>,
+    pub unit: Option<QuantityUnit>,
+    // recomputed from the price and the quantity when either changes and it is left out
+    pub line_price: Option<BigDecimal>,
+    pub discount: Option<BigDecimal>,
+    #[serde(
+        default, 
+        with = "::serde_with::rust::double_option",
+    )]
+    pub tax_rate_id: Option<Option<i32>>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct PatchReceiptPayload {
-    pub transaction_date: Option<NaiveDateTime>,
+    pub transaction_date: Option<TransactionDate>,
+    pub time_zone: Option<Tz>,
+    // accepted from older clients and ignored, whether a receipt is taxed follows the tax rates of its lines
     pub is_inventory_taxed: Option<bool>,
+    pub discount: Option<BigDecimal>,
+    pub service_charge: Option<BigDecimal>,
+    #[serde(
+        default, 
+        with = "::serde_with::rust::double_option",
+    )]
+    pub printed_total: Option<Option<BigDecimal>>,
+    #[serde(
+        default, 
+        with = "::serde_with::rust::double_option",
+    )]
+    pub settlement: Option<Option<CreateSettlementInReceiptPayload>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PatchUserSettingPayload {
+    #[serde(
+        default, 
+        with = "::serde_with::rust::double_option",
+    )]
+    pub base_currency_id: Option<Option<i32>>
 }
\ No newline at end of file
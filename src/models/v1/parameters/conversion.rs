@@ -0,0 +1,23 @@
+use axum::{async_trait, extract::{FromRequestParts, Query}, http::{request::Parts, StatusCode}};
+
+use crate::{models::v1::parameters::query_filters::ConversionFilters, mw_auth::SessionUser};
+
+// convert_to of the query with the user of the session, whose base currency "base" stands for
+pub struct Conversion {
+    pub convert_to: Option<String>,
+    pub username: String
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Conversion {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let conversion_filters = Query::<ConversionFilters>::from_request_parts(parts, state).await.map(|q| q.0).unwrap_or_default();
+        let session_user = parts.extensions.get::<SessionUser>().cloned().ok_or(StatusCode::UNAUTHORIZED)?;
+        Ok(Self {
+            convert_to: conversion_filters.convert_to,
+            username: session_user.username
+        })
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Cursor {
+    pub sort: String,
+    pub value: serde_json::Value,
+    pub id: i32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CursorDirection {
+    After,
+    Before
+}
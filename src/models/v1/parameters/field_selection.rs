@@ -0,0 +1,27 @@
+use serde::Deserialize;
+
+// fields=id,transaction_date,store.name keeps only those response fields of any read,
+// include=store,currency (or expand=) embeds only those receipt relations
+#[derive(Deserialize, Debug, Default)]
+pub struct FieldSelection {
+    pub fields: Option<String>,
+    pub include: Option<String>,
+    pub expand: Option<String>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReceiptIncludes {
+    pub currency: bool,
+    pub store: bool,
+    pub inventories: bool
+}
+
+impl Default for ReceiptIncludes {
+    fn default() -> Self {
+        Self {
+            currency: true,
+            store: true,
+            inventories: true
+        }
+    }
+}
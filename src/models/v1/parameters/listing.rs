@@ -0,0 +1,42 @@
+use axum::{async_trait, extract::{FromRequestParts, Query}, http::{request::Parts, StatusCode}, Json};
+
+use crate::models::v1::{
+    errors::api_error::ApiError, 
+    parameters::{field_selection::FieldSelection, pagination::Pagination, query_filters::TimestampFilters, sorting::Sorting}, 
+    responses::response_collection::ResponseCollectionPayload
+};
+
+// the paging, timestamp, sort and field parameters shared by the listings, each falls back to its default when absent
+pub struct Listing {
+    pub pagination: Pagination,
+    pub timestamp_filters: TimestampFilters,
+    pub sorting: Sorting,
+    pub field_selection: FieldSelection
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Listing {
+    type Rejection = (StatusCode, Json<ResponseCollectionPayload<serde_json::Value>>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // a malformed timestamp filter would widen the listing instead of narrowing it, so it is rejected rather than dropped
+        let timestamp_filters = Query::<TimestampFilters>::from_request_parts(parts, state).await.map(|q| q.0).map_err(|e| {
+            tracing::warn!("malformed timestamp filters: {}", e);
+            let payload = ResponseCollectionPayload {
+                data: None,
+                total: None,
+                pagination: None,
+                not_found: None,
+                error: Some(ApiError::InvalidParameter)
+            };
+            (StatusCode::BAD_REQUEST, Json(payload))
+        })?;
+
+        Ok(Self {
+            pagination: Query::<Pagination>::from_request_parts(parts, state).await.map(|q| q.0).unwrap_or_default(),
+            timestamp_filters,
+            sorting: Query::<Sorting>::from_request_parts(parts, state).await.map(|q| q.0).unwrap_or_default(),
+            field_selection: Query::<FieldSelection>::from_request_parts(parts, state).await.map(|q| q.0).unwrap_or_default()
+        })
+    }
+}
@@ -1,2 +1,8 @@
+pub mod conversion;
+pub mod cursor;
+pub mod field_selection;
+pub mod listing;
+pub mod matching;
 pub mod pagination;
-pub mod query_filters;
\ No newline at end of file
+pub mod query_filters;
+pub mod sorting;
\ No newline at end of file
@@ -2,19 +2,31 @@ use serde::Deserialize;
 
 #[derive(Deserialize)]
 pub struct Pagination {
+    #[serde(default = "default_limit")]
     pub limit: i64,
-    pub offset: i64
+    #[serde(default)]
+    pub offset: i64,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub include_total: Option<bool>
 }
 
 pub const DEFAULT_OFFSET: i64 = 0;
 pub const DEFAULT_LIMIT: i64 = 20;
 pub const MAX_LIMIT: i64 = 100;
 
+fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
 impl Default for Pagination {
     fn default() -> Self {
         Self { 
             limit: DEFAULT_LIMIT, 
-            offset: DEFAULT_OFFSET
+            offset: DEFAULT_OFFSET,
+            after: None,
+            before: None,
+            include_total: None
         }
     }
-}
\ No newline at end of file
+}
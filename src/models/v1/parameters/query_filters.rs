@@ -1,16 +1,64 @@
-use chrono::NaiveDate;
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
 use serde::Deserialize;
+use serde_with::{formats::CommaSeparator, serde_as, StringWithSeparator};
+use uuid::Uuid;
 
+use super::matching::MatchMode;
+
+// relative ranges ending today, today itself included
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DatePreset {
+    Today,
+    Yesterday,
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+    ThisYear,
+    LastYear,
+    #[serde(rename = "last_7_days")]
+    Last7Days,
+    #[serde(rename = "last_30_days")]
+    Last30Days,
+    #[serde(rename = "last_90_days")]
+    Last90Days
+}
+
+// id lists are comma separated, e.g. store_ids=1,2,3
+#[serde_as]
 #[derive(Deserialize, Debug)]
 pub struct QueryFilters {
     pub start_date: Option<NaiveDate>,
     pub end_date: Option<NaiveDate>,
+    pub preset: Option<DatePreset>,
+    pub tz: Option<Tz>,
     pub currency: Option<String>,
     pub store_name: Option<String>,
     pub store_alias: Option<String>,
     pub product_name: Option<String>,
     pub product_alias: Option<String>,
-    pub product_brand: Option<String>
+    pub product_brand: Option<String>,
+    pub specification_unit: Option<String>,
+    pub has_alias: Option<bool>,
+    pub taxed: Option<bool>,
+    pub min_price: Option<BigDecimal>,
+    pub max_price: Option<BigDecimal>,
+    pub min_quantity: Option<BigDecimal>,
+    pub max_quantity: Option<BigDecimal>,
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, i32>>")]
+    #[serde(default)]
+    pub product_ids: Option<Vec<i32>>,
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, i32>>")]
+    #[serde(default)]
+    pub store_ids: Option<Vec<i32>>,
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, i32>>")]
+    #[serde(default)]
+    pub currency_ids: Option<Vec<i32>>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<MatchMode>
 }
 
 impl Default for QueryFilters {
@@ -18,25 +66,160 @@ impl Default for QueryFilters {
         Self {
             start_date: None,
             end_date: None,
+            preset: None,
+            tz: None,
             currency: None,
             store_name: None,
             store_alias: None,
             product_name: None,
             product_alias: None,
-            product_brand: None
+            product_brand: None,
+            specification_unit: None,
+            has_alias: None,
+            taxed: None,
+            min_price: None,
+            max_price: None,
+            min_quantity: None,
+            max_quantity: None,
+            product_ids: None,
+            store_ids: None,
+            currency_ids: None,
+            match_mode: None
         }
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct KeywordFilters {
-    pub keyword: Option<String>
+    pub keyword: Option<String>,
+    pub store_id: Option<i32>,
+    pub limit: Option<i64>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<MatchMode>
 }
 
-impl Default for KeywordFilters {
-    fn default() -> Self {
-        Self {
-            keyword: None
+#[derive(Deserialize, Debug, Default)]
+pub struct TimestampFilters {
+    pub created_from: Option<NaiveDateTime>,
+    pub created_to: Option<NaiveDateTime>,
+    pub updated_from: Option<NaiveDateTime>,
+    pub updated_to: Option<NaiveDateTime>
+}
+#[derive(Deserialize, Debug, Default)]
+pub struct ReceiptFilters {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub preset: Option<DatePreset>,
+    pub tz: Option<Tz>,
+    pub store_id: Option<i32>,
+    pub store_name: Option<String>,
+    pub currency: Option<String>,
+    pub is_inventory_taxed: Option<bool>,
+    pub product_id: Option<i32>,
+    pub product_name: Option<String>,
+    pub min_total: Option<BigDecimal>,
+    pub max_total: Option<BigDecimal>,
+    pub transaction_id: Option<Uuid>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<MatchMode>
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportPeriod {
+    Day,
+    Week,
+    #[default]
+    Month,
+    Quarter,
+    Year
+}
+
+impl ReportPeriod {
+    // the field name of date_trunc
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Quarter => "quarter",
+            Self::Year => "year"
         }
     }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ReportFilters {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub preset: Option<DatePreset>,
+    pub tz: Option<Tz>,
+    pub period: Option<ReportPeriod>,
+    pub store_id: Option<i32>
+}
+
+// the keyword is matched on the same text of products as /search
+#[derive(Deserialize, Debug, Default)]
+pub struct UnitPriceReportFilters {
+    pub keyword: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub preset: Option<DatePreset>,
+    pub tz: Option<Tz>,
+    pub store_id: Option<i32>
+}
+
+// product_ids=1,2&quantities=2,0.5&units=piece,kg, quantities and units follow the order of the ids and an item is 1 piece when left out
+#[serde_as]
+#[derive(Deserialize, Debug, Default)]
+pub struct BasketFilters {
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, i32>>")]
+    #[serde(default)]
+    pub product_ids: Option<Vec<i32>>,
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, BigDecimal>>")]
+    #[serde(default)]
+    pub quantities: Option<Vec<BigDecimal>>,
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
+    #[serde(default)]
+    pub units: Option<Vec<String>>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub preset: Option<DatePreset>,
+    pub tz: Option<Tz>
+}
+
+// ids=1,2,3 reads exactly those records instead of a page
+#[serde_as]
+#[derive(Deserialize, Debug, Default)]
+pub struct IdFilters {
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, i32>>")]
+    #[serde(default)]
+    pub ids: Option<Vec<i32>>
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchFilters {
+    pub q: Option<String>,
+    pub limit: Option<i64>
+}
+
+
+// amounts are converted to a currency code, name or alias, "base" is the base currency of the user
+#[derive(Deserialize, Debug, Default)]
+pub struct ConversionFilters {
+    pub convert_to: Option<String>
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ExchangeRateFilters {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>
+}
+
+// the ECB rates are quoted against the euro, the base of a file without one
+#[derive(Deserialize, Debug, Default)]
+pub struct ExchangeRateImportFilters {
+    pub base: Option<String>
 }
\ No newline at end of file
@@ -0,0 +1,188 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Sorting {
+    pub sort: Option<String>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc
+}
+
+impl SortDirection {
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Asc => Self::Desc,
+            Self::Desc => Self::Asc
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortKey<F> {
+    pub field: F,
+    pub direction: SortDirection
+}
+
+pub trait SortField: Sized + Copy + PartialEq + 'static {
+    // the name of every field in sort=
+    const NAMES: &'static [(&'static str, Self)];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES.iter().find(|(n, _)| *n == name).map(|(_, f)| *f)
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAMES.iter().find(|(_, f)| f == self).map(|(n, _)| *n).expect("every sort field should be in NAMES")
+    }
+
+    // keyset pagination cannot seek over nullable columns
+    fn is_seekable(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReceiptSortField {
+    Id,
+    TransactionDate,
+    StoreName,
+    Currency,
+    IsInventoryTaxed,
+    CreatedAt,
+    UpdatedAt
+}
+
+impl SortField for ReceiptSortField {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("id", Self::Id),
+        ("transaction_date", Self::TransactionDate),
+        ("store_name", Self::StoreName),
+        ("currency", Self::Currency),
+        ("is_inventory_taxed", Self::IsInventoryTaxed),
+        ("created_at", Self::CreatedAt),
+        ("updated_at", Self::UpdatedAt)
+    ];
+
+    // the taxed flag is computed from the lines, so it is sorted by page offset only
+    fn is_seekable(&self) -> bool {
+        !matches!(self, Self::IsInventoryTaxed)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StoreSortField {
+    Id,
+    Name,
+    Alias,
+    Branch,
+    CreatedAt,
+    UpdatedAt
+}
+
+impl SortField for StoreSortField {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("id", Self::Id),
+        ("name", Self::Name),
+        ("alias", Self::Alias),
+        ("branch", Self::Branch),
+        ("created_at", Self::CreatedAt),
+        ("updated_at", Self::UpdatedAt)
+    ];
+
+    fn is_seekable(&self) -> bool {
+        !matches!(self, Self::Alias | Self::Branch)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProductSortField {
+    Id,
+    Name,
+    Alias,
+    Brand,
+    CreatedAt,
+    UpdatedAt
+}
+
+impl SortField for ProductSortField {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("id", Self::Id),
+        ("name", Self::Name),
+        ("alias", Self::Alias),
+        ("brand", Self::Brand),
+        ("created_at", Self::CreatedAt),
+        ("updated_at", Self::UpdatedAt)
+    ];
+
+    fn is_seekable(&self) -> bool {
+        !matches!(self, Self::Alias | Self::Brand)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurrencySortField {
+    Id,
+    Name,
+    CreatedAt,
+    UpdatedAt
+}
+
+impl SortField for CurrencySortField {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("id", Self::Id),
+        ("name", Self::Name),
+        ("created_at", Self::CreatedAt),
+        ("updated_at", Self::UpdatedAt)
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InventorySortField {
+    Id,
+    Price,
+    Quantity,
+    ProductName,
+    CreatedAt,
+    UpdatedAt
+}
+
+impl SortField for InventorySortField {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("id", Self::Id),
+        ("price", Self::Price),
+        ("quantity", Self::Quantity),
+        ("product_name", Self::ProductName),
+        ("created_at", Self::CreatedAt),
+        ("updated_at", Self::UpdatedAt)
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CustomizedInventorySortField {
+    Id,
+    Price,
+    Quantity,
+    ProductName,
+    TransactionDate,
+    StoreName,
+    Currency,
+    CreatedAt,
+    UpdatedAt
+}
+
+impl SortField for CustomizedInventorySortField {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("id", Self::Id),
+        ("price", Self::Price),
+        ("quantity", Self::Quantity),
+        ("product_name", Self::ProductName),
+        ("transaction_date", Self::TransactionDate),
+        ("store_name", Self::StoreName),
+        ("currency", Self::Currency),
+        ("created_at", Self::CreatedAt),
+        ("updated_at", Self::UpdatedAt)
+    ];
+}
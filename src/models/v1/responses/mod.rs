@@ -2,4 +2,10 @@ pub mod response_product;
 pub mod response_inventory;
 pub mod response_store;
 pub mod response_currency;
-pub mod response_receipt;
\ No newline at end of file
+pub mod response_receipt;
+pub mod response_collection;
+pub mod response_search;
+pub mod response_tax_rate;
+pub mod response_report;
+pub mod response_exchange_rate;
+pub mod response_user_setting;
\ No newline at end of file
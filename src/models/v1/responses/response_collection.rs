@@ -0,0 +1,33 @@
+use serde::Serialize;
+
+use crate::models::v1::errors::api_error::ApiError;
+
+#[derive(Serialize)]
+pub struct ResponsePaginationLinks {
+    pub first: Option<String>,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    pub last: Option<String>
+}
+
+#[derive(Serialize)]
+pub struct ResponsePagination {
+    pub limit: i64,
+    pub offset: Option<i64>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub links: ResponsePaginationLinks
+}
+
+#[derive(Serialize)]
+pub struct ResponseCollectionPayload<T> {
+    pub data: Option<Vec<T>>,
+    pub total: Option<i64>,
+    pub pagination: Option<ResponsePagination>,
+    // ids requested with ?ids= that do not exist
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_found: Option<Vec<i32>>,
+    pub error: Option<ApiError>
+}
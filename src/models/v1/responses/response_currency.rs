@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use serde::Serialize;
 
 use crate::models::v1::errors::api_error::ApiError;
@@ -5,19 +6,18 @@ use crate::models::v1::errors::api_error::ApiError;
 #[derive(Serialize)]
 pub struct ResponseCurrency {
     pub id: i32,
-    pub name: String
+    pub name: String,
+    pub code: Option<String>,
+    pub symbol: Option<String>,
+    pub display_name: Option<String>,
+    pub minor_units: i32,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
 }
 
-
 #[derive(Serialize)]
 pub struct ResponseCurrencyPayload {
-    pub data: Option<ResponseCurrency>,
-    pub error: Option<ApiError>
-}
-
-#[derive(Serialize)]
-pub struct ResponseCurrenciesPayload {
-    pub data: Option<Vec<ResponseCurrency>>,
-    pub total: Option<i64>,
+    pub data: Option<serde_json::Value>,
     pub error: Option<ApiError>
 }
\ No newline at end of file
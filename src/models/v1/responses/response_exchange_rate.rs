@@ -0,0 +1,64 @@
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+
+use crate::models::v1::errors::api_error::ApiError;
+
+use super::response_receipt::ResponseReceiptTotals;
+
+// 1 unit of the from currency is worth rate units of the to currency
+#[derive(Serialize)]
+pub struct ResponseExchangeRate {
+    pub id: i32,
+    pub rate_date: NaiveDate,
+    pub from_currency_id: i32,
+    pub to_currency_id: i32,
+    pub rate: BigDecimal,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Serialize)]
+pub struct ResponseSkippedExchangeRate {
+    pub line: usize,
+    pub reason: String
+}
+
+// rows are queued to the writer, the skipped ones are never imported
+#[derive(Serialize)]
+pub struct ResponseExchangeRateImport {
+    pub accepted: usize,
+    pub skipped: Vec<ResponseSkippedExchangeRate>
+}
+
+#[derive(Serialize)]
+pub struct ResponseExchangeRateImportPayload {
+    pub data: Option<ResponseExchangeRateImport>,
+    pub error: Option<ApiError>
+}
+
+// amounts are converted with the rate effective on the transaction date, or the one implied by the settled amount when the card was charged in that currency, and padded to the minor units of the converted currency
+#[derive(Serialize)]
+pub struct ResponseConvertedReceipt {
+    pub currency_id: i32,
+    pub currency: String,
+    pub rate: BigDecimal,
+    // the rate is implied by the settled amount instead of a market rate
+    pub is_settled: bool,
+    pub totals: ResponseReceiptTotals
+}
+
+#[derive(Serialize)]
+pub struct ResponseConvertedInventory {
+    pub currency_id: i32,
+    pub currency: String,
+    pub rate: BigDecimal,
+    pub is_settled: bool,
+    pub price: String,
+    pub line_price: String,
+    pub discount: String,
+    pub line_total: String,
+    pub pre_tax_amount: String,
+    pub tax_amount: String,
+    pub post_tax_amount: String
+}
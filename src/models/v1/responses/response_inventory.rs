@@ -1,28 +1,33 @@
 use crate::models::v1::errors::api_error::ApiError;
 
-use super::{response_currency::ResponseCurrency, response_product::ResponseProduct};
-use chrono::NaiveDateTime;
+use super::{response_currency::ResponseCurrency, response_exchange_rate::ResponseConvertedInventory, response_product::ResponseProduct};
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use serde::Serialize;
 
 #[derive(Serialize)]
 pub struct ResponseInventory {
     pub id: i32,
     pub product: ResponseProduct,
-    pub price: f64,
-    pub quantity: i32
+    pub price: String,
+    pub quantity: String,
+    pub unit: String,
+    pub line_price: String,
+    pub discount: String,
+    pub line_total: String,
+    pub tax_rate_id: Option<i32>,
+    pub pre_tax_amount: String,
+    pub tax_amount: String,
+    pub post_tax_amount: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<Option<ResponseConvertedInventory>>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
 }
 
-
 #[derive(Serialize)]
 pub struct ResponseInventoryPayload {
-    pub data: Option<ResponseInventory>,
-    pub error: Option<ApiError>
-}
-
-#[derive(Serialize)]
-pub struct ResponseInventoriesPayload {
-    pub data: Option<Vec<ResponseInventory>>,
-    pub total: Option<i64>,
+    pub data: Option<serde_json::Value>,
     pub error: Option<ApiError>
 }
 
@@ -30,25 +35,42 @@ pub struct ResponseInventoriesPayload {
 pub struct ResponseCustomizedInventory {
     pub id: i32,
     pub product: ResponseProduct,
-    pub price: f64,
+    pub price: String,
+    pub quantity: String,
+    pub unit: String,
+    pub line_price: String,
+    pub discount: String,
+    pub line_total: String,
+    pub tax_rate_id: Option<i32>,
+    pub pre_tax_amount: String,
+    pub tax_amount: String,
+    pub post_tax_amount: String,
+    // None when the unit of the line is not known
+    pub unit_price: Option<ResponseUnitPrice>,
     pub receipt_id: i32,
+    // true when the line has a tax amount
     pub taxed: bool,
-    pub transaction_date: NaiveDateTime,
+    pub transaction_date: DateTime<FixedOffset>,
     pub store_id: i32,
     pub store_name: String,
     pub store_alias: Option<String>,
-    pub currency: ResponseCurrency
+    pub currency: ResponseCurrency,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<Option<ResponseConvertedInventory>>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
 }
 
+// the line total per 100 g, per litre or per piece, with 2 more decimal places than the currency
 #[derive(Serialize)]
-pub struct ResponseCustomizedInventoryPayload {
-    pub data: Option<ResponseCustomizedInventory>,
-    pub error: Option<ApiError>
+pub struct ResponseUnitPrice {
+    pub price: String,
+    pub per: String
 }
 
 #[derive(Serialize)]
-pub struct ResponseCustomizedInventoriesPayload {
-    pub data: Option<Vec<ResponseCustomizedInventory>>,
-    pub total: Option<i64>,
+pub struct ResponseCustomizedInventoryPayload {
+    pub data: Option<serde_json::Value>,
     pub error: Option<ApiError>
 }
\ No newline at end of file
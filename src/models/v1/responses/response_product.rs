@@ -1,3 +1,4 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Serialize;
 
 use crate::models::v1::errors::api_error::ApiError;
@@ -10,18 +11,24 @@ pub struct ResponseProduct {
     pub specification_amount: Option<i32>,
     pub specification_unit: Option<String>,
     pub specification_others: Option<String>,
-    pub brand: Option<String>
+    pub brand: Option<String>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
 }
 
 #[derive(Serialize)]
 pub struct ResponseProductPayload {
-    pub data: Option<ResponseProduct>,
+    pub data: Option<serde_json::Value>,
     pub error: Option<ApiError>
 }
 
 #[derive(Serialize)]
-pub struct ResponseProductsPayload {
-    pub data: Option<Vec<ResponseProduct>>,
-    pub total: Option<i64>,
-    pub error: Option<ApiError>
-}
+pub struct ResponseProductSuggestion {
+    #[serde(flatten)]
+    pub product: ResponseProduct,
+    pub purchase_count: i64,
+    pub last_price: Option<String>,
+    pub last_currency: Option<String>,
+    pub last_purchased_at: Option<DateTime<Utc>>
+}
\ No newline at end of file
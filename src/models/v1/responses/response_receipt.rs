@@ -1,9 +1,10 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use uuid::Uuid;
 use crate::models::v1::errors::api_error::ApiError;
 
 use super::{
     response_currency::ResponseCurrency, 
+    response_exchange_rate::ResponseConvertedReceipt, 
     response_inventory::ResponseInventory, 
     response_store::ResponseStore
 };
@@ -12,24 +13,50 @@ use serde::Serialize;
 #[derive(Serialize)]
 pub struct ResponseReceipt {
     pub id: i32,
-    pub transaction_date: NaiveDateTime,
+    pub transaction_date: DateTime<FixedOffset>,
+    // true when any of the lines has a tax amount
     pub is_inventory_taxed: bool,
+    pub totals: ResponseReceiptTotals,
     pub transaction_id: Option<Uuid>,
-    pub currency: ResponseCurrency,
-    pub store: ResponseStore,
-    pub inventories: Vec<ResponseInventory>
+    pub currency_id: i32,
+    // what the card was charged, padded to the minor units of the settlement currency
+    pub settlement_currency_id: Option<i32>,
+    pub settled_amount: Option<String>,
+    pub settlement_fee: Option<String>,
+    pub store_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<ResponseCurrency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store: Option<ResponseStore>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inventories: Option<Vec<ResponseInventory>>,
+    // only with convert_to, null when no rate is effective on the transaction date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<Option<ResponseConvertedReceipt>>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
 }
 
+// everything but the printed total is computed from the lines, the subtotal is the sum of the line totals as printed
+// amounts are decimal strings padded to the minor units of the currency
 #[derive(Serialize)]
-pub struct ResponseReceiptPayload {
-    pub data: Option<ResponseReceipt>,
-    pub error: Option<ApiError>
+pub struct ResponseReceiptTotals {
+    pub subtotal: String,
+    pub pre_tax: String,
+    pub tax: String,
+    pub post_tax: String,
+    pub discount: String,
+    pub service_charge: String,
+    pub grand_total: String,
+    pub printed_total: Option<String>,
+    pub is_total_mismatched: bool
 }
 
 #[derive(Serialize)]
-pub struct ResponseReceiptsPayload {
-    pub data: Option<Vec<ResponseReceipt>>,
-    pub total: Option<i64>,
+pub struct ResponseReceiptPayload {
+    pub data: Option<serde_json::Value>,
     pub error: Option<ApiError>
 }
 
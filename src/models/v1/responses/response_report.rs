@@ -0,0 +1,87 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::Serialize;
+
+// amounts of different currencies are never added up, so a period has a row per currency unless they are converted to one
+#[derive(Serialize)]
+pub struct ResponseTaxReport {
+    pub period_start: NaiveDate,
+    pub currency_id: i32,
+    pub currency: String,
+    pub receipt_count: i64,
+    pub pre_tax: String,
+    pub tax: String,
+    pub post_tax: String
+}
+
+// unit prices are ranked within a standard unit and a currency, the cheapest on average first
+#[derive(Serialize)]
+pub struct ResponseUnitPriceComparison {
+    pub product_id: i32,
+    pub product_name: String,
+    pub brand: Option<String>,
+    pub store_id: i32,
+    pub store_name: String,
+    pub store_branch: Option<String>,
+    pub currency_id: i32,
+    pub currency: String,
+    pub per: String,
+    pub line_count: i64,
+    pub lowest: String,
+    pub average: String,
+    pub latest: String,
+    pub last_purchased_at: DateTime<FixedOffset>,
+    pub is_cheapest: bool
+}
+
+// a series of a product at a store, the unit prices of a point are of the purchases in its period
+#[derive(Serialize)]
+pub struct ResponsePriceHistory {
+    pub store_id: i32,
+    pub store_name: String,
+    pub store_branch: Option<String>,
+    pub currency_id: i32,
+    pub currency: String,
+    pub per: String,
+    pub points: Vec<ResponsePriceHistoryPoint>
+}
+
+#[derive(Serialize)]
+pub struct ResponsePriceHistoryPoint {
+    pub period_start: NaiveDate,
+    pub line_count: i64,
+    pub min: String,
+    pub max: String,
+    pub mean: String,
+    pub last: String,
+    pub last_purchased_at: DateTime<FixedOffset>
+}
+
+// totals are estimated from the unit prices of the items a store has been seen selling, a store missing any item is never the cheapest
+#[derive(Serialize)]
+pub struct ResponseStoreBasket {
+    pub store_id: i32,
+    pub store_name: String,
+    pub store_branch: Option<String>,
+    pub currency_id: i32,
+    pub currency: String,
+    pub items: Vec<ResponseStoreBasketItem>,
+    pub missing_product_ids: Vec<i32>,
+    pub latest_total: String,
+    pub average_total: String,
+    pub is_cheapest: bool
+}
+
+#[derive(Serialize)]
+pub struct ResponseStoreBasketItem {
+    pub product_id: i32,
+    pub product_name: String,
+    pub quantity: String,
+    pub unit: String,
+    pub per: String,
+    pub line_count: i64,
+    pub latest: String,
+    pub average: String,
+    pub latest_cost: String,
+    pub average_cost: String,
+    pub last_purchased_at: DateTime<FixedOffset>
+}
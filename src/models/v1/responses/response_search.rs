@@ -0,0 +1,20 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchHitType {
+    Product,
+    Store,
+    Receipt
+}
+
+#[derive(Serialize)]
+pub struct ResponseSearchHit {
+    #[serde(rename = "type")]
+    pub hit_type: SearchHitType,
+    pub id: i32,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub score: f32,
+    pub highlight: Option<String>
+}
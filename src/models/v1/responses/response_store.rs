@@ -1,3 +1,4 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Serialize;
 
 use crate::models::v1::errors::api_error::ApiError;
@@ -8,18 +9,23 @@ pub struct ResponseStore {
     pub name: String,
     pub alias: Option<String>,
     pub branch: Option<String>,
-    pub address: Option<String>
+    pub address: Option<String>,
+    pub tax_rate_id: Option<i32>,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
 }
 
 #[derive(Serialize)]
 pub struct ResponseStorePayload {
-    pub data: Option<ResponseStore>,
+    pub data: Option<serde_json::Value>,
     pub error: Option<ApiError>
 }
 
 #[derive(Serialize)]
-pub struct ResponseStoresPayload {
-    pub data: Option<Vec<ResponseStore>>,
-    pub total: Option<i64>,
-    pub error: Option<ApiError>
-}
+pub struct ResponseStoreSuggestion {
+    #[serde(flatten)]
+    pub store: ResponseStore,
+    pub visit_count: i64,
+    pub last_visited_at: Option<DateTime<Utc>>
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::models::v1::errors::api_error::ApiError;
+
+#[derive(Serialize)]
+pub struct ResponseTaxRate {
+    pub id: i32,
+    pub name: String,
+    pub rate: BigDecimal,
+    pub is_inclusive: bool,
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Serialize)]
+pub struct ResponseTaxRatePayload {
+    pub data: Option<ResponseTaxRate>,
+    pub error: Option<ApiError>
+}
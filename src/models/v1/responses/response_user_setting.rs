@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::models::v1::errors::api_error::ApiError;
+
+// a user who has never saved the settings gets the defaults at version 0
+#[derive(Serialize)]
+pub struct ResponseUserSetting {
+    pub username: String,
+    pub base_currency_id: Option<i32>,
+    pub version: i32,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>
+}
+
+#[derive(Serialize)]
+pub struct ResponseUserSettingPayload {
+    pub data: Option<ResponseUserSetting>,
+    pub error: Option<ApiError>
+}
@@ -4,15 +4,24 @@ use tower_cookies::Cookies;
 use axum::response::Response;
 use tracing::info;
 
+// sessions are not stored yet, so every session belongs to the only user
+pub const LOGIN_USERNAME: &str = "test1";
+
+#[derive(Clone, Debug)]
+pub struct SessionUser {
+    pub username: String
+}
+
 pub async fn mw_require_auth(
     cookies: Cookies, 
-    req: Request<Body>, 
+    mut req: Request<Body>, 
     next: Next
 ) -> Result<Response, Error> {
     let session_id = cookies.get(SESSION_ID).map(|c| c.value().to_string());
     info!("MIDDLEAWARE: {:#?}", session_id);
     //TODO: session_id and validation
     session_id.ok_or(Error::AuthFailNoAuthTokenCookie)?;
+    req.extensions_mut().insert(SessionUser { username: LOGIN_USERNAME.to_string() });
     
     Ok(next.run(req).await)
 }
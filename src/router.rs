@@ -1,14 +1,14 @@
 use std::time::Duration;
 
 use axum::{
-    body::Bytes, extract::MatchedPath, http::{HeaderMap, Request}, middleware, response::Response, routing::{delete, get, patch, post}, Router
+    body::Bytes, extract::MatchedPath, http::{HeaderMap, Request}, middleware, response::Response, routing::{delete, get, patch, post, put}, Router
 };
 use tower_cookies::CookieManagerLayer;
 use tower_http::{classify::ServerErrorsFailureClass, trace::TraceLayer};
 use tracing::{info_span, Span};
 
 use crate::{
-    handlers::v1::{currencies::currencies_handlers::CurrenciesHandlers, inventories::{customized_inventories_handlers::CustomizedInventoriesHandlers, inventories_handlers::InventoriesHandlers}, loginout::loginout_handlers::LoginoutHandlers, products::products_handlers::ProductsHandlers, receipts::receipts_handlers::ReceiptsHandlers, stores::stores_handlers::StoresHandlers}, mw_auth, response_mapper::response_mapper, share_state::HandlerState
+    handlers::v1::{currencies::currencies_handlers::CurrenciesHandlers, inventories::{customized_inventories_handlers::CustomizedInventoriesHandlers, inventories_handlers::InventoriesHandlers}, loginout::loginout_handlers::LoginoutHandlers, products::products_handlers::ProductsHandlers, receipts::receipts_handlers::ReceiptsHandlers, search::search_handlers::SearchHandlers, stores::stores_handlers::StoresHandlers, tax_rates::tax_rates_handlers::TaxRatesHandlers, reports::reports_handlers::ReportsHandlers, exchange_rates::exchange_rates_handlers::ExchangeRatesHandlers, user_settings::user_settings_handlers::UserSettingsHandlers}, mw_auth, response_mapper::response_mapper, share_state::HandlerState
 };
 
 pub struct AppRouter {
@@ -21,9 +21,12 @@ impl<'a> AppRouter {
             .route("/receipts/:id", get(ReceiptsHandlers::get_receipt))
             .route("/receipts/transaction/:transaction_id", get(ReceiptsHandlers::get_receipt_by_transaction_id))
             .route("/receipts", get(ReceiptsHandlers::get_receipts))
+            .route("/receipts/trash", get(ReceiptsHandlers::get_trashed_receipts))
             .route("/receipts", post(ReceiptsHandlers::post_receipt))
             .route("/receipts/:id", patch(ReceiptsHandlers::patch_receipt))
+            .route("/receipts/:id", put(ReceiptsHandlers::put_receipt))
             .route("/receipts/:id", delete(ReceiptsHandlers::delete_receipt))
+            .route("/receipts/:id/restore", post(ReceiptsHandlers::restore_receipt))
             .route("/receipts/:id/customized_inventories", get(CustomizedInventoriesHandlers::get_customized_inventories_by_receipt_id));
         
         let v1_stores_router = Router::new()
@@ -45,7 +48,8 @@ impl<'a> AppRouter {
             .route("/products", get(ProductsHandlers::get_products))
             .route("/products/:id", patch(ProductsHandlers::patch_product))
             .route("/products/autocomplete", get(ProductsHandlers::autocomplete_products))
-            .route("/products/:id/customized_inventories", get(CustomizedInventoriesHandlers::get_customized_inventories_by_product_id));
+            .route("/products/:id/customized_inventories", get(CustomizedInventoriesHandlers::get_customized_inventories_by_product_id))
+            .route("/products/:id/price-history", get(ProductsHandlers::get_price_history));
         
         let v1_inventories_router = Router::new()
             .route("/inventories/:id", get(InventoriesHandlers::get_inventory))
@@ -56,6 +60,29 @@ impl<'a> AppRouter {
             .route("/customized_inventories/:id", get(CustomizedInventoriesHandlers::get_customized_inventory))
             .route("/customized_inventories", get(CustomizedInventoriesHandlers::get_customized_inventories));
 
+        let v1_tax_rates_router = Router::new()
+            .route("/tax_rates/:id", get(TaxRatesHandlers::get_tax_rate))
+            .route("/tax_rates", get(TaxRatesHandlers::get_tax_rates))
+            .route("/tax_rates", post(TaxRatesHandlers::post_tax_rate))
+            .route("/tax_rates/:id", patch(TaxRatesHandlers::patch_tax_rate))
+            .route("/tax_rates/:id", delete(TaxRatesHandlers::delete_tax_rate));
+
+        let v1_reports_router = Router::new()
+            .route("/reports/tax", get(ReportsHandlers::get_tax_report))
+            .route("/reports/unit_prices", get(ReportsHandlers::get_unit_price_report))
+            .route("/reports/cheapest_stores", get(ReportsHandlers::get_cheapest_stores));
+
+        let v1_exchange_rates_router = Router::new()
+            .route("/exchange_rates", get(ExchangeRatesHandlers::get_exchange_rates))
+            .route("/exchange_rates/import", post(ExchangeRatesHandlers::import_exchange_rates));
+
+        let v1_user_settings_router = Router::new()
+            .route("/settings", get(UserSettingsHandlers::get_user_setting))
+            .route("/settings", patch(UserSettingsHandlers::patch_user_setting));
+
+        let v1_search_router = Router::new()
+            .route("/search", get(SearchHandlers::search));
+
         let v1_login_router = Router::new()
             .route("/login", post(LoginoutHandlers::api_login));
         
@@ -66,6 +93,11 @@ impl<'a> AppRouter {
             .nest("/api/v1", v1_product_router)
             .nest("/api/v1", v1_inventories_router)
             .nest("/api/v1", v1_customized_inventories_router)
+            .nest("/api/v1", v1_tax_rates_router)
+            .nest("/api/v1", v1_reports_router)
+            .nest("/api/v1", v1_exchange_rates_router)
+            .nest("/api/v1", v1_user_settings_router)
+            .nest("/api/v1", v1_search_router)
             .route_layer(middleware::from_fn(mw_auth::mw_require_auth));
 
         let router = Router::new()
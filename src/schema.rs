@@ -4,6 +4,33 @@ diesel::table! {
     currencies (id) {
         id -> Int4,
         name -> Text,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        minor_units -> Int4,
+        code -> Nullable<Text>,
+        symbol -> Nullable<Text>,
+        display_name -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    currency_aliases (id) {
+        id -> Int4,
+        currency_id -> Int4,
+        alias -> Text,
+    }
+}
+
+diesel::table! {
+    exchange_rates (id) {
+        id -> Int4,
+        rate_date -> Date,
+        from_currency_id -> Int4,
+        to_currency_id -> Int4,
+        rate -> Numeric,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -11,9 +38,18 @@ diesel::table! {
     inventories (id) {
         id -> Int4,
         price -> Numeric,
-        quantity -> Int4,
+        quantity -> Numeric,
         product_id -> Int4,
         receipt_id -> Int4,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        discount -> Numeric,
+        tax_rate_id -> Nullable<Int4>,
+        pre_tax_amount -> Numeric,
+        post_tax_amount -> Numeric,
+        unit -> Text,
+        line_price -> Numeric,
     }
 }
 
@@ -26,17 +62,31 @@ diesel::table! {
         specification_amount -> Nullable<Int4>,
         specification_unit -> Nullable<Text>,
         specification_others -> Nullable<Text>,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        search_text -> Text,
     }
 }
 
 diesel::table! {
     receipts (id) {
         id -> Int4,
-        transaction_date -> Timestamp,
-        is_inventory_taxed -> Bool,
+        transaction_date -> Timestamptz,
         currency_id -> Int4,
         store_id -> Int4,
         transaction_id -> Nullable<Uuid>,
+        deleted_at -> Nullable<Timestamp>,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        transaction_offset -> Int4,
+        discount -> Numeric,
+        service_charge -> Numeric,
+        printed_total -> Nullable<Numeric>,
+        settlement_currency_id -> Nullable<Int4>,
+        settled_amount -> Nullable<Numeric>,
+        settlement_fee -> Nullable<Numeric>,
     }
 }
 
@@ -47,18 +97,53 @@ diesel::table! {
         alias -> Nullable<Text>,
         branch -> Nullable<Text>,
         address -> Nullable<Text>,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        search_text -> Text,
+        tax_rate_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    tax_rates (id) {
+        id -> Int4,
+        name -> Text,
+        rate -> Numeric,
+        is_inclusive -> Bool,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    user_settings (username) {
+        username -> Text,
+        base_currency_id -> Nullable<Int4>,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
+diesel::joinable!(currency_aliases -> currencies (currency_id));
 diesel::joinable!(inventories -> products (product_id));
 diesel::joinable!(inventories -> receipts (receipt_id));
+diesel::joinable!(inventories -> tax_rates (tax_rate_id));
 diesel::joinable!(receipts -> currencies (currency_id));
 diesel::joinable!(receipts -> stores (store_id));
+diesel::joinable!(stores -> tax_rates (tax_rate_id));
+diesel::joinable!(user_settings -> currencies (base_currency_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     currencies,
+    currency_aliases,
+    exchange_rates,
     inventories,
     products,
     receipts,
     stores,
+    tax_rates,
+    user_settings,
 );
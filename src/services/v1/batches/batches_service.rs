@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use axum::{extract::{rejection::QueryRejection, Query}, http::{HeaderMap, StatusCode}, Json};
+
+use crate::{
+    models::v1::{errors::api_error::ApiError, parameters::{pagination::MAX_LIMIT, query_filters::IdFilters}, responses::response_collection::ResponseCollectionPayload}, 
+    services::v1::converters::api_error_converter_service::ApiErrorConventerService
+};
+
+pub struct BatchService {
+}
+
+impl BatchService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    // none when the listing is not a batch read, a malformed ids is rejected instead of falling back to the full listing
+    pub fn get_requested_ids(&self, id_filters: Result<Query<IdFilters>, QueryRejection>) -> Option<Result<Vec<i32>, ApiError>> {
+        match id_filters {
+            Ok(filters) => filters.0.ids.map(Ok),
+            Err(e) => {
+                tracing::warn!("malformed ids: {}", e);
+                Some(Err(ApiError::InvalidParameter))
+            }
+        }
+    }
+
+    pub fn get_batch_response<T>(&self, batch: Result<(Vec<T>, Vec<i32>), ApiError>) -> (StatusCode, HeaderMap, Json<ResponseCollectionPayload<T>>) {
+        match batch {
+            Ok((responses, not_found)) => {
+                let payload = ResponseCollectionPayload {
+                    total: Some(responses.len() as i64),
+                    data: Some(responses),
+                    pagination: None,
+                    not_found: Some(not_found),
+                    error: None
+                };
+                (StatusCode::OK, HeaderMap::new(), Json(payload))
+            },
+            Err(e) => {
+                let api_error_converter_service = ApiErrorConventerService::new();
+                let http_return_code = api_error_converter_service.get_http_status_from_api_error(&e);
+                let payload = ResponseCollectionPayload {
+                    data: None,
+                    total: None,
+                    pagination: None,
+                    not_found: None,
+                    error: Some(e)
+                };
+                (http_return_code, HeaderMap::new(), Json(payload))
+            }
+        }
+    }
+
+    // duplicated ids are read once, a batch is as large as a page at most
+    pub fn get_unique_ids(&self, ids: &[i32]) -> Result<Vec<i32>, ApiError> {
+        let mut unique_ids = vec![];
+        for id in ids {
+            if !unique_ids.contains(id) {
+                unique_ids.push(*id);
+            }
+        }
+
+        if unique_ids.is_empty() || unique_ids.len() as i64 > MAX_LIMIT {
+            tracing::warn!("batch read needs 1 to {} ids, got {}", MAX_LIMIT, unique_ids.len());
+            return Err(ApiError::InvalidParameter);
+        }
+
+        Ok(unique_ids)
+    }
+
+    // keeps the requested order and reports the ids without a record
+    pub fn arrange<T>(&self, ids: &[i32], items: Vec<T>, get_id: impl Fn(&T) -> i32) -> (Vec<T>, Vec<i32>) {
+        let mut items_map = items.into_iter().map(|item| (get_id(&item), item)).collect::<HashMap<i32, T>>();
+        let mut arranged = vec![];
+        let mut not_found = vec![];
+        for id in ids {
+            match items_map.remove(id) {
+                Some(item) => arranged.push(item),
+                None => not_found.push(*id)
+            }
+        }
+
+        (arranged, not_found)
+    }
+}
@@ -0,0 +1 @@
+pub mod batches_service;
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::{models::v1::commands::writer_command::WriterCommand, repository::DbRepository, services::v1::{currencies::currencies_service::CurrencyService, inventories::inventories_service::InventoryService, products::products_service::ProductService, receipts::receipts_service::ReceiptService, stores::stores_service::StoreService}};
+use crate::{models::v1::commands::writer_command::WriterCommand, repository::DbRepository, services::v1::{currencies::currencies_service::CurrencyService, exchange_rates::exchange_rates_service::ExchangeRateService, inventories::inventories_service::InventoryService, products::products_service::ProductService, receipts::receipts_service::ReceiptService, stores::stores_service::StoreService, tax_rates::tax_rates_service::TaxRateService, user_settings::user_settings_service::UserSettingService}};
 
 pub struct CommandService {
 }
@@ -15,38 +15,78 @@ impl CommandService {
                     match result {
                         WriterCommand::CreateReceipt(new_receipt) => {
                             let service = ReceiptService::new(&repository);
-                            tracing::debug!("Start to process create new receipt at date: {}, transaction_id: {:#?}", new_receipt.transaction_date, new_receipt.transaction_id);
+                            tracing::debug!("Start to process create new receipt at date: {:?}, transaction_id: {:#?}", new_receipt.transaction_date, new_receipt.transaction_id);
                             let _ = service.create_receipt(&new_receipt).await;
                         },
-                        WriterCommand::DeleteReceipt(id) => {
+                        WriterCommand::DeleteReceipt(id, expected_version) => {
                             let service = ReceiptService::new(&repository);
                             tracing::debug!("Start to process delete receipt {}", id);
-                            let _ = service.delete_receipt(id).await;
+                            let _ = service.delete_receipt(id, expected_version).await;
                         },
-                        WriterCommand::PatchReceipt(id, patch_receipt) => {
+                        WriterCommand::RestoreReceipt(id) => {
+                            let service = ReceiptService::new(&repository);
+                            tracing::debug!("Start to process restore receipt {}", id);
+                            let _ = service.restore_receipt(id).await;
+                        },
+                        WriterCommand::PurgeTrashedReceipts(retention_days) => {
+                            let service = ReceiptService::new(&repository);
+                            tracing::debug!("Start to process purge receipts trashed more than {} days", retention_days);
+                            let _ = service.purge_expired_receipts(retention_days).await;
+                        },
+                        WriterCommand::PatchReceipt(id, patch_receipt, expected_version) => {
                             let service = ReceiptService::new(&repository);
                             tracing::debug!("Start to process patch receipt {}", id);
-                            let _ = service.patch_receipt(id, &patch_receipt).await;
+                            let _ = service.patch_receipt(id, &patch_receipt, expected_version).await;
+                        },
+                        WriterCommand::PutReceipt(id, put_receipt, expected_version) => {
+                            let service = ReceiptService::new(&repository);
+                            tracing::debug!("Start to process put receipt {}", id);
+                            let _ = service.replace_receipt(id, &put_receipt, expected_version).await;
                         },
-                        WriterCommand::PatchCurrency(id, patch_currency) => {
+                        WriterCommand::PatchCurrency(id, patch_currency, expected_version) => {
                             let service = CurrencyService::new(&repository);
                             tracing::debug!("Start to process patch currency {}", id);
-                            let _ = service.patch_currency(id, &patch_currency).await;
+                            let _ = service.patch_currency(id, &patch_currency, expected_version).await;
                         }, 
-                        WriterCommand::PatchStore(id, patch_store) => {
+                        WriterCommand::PatchStore(id, patch_store, expected_version) => {
                             let service = StoreService::new(&repository);
                             tracing::debug!("Start to process patch store {}", id);
-                            let _ = service.patch_store(id, &patch_store).await;
+                            let _ = service.patch_store(id, &patch_store, expected_version).await;
                         }, 
-                        WriterCommand::PatchProduct(id, patch_product) => {
+                        WriterCommand::PatchProduct(id, patch_product, expected_version) => {
                             let service = ProductService::new(&repository);
                             tracing::debug!("Start to process patch product {}", id);
-                            let _ = service.patch_product(id, &patch_product).await;
+                            let _ = service.patch_product(id, &patch_product, expected_version).await;
                         }, 
-                        WriterCommand::PatchInventory(id, patch_inventory) => {
+                        WriterCommand::PatchInventory(id, patch_inventory, expected_version) => {
                             let service = InventoryService::new(&repository);
                             tracing::debug!("Start to process patch inventory {}", id);
-                            let _ = service.patch_inventory(id, &patch_inventory).await;
+                            let _ = service.patch_inventory(id, &patch_inventory, expected_version).await;
+                        },
+                        WriterCommand::CreateTaxRate(new_tax_rate) => {
+                            let service = TaxRateService::new(&repository);
+                            tracing::debug!("Start to process create new tax rate {}", new_tax_rate.name);
+                            let _ = service.create_tax_rate(&new_tax_rate).await;
+                        },
+                        WriterCommand::PatchTaxRate(id, patch_tax_rate, expected_version) => {
+                            let service = TaxRateService::new(&repository);
+                            tracing::debug!("Start to process patch tax rate {}", id);
+                            let _ = service.patch_tax_rate(id, &patch_tax_rate, expected_version).await;
+                        },
+                        WriterCommand::DeleteTaxRate(id, expected_version) => {
+                            let service = TaxRateService::new(&repository);
+                            tracing::debug!("Start to process delete tax rate {}", id);
+                            let _ = service.delete_tax_rate(id, expected_version).await;
+                        },
+                        WriterCommand::ImportExchangeRates(new_exchange_rates) => {
+                            let service = ExchangeRateService::new(&repository);
+                            tracing::debug!("Start to process import {} exchange rates", new_exchange_rates.len());
+                            let _ = service.import_exchange_rates(&new_exchange_rates).await;
+                        },
+                        WriterCommand::PatchUserSetting(username, patch_user_setting, expected_version) => {
+                            let service = UserSettingService::new(&repository);
+                            tracing::debug!("Start to process patch settings of {}", username);
+                            let _ = service.patch_user_setting(&username, &patch_user_setting, expected_version).await;
                         }
                     }
                 }
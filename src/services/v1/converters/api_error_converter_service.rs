@@ -38,7 +38,32 @@ impl ApiErrorConventerService {
             &ApiError::DeleteReceiptIdNotExisted => StatusCode::GONE,
             &ApiError::DeleteReceiptAssociatedEntryFailed => StatusCode::NOT_ACCEPTABLE,
             &ApiError::DeleteReceiptEntryFailed => StatusCode::UNPROCESSABLE_ENTITY,
-            &ApiError::DeleteReceiptRelatedEntryFailed => StatusCode::GONE
+            &ApiError::DeleteReceiptRelatedEntryFailed => StatusCode::GONE,
+            &ApiError::ReplaceReceiptIdNotExisted => StatusCode::NOT_FOUND,
+            &ApiError::ReplaceReceiptInventoriesFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            &ApiError::RestoreReceiptIdNotExisted => StatusCode::NOT_FOUND,
+            &ApiError::RestoreReceiptFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            &ApiError::PurgeReceiptFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            &ApiError::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            &ApiError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            &ApiError::InvalidSortField => StatusCode::BAD_REQUEST,
+            &ApiError::InvalidCursor => StatusCode::BAD_REQUEST,
+            &ApiError::InvalidDateRange => StatusCode::BAD_REQUEST,
+            &ApiError::InvalidField => StatusCode::BAD_REQUEST,
+            &ApiError::TaxRateIdNotExisted => StatusCode::BAD_REQUEST,
+            &ApiError::TaxRateNameDuplicated => StatusCode::BAD_REQUEST,
+            &ApiError::InsertTaxRateFailed => StatusCode::CONFLICT,
+            &ApiError::UpdateTaxRateFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            &ApiError::TaxRateReferenced => StatusCode::CONFLICT,
+            &ApiError::DeleteTaxRateFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            &ApiError::InvalidAmountPrecision => StatusCode::BAD_REQUEST,
+            &ApiError::InvalidQuantity => StatusCode::BAD_REQUEST,
+            &ApiError::DiscountExceedsLinePrice => StatusCode::BAD_REQUEST,
+            &ApiError::ExchangeRateNotExisted => StatusCode::UNPROCESSABLE_ENTITY,
+            &ApiError::InvalidExchangeRateFile => StatusCode::BAD_REQUEST,
+            &ApiError::ImportExchangeRatesFailed => StatusCode::CONFLICT,
+            &ApiError::BaseCurrencyNotSet => StatusCode::BAD_REQUEST,
+            &ApiError::UpdateUserSettingFailed => StatusCode::UNPROCESSABLE_ENTITY
         }
     }
 }
\ No newline at end of file
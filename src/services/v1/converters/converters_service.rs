@@ -1,7 +1,9 @@
 use std::collections::HashMap;
-use bigdecimal::ToPrimitive;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, FixedOffset};
 
-use crate::models::v1::{entities::{entity_currency::EntityCurrency, entity_inventory::EntityInventory, entity_product::EntityProduct, entity_receipt::EntityReceipt, entity_store::EntityStore}, responses::{response_currency::ResponseCurrency, response_inventory::{ResponseCustomizedInventory, ResponseInventory}, response_product::ResponseProduct, response_receipt::ResponseReceipt, response_store::ResponseStore}};
+use crate::models::v1::{entities::{entity_currency::EntityCurrency, entity_exchange_rate::{EntityConversion, EntityExchangeRate}, entity_inventory::EntityInventory, entity_product::EntityProduct, entity_receipt::EntityReceipt, entity_search::EntitySearchHit, entity_store::EntityStore, entity_tax_rate::EntityTaxRate, entity_total::{EntityReceiptAmounts, EntityReceiptSubtotal, EntityReceiptsAmounts, EntityTaxReport}, entity_unit::{EntityBasketItem, EntityPriceHistory, EntityStoreBasket, EntityUnitPriceComparison}, entity_usage::{EntityProductUsage, EntityStoreUsage}, entity_user_setting::EntityUserSetting}, responses::{response_currency::ResponseCurrency, response_exchange_rate::{ResponseConvertedInventory, ResponseConvertedReceipt, ResponseExchangeRate}, response_inventory::{ResponseCustomizedInventory, ResponseInventory, ResponseUnitPrice}, response_product::{ResponseProduct, ResponseProductSuggestion}, response_receipt::ResponseReceipt, response_report::{ResponsePriceHistory, ResponsePriceHistoryPoint, ResponseStoreBasket, ResponseStoreBasketItem, ResponseTaxReport, ResponseUnitPriceComparison}, response_search::{ResponseSearchHit, SearchHitType}, response_store::{ResponseStore, ResponseStoreSuggestion}, response_tax_rate::ResponseTaxRate, response_user_setting::ResponseUserSetting}};
+use crate::services::v1::{decimals::decimals_service::{DecimalService, DEFAULT_MINOR_UNITS}, totals::totals_service::TotalService, units::units_service::{UnitService, UNIT_PRICE_EXTRA_DIGITS}};
 
 pub struct ConverterService {
 }
@@ -11,7 +13,7 @@ impl ConverterService {
         Self {}
     }
 
-    pub fn convert_to_all_receipt_response(&self, compound_receipts: Vec<(EntityReceipt, EntityCurrency, EntityStore)>, compound_inventories: Vec<(EntityInventory, EntityProduct)>) -> Vec<ResponseReceipt> {
+    pub fn convert_to_all_receipt_response(&self, compound_receipts: Vec<(EntityReceipt, EntityCurrency, EntityStore)>, compound_inventories: Vec<(EntityInventory, EntityProduct)>, settlement_minor_units: &HashMap<i32, i32>) -> Vec<ResponseReceipt> {
         let mut receipts = vec![];
         let mut compound_inventories_hash_map: HashMap<i32, Vec<(EntityInventory, EntityProduct)>> = HashMap::<i32, Vec<(EntityInventory, EntityProduct)>>::new();
         for tuple in compound_inventories {
@@ -24,14 +26,25 @@ impl ConverterService {
         }
         
 
+        let total_service = TotalService::new();
+        let minor_units = compound_receipts.iter().map(|t| (t.0.id, t.1.minor_units)).collect::<HashMap<i32, i32>>();
         for receipt_currency_store in compound_receipts {
             let id = &receipt_currency_store.0.id;
+            let compound_inventories = compound_inventories_hash_map.remove(id).unwrap_or_default();
+            let subtotal = total_service.get_subtotal(*id, &compound_inventories.iter().map(|t| t.0.clone()).collect::<Vec<EntityInventory>>());
+            let receipt_minor_units = receipt_currency_store.1.minor_units;
             receipts.push(
                 self.convert_to_receipt_response(
                     receipt_currency_store.0.clone(), 
-                    receipt_currency_store.1, 
-                    receipt_currency_store.2, 
-                    self.convert_to_all_inventories_response(compound_inventories_hash_map[id].clone())
+                    Some(receipt_currency_store.1), 
+                    Some(receipt_currency_store.2), 
+                    Some(self.convert_to_all_inventories_response(compound_inventories, &minor_units, None)),
+                    EntityReceiptAmounts {
+                        subtotal: &subtotal,
+                        minor_units: receipt_minor_units,
+                        settlement_minor_units: settlement_minor_units.get(id).copied(),
+                        conversion: None
+                    }
                 )
             );
         }
@@ -39,10 +52,37 @@ impl ConverterService {
         receipts
     }
 
-    pub fn convert_to_all_inventories_response(&self, compound_inventories: Vec<(EntityInventory, EntityProduct)>) -> Vec<ResponseInventory> {
+    // relations that were not requested are left as None and skipped in the response, a receipt without any subtotal has no inventories
+    pub fn convert_to_included_receipts_response(&self, receipts: Vec<EntityReceipt>, currencies: Option<HashMap<i32, EntityCurrency>>, stores: Option<HashMap<i32, EntityStore>>, compound_inventories: Option<Vec<(EntityInventory, EntityProduct)>>, amounts: EntityReceiptsAmounts) -> Vec<ResponseReceipt> {
+        let mut inventories_map = compound_inventories.map(|compound_inventories| {
+            let mut inventories_map: HashMap<i32, Vec<(EntityInventory, EntityProduct)>> = receipts.iter().map(|r| (r.id, vec![])).collect();
+            for tuple in compound_inventories {
+                inventories_map.entry(tuple.0.receipt_id).or_default().push(tuple);
+            }
+            inventories_map
+        });
+
+        receipts.into_iter().map(|receipt| {
+            let currency = currencies.as_ref().and_then(|m| m.get(&receipt.currency_id).cloned());
+            let store = stores.as_ref().and_then(|m| m.get(&receipt.store_id).cloned());
+            let inventories = inventories_map.as_mut().map(|m| self.convert_to_all_inventories_response(m.remove(&receipt.id).unwrap_or_default(), &amounts.minor_units, amounts.conversion));
+            let subtotal = amounts.subtotals.get(&receipt.id).cloned().unwrap_or(EntityReceiptSubtotal { receipt_id: receipt.id, ..Default::default() });
+            let receipt_amounts = EntityReceiptAmounts {
+                subtotal: &subtotal,
+                minor_units: amounts.minor_units.get(&receipt.id).copied().unwrap_or(DEFAULT_MINOR_UNITS),
+                settlement_minor_units: amounts.settlement_minor_units.get(&receipt.id).copied(),
+                conversion: amounts.conversion
+            };
+            self.convert_to_receipt_response(receipt, currency, store, inventories, receipt_amounts)
+        }).collect()
+    }
+
+    // the minor units are looked up by the receipt of each inventory
+    pub fn convert_to_all_inventories_response(&self, compound_inventories: Vec<(EntityInventory, EntityProduct)>, minor_units: &HashMap<i32, i32>, conversion: Option<&EntityConversion>) -> Vec<ResponseInventory> {
         let mut inventories = vec![];
         for inventory_product in compound_inventories {
-            inventories.push(self.convert_to_inventory_response(inventory_product.0, inventory_product.1));
+            let inventory_minor_units = minor_units.get(&inventory_product.0.receipt_id).copied().unwrap_or(DEFAULT_MINOR_UNITS);
+            inventories.push(self.convert_to_inventory_response(inventory_product.0, inventory_product.1, inventory_minor_units, conversion));
         }
 
         inventories
@@ -66,7 +106,14 @@ impl ConverterService {
     pub fn convert_to_currency_response(&self, currency: EntityCurrency) -> ResponseCurrency {
         ResponseCurrency {
             id: currency.id,
-            name: currency.name
+            name: currency.name,
+            code: currency.code,
+            symbol: currency.symbol,
+            display_name: currency.display_name,
+            minor_units: currency.minor_units,
+            version: currency.version,
+            created_at: currency.created_at,
+            updated_at: currency.updated_at
         }
     }
 
@@ -76,7 +123,11 @@ impl ConverterService {
             name: store.name,
             alias: store.alias,
             branch: store.branch,
-            address: store.address
+            address: store.address,
+            tax_rate_id: store.tax_rate_id,
+            version: store.version,
+            created_at: store.created_at,
+            updated_at: store.updated_at
         }
     }
 
@@ -88,68 +139,382 @@ impl ConverterService {
             specification_amount: product.specification_amount,
             specification_others: product.specification_others,
             specification_unit: product.specification_unit,
-            brand: product.brand
+            brand: product.brand,
+            version: product.version,
+            created_at: product.created_at,
+            updated_at: product.updated_at
         }
     }
 
-    pub fn convert_to_inventory_response(&self, inventory: EntityInventory, product: EntityProduct) -> ResponseInventory {
+    pub fn convert_to_inventory_response(&self, inventory: EntityInventory, product: EntityProduct, minor_units: i32, conversion: Option<&EntityConversion>) -> ResponseInventory {
+        let decimal_service = DecimalService::new();
+        let line_total = TotalService::new().get_line_total(&inventory.line_price, &inventory.discount);
+        let converted = self.convert_to_converted_inventory_response(&inventory, conversion);
         ResponseInventory {
             id: inventory.id,
-            price: inventory.price.to_f64().unwrap(),
-            quantity: inventory.quantity,
-            product: self.convert_to_product_response(product)
+            price: decimal_service.format(&inventory.price, minor_units),
+            quantity: decimal_service.normalize(&inventory.quantity).to_string(),
+            unit: inventory.unit,
+            line_price: decimal_service.format(&inventory.line_price, minor_units),
+            discount: decimal_service.format(&inventory.discount, minor_units),
+            line_total: decimal_service.format(&line_total, minor_units),
+            tax_rate_id: inventory.tax_rate_id,
+            pre_tax_amount: decimal_service.format(&inventory.pre_tax_amount, minor_units),
+            tax_amount: decimal_service.format(&(&inventory.post_tax_amount - &inventory.pre_tax_amount), minor_units),
+            post_tax_amount: decimal_service.format(&inventory.post_tax_amount, minor_units),
+            converted,
+            product: self.convert_to_product_response(product),
+            version: inventory.version,
+            created_at: inventory.created_at,
+            updated_at: inventory.updated_at
         }
     }
 
-    pub fn convert_to_receipt_response(&self, receipt: EntityReceipt, currency: EntityCurrency, store: EntityStore, inventories: Vec<ResponseInventory>) -> ResponseReceipt {
+    pub fn convert_to_receipt_response(&self, receipt: EntityReceipt, currency: Option<EntityCurrency>, store: Option<EntityStore>, inventories: Option<Vec<ResponseInventory>>, amounts: EntityReceiptAmounts) -> ResponseReceipt {
+        let decimal_service = DecimalService::new();
+        let subtotal = amounts.subtotal;
+        let settlement_minor_units = amounts.settlement_minor_units.unwrap_or(DEFAULT_MINOR_UNITS);
+        let converted = self.convert_to_converted_receipt_response(&receipt, subtotal, amounts.conversion);
         let response_receipt = ResponseReceipt {
             id: receipt.id,
-            transaction_date: receipt.transaction_date,
+            transaction_date: self.convert_to_transaction_date(&receipt),
             transaction_id: receipt.transaction_id,
-            is_inventory_taxed: receipt.is_inventory_taxed,
-            currency: self.convert_to_currency_response(currency),
-            store: self.convert_to_store_response(store),
-            inventories
+            is_inventory_taxed: subtotal.post_tax != subtotal.pre_tax,
+            totals: TotalService::new().get_receipt_totals(&receipt, subtotal, amounts.minor_units),
+            currency_id: receipt.currency_id,
+            settlement_currency_id: receipt.settlement_currency_id,
+            settled_amount: receipt.settled_amount.as_ref().map(|amount| decimal_service.format(amount, settlement_minor_units)),
+            settlement_fee: receipt.settlement_fee.as_ref().map(|fee| decimal_service.format(fee, settlement_minor_units)),
+            store_id: receipt.store_id,
+            currency: currency.map(|c| self.convert_to_currency_response(c)),
+            store: store.map(|s| self.convert_to_store_response(s)),
+            inventories,
+            converted,
+            deleted_at: receipt.deleted_at,
+            version: receipt.version,
+            created_at: receipt.created_at,
+            updated_at: receipt.updated_at
         };
 
         response_receipt
     }
 
-    pub fn convert_to_customized_inventory_response(&self, inventory: EntityInventory, product: EntityProduct, receipt: EntityReceipt, store: EntityStore, currency: EntityCurrency) -> ResponseCustomizedInventory {
+    // None when nothing is converted, Some(None) when no rate is effective on the transaction date
+    pub fn convert_to_converted_receipt_response(&self, receipt: &EntityReceipt, subtotal: &EntityReceiptSubtotal, conversion: Option<&EntityConversion>) -> Option<Option<ResponseConvertedReceipt>> {
+        conversion.map(|conversion| conversion.rates.get(&receipt.id).map(|rate| ResponseConvertedReceipt {
+            currency_id: conversion.currency.id,
+            currency: self.convert_to_currency_label(&conversion.currency),
+            rate: self.convert_to_rate(rate),
+            is_settled: conversion.settled_receipt_ids.contains(&receipt.id),
+            totals: TotalService::new().get_converted_receipt_totals(receipt, subtotal, rate, conversion.currency.minor_units)
+        }))
+    }
+
+    pub fn convert_to_converted_inventory_response(&self, inventory: &EntityInventory, conversion: Option<&EntityConversion>) -> Option<Option<ResponseConvertedInventory>> {
+        let decimal_service = DecimalService::new();
+        conversion.map(|conversion| conversion.rates.get(&inventory.receipt_id).map(|rate| {
+            let minor_units = conversion.currency.minor_units;
+            let line_total = TotalService::new().get_line_total(&inventory.line_price, &inventory.discount);
+            ResponseConvertedInventory {
+                currency_id: conversion.currency.id,
+                currency: self.convert_to_currency_label(&conversion.currency),
+                rate: self.convert_to_rate(rate),
+                is_settled: conversion.settled_receipt_ids.contains(&inventory.receipt_id),
+                price: decimal_service.format(&(&inventory.price * rate), minor_units),
+                line_price: decimal_service.format(&(&inventory.line_price * rate), minor_units),
+                discount: decimal_service.format(&(&inventory.discount * rate), minor_units),
+                line_total: decimal_service.format(&(line_total * rate), minor_units),
+                pre_tax_amount: decimal_service.format(&(&inventory.pre_tax_amount * rate), minor_units),
+                tax_amount: decimal_service.format(&((&inventory.post_tax_amount - &inventory.pre_tax_amount) * rate), minor_units),
+                post_tax_amount: decimal_service.format(&(&inventory.post_tax_amount * rate), minor_units)
+            }
+        }))
+    }
+
+    pub fn convert_to_unit_price_response(&self, inventory: &EntityInventory, product: &EntityProduct, line_total: &BigDecimal, minor_units: i32) -> Option<ResponseUnitPrice> {
+        let unit_service = UnitService::new();
+        unit_service.get_standard_quantity(inventory, product).map(|standard_quantity| ResponseUnitPrice {
+            price: DecimalService::new().format(&unit_service.get_unit_price(line_total, &standard_quantity), minor_units + UNIT_PRICE_EXTRA_DIGITS),
+            per: standard_quantity.unit.name().to_string()
+        })
+    }
+
+    // the code of an ISO currency, the name of any other
+    pub fn convert_to_currency_label(&self, currency: &EntityCurrency) -> String {
+        currency.code.clone().unwrap_or(currency.name.clone())
+    }
+
+    // a crossed rate is a long division, 12 digits are as many as a stored rate has
+    pub fn convert_to_rate(&self, rate: &BigDecimal) -> BigDecimal {
+        DecimalService::new().normalize(&rate.round(12))
+    }
+
+    // shows the instant in the offset where the receipt was issued
+    pub fn convert_to_transaction_date(&self, receipt: &EntityReceipt) -> DateTime<FixedOffset> {
+        let offset = FixedOffset::east_opt(receipt.transaction_offset).unwrap_or(FixedOffset::east_opt(0).expect("zero offset should always be valid"));
+        receipt.transaction_date.with_timezone(&offset)
+    }
+
+    pub fn convert_to_customized_inventory_response(&self, inventory: EntityInventory, product: EntityProduct, receipt: EntityReceipt, store: EntityStore, currency: EntityCurrency, conversion: Option<&EntityConversion>) -> ResponseCustomizedInventory {
+        let decimal_service = DecimalService::new();
+        let line_total = TotalService::new().get_line_total(&inventory.line_price, &inventory.discount);
+        let converted = self.convert_to_converted_inventory_response(&inventory, conversion);
+        let unit_price = self.convert_to_unit_price_response(&inventory, &product, &line_total, currency.minor_units);
         let customized_inventory = ResponseCustomizedInventory {
             id: inventory.id,
             product: self.convert_to_product_response(product),
-            price: inventory.price.to_f64().unwrap(),
+            price: decimal_service.format(&inventory.price, currency.minor_units),
+            quantity: decimal_service.normalize(&inventory.quantity).to_string(),
+            unit: inventory.unit,
+            line_price: decimal_service.format(&inventory.line_price, currency.minor_units),
+            discount: decimal_service.format(&inventory.discount, currency.minor_units),
+            line_total: decimal_service.format(&line_total, currency.minor_units),
+            tax_rate_id: inventory.tax_rate_id,
+            pre_tax_amount: decimal_service.format(&inventory.pre_tax_amount, currency.minor_units),
+            tax_amount: decimal_service.format(&(&inventory.post_tax_amount - &inventory.pre_tax_amount), currency.minor_units),
+            post_tax_amount: decimal_service.format(&inventory.post_tax_amount, currency.minor_units),
+            unit_price,
             receipt_id: receipt.id,
-            taxed: receipt.is_inventory_taxed,
-            transaction_date: receipt.transaction_date,
+            taxed: inventory.post_tax_amount != inventory.pre_tax_amount,
+            transaction_date: self.convert_to_transaction_date(&receipt),
             store_id: store.id,
             store_name: store.name,
             store_alias: store.alias,
-            currency: self.convert_to_currency_response(currency)
+            currency: self.convert_to_currency_response(currency),
+            converted,
+            version: inventory.version,
+            created_at: inventory.created_at,
+            updated_at: inventory.updated_at
         };
 
         customized_inventory
     }
 
-    pub fn convert_to_customized_inventories_response(&self, compound_inventories: Vec<(EntityInventory, EntityProduct)>, compound_receipts: Vec<(EntityReceipt, EntityStore, EntityCurrency)>) -> Vec<ResponseCustomizedInventory> {
+    pub fn convert_to_customized_inventories_response(&self, compound_inventories: Vec<(EntityInventory, EntityProduct)>, compound_receipts: Vec<(EntityReceipt, EntityStore, EntityCurrency)>, conversion: Option<&EntityConversion>) -> Vec<ResponseCustomizedInventory> {
+        let decimal_service = DecimalService::new();
         let receipt_tuple_hash_map = compound_receipts.into_iter().map(|t| (t.0.id, t)).collect::<HashMap<i32, (EntityReceipt, EntityStore, EntityCurrency)>>();
+        let total_service = TotalService::new();
         let customized_inventories = compound_inventories.into_iter().map(|t| {
             let compound_receipt = receipt_tuple_hash_map.get(&t.0.receipt_id).unwrap();
+            let line_total = total_service.get_line_total(&t.0.line_price, &t.0.discount);
+            let converted = self.convert_to_converted_inventory_response(&t.0, conversion);
+            let unit_price = self.convert_to_unit_price_response(&t.0, &t.1, &line_total, compound_receipt.2.minor_units);
             ResponseCustomizedInventory {
                 id: t.0.id,
                 product: self.convert_to_product_response(t.1),
-                price: t.0.price.to_f64().unwrap(),
+                price: decimal_service.format(&t.0.price, compound_receipt.2.minor_units),
+                quantity: decimal_service.normalize(&t.0.quantity).to_string(),
+                unit: t.0.unit,
+                line_price: decimal_service.format(&t.0.line_price, compound_receipt.2.minor_units),
+                discount: decimal_service.format(&t.0.discount, compound_receipt.2.minor_units),
+                line_total: decimal_service.format(&line_total, compound_receipt.2.minor_units),
+                tax_rate_id: t.0.tax_rate_id,
+                pre_tax_amount: decimal_service.format(&t.0.pre_tax_amount, compound_receipt.2.minor_units),
+                tax_amount: decimal_service.format(&(&t.0.post_tax_amount - &t.0.pre_tax_amount), compound_receipt.2.minor_units),
+                post_tax_amount: decimal_service.format(&t.0.post_tax_amount, compound_receipt.2.minor_units),
+                unit_price,
                 receipt_id: t.0.receipt_id,
-                taxed: compound_receipt.0.is_inventory_taxed,
-                transaction_date: compound_receipt.0.transaction_date,
+                taxed: t.0.post_tax_amount != t.0.pre_tax_amount,
+                transaction_date: self.convert_to_transaction_date(&compound_receipt.0),
                 store_id: compound_receipt.0.id,
                 store_name: compound_receipt.1.name.clone(),
                 store_alias: compound_receipt.1.alias.clone(),
-                currency: self.convert_to_currency_response(compound_receipt.2.clone())
+                currency: self.convert_to_currency_response(compound_receipt.2.clone()),
+                converted,
+                version: t.0.version,
+                created_at: t.0.created_at,
+                updated_at: t.0.updated_at
             }
         }).collect();
 
         customized_inventories
     }
-}
\ No newline at end of file
+
+    pub fn convert_to_search_hit_response(&self, hit: EntitySearchHit, highlight: Option<String>) -> ResponseSearchHit {
+        let hit_type = match hit.kind.as_str() {
+            "store" => SearchHitType::Store,
+            "receipt" => SearchHitType::Receipt,
+            _ => SearchHitType::Product
+        };
+
+        ResponseSearchHit {
+            hit_type,
+            id: hit.id,
+            title: hit.title,
+            subtitle: hit.subtitle,
+            score: hit.score,
+            highlight
+        }
+    }
+
+    pub fn convert_to_product_suggestion_response(&self, product: EntityProduct, usage: EntityProductUsage) -> ResponseProductSuggestion {
+        let decimal_service = DecimalService::new();
+        ResponseProductSuggestion {
+            product: self.convert_to_product_response(product),
+            purchase_count: usage.purchase_count,
+            last_price: usage.last_price.map(|p| decimal_service.format(&p, usage.last_minor_units.unwrap_or(DEFAULT_MINOR_UNITS))),
+            last_currency: usage.last_currency,
+            last_purchased_at: usage.last_purchased_at
+        }
+    }
+
+    pub fn convert_to_store_suggestion_response(&self, store: EntityStore, usage: EntityStoreUsage) -> ResponseStoreSuggestion {
+        ResponseStoreSuggestion {
+            store: self.convert_to_store_response(store),
+            visit_count: usage.visit_count,
+            last_visited_at: usage.last_visited_at
+        }
+    }
+
+    pub fn convert_to_tax_rate_response(&self, tax_rate: EntityTaxRate) -> ResponseTaxRate {
+        let decimal_service = DecimalService::new();
+        ResponseTaxRate {
+            id: tax_rate.id,
+            name: tax_rate.name,
+            rate: decimal_service.normalize(&tax_rate.rate),
+            is_inclusive: tax_rate.is_inclusive,
+            version: tax_rate.version,
+            created_at: tax_rate.created_at,
+            updated_at: tax_rate.updated_at
+        }
+    }
+
+    pub fn convert_to_all_tax_rates_response(&self, entity_tax_rates: Vec<EntityTaxRate>) -> Vec<ResponseTaxRate> {
+        entity_tax_rates.into_iter().map(|et| self.convert_to_tax_rate_response(et)).collect()
+    }
+
+    pub fn convert_to_tax_report_response(&self, report: EntityTaxReport) -> ResponseTaxReport {
+        let decimal_service = DecimalService::new();
+        ResponseTaxReport {
+            period_start: report.period_start,
+            currency_id: report.currency_id,
+            currency: report.currency,
+            receipt_count: report.receipt_count,
+            pre_tax: decimal_service.format(&report.pre_tax, report.minor_units),
+            tax: decimal_service.format(&(&report.post_tax - &report.pre_tax), report.minor_units),
+            post_tax: decimal_service.format(&report.post_tax, report.minor_units)
+        }
+    }
+
+    pub fn convert_to_unit_price_comparison_response(&self, comparison: EntityUnitPriceComparison, is_cheapest: bool) -> ResponseUnitPriceComparison {
+        let decimal_service = DecimalService::new();
+        let digits = comparison.currency.minor_units + UNIT_PRICE_EXTRA_DIGITS;
+        let average = comparison.stats.average();
+        ResponseUnitPriceComparison {
+            product_id: comparison.product.id,
+            product_name: comparison.product.name,
+            brand: comparison.product.brand,
+            store_id: comparison.store.id,
+            store_name: comparison.store.name,
+            store_branch: comparison.store.branch,
+            currency_id: comparison.currency.id,
+            currency: self.convert_to_currency_label(&comparison.currency),
+            per: comparison.unit.name().to_string(),
+            line_count: comparison.stats.line_count,
+            lowest: decimal_service.format(&comparison.stats.lowest, digits),
+            average: decimal_service.format(&average, digits),
+            latest: decimal_service.format(&comparison.stats.latest, digits),
+            last_purchased_at: comparison.stats.last_purchased_at,
+            is_cheapest
+        }
+    }
+
+    pub fn convert_to_price_history_response(&self, history: EntityPriceHistory) -> ResponsePriceHistory {
+        let decimal_service = DecimalService::new();
+        let digits = history.currency.minor_units + UNIT_PRICE_EXTRA_DIGITS;
+        ResponsePriceHistory {
+            store_id: history.store.id,
+            store_name: history.store.name,
+            store_branch: history.store.branch,
+            currency_id: history.currency.id,
+            currency: self.convert_to_currency_label(&history.currency),
+            per: history.unit.name().to_string(),
+            points: history.points.into_iter().map(|(period_start, stats)| ResponsePriceHistoryPoint {
+                period_start,
+                line_count: stats.line_count,
+                min: decimal_service.format(&stats.lowest, digits),
+                max: decimal_service.format(&stats.highest, digits),
+                mean: decimal_service.format(&stats.average(), digits),
+                last: decimal_service.format(&stats.latest, digits),
+                last_purchased_at: stats.last_purchased_at
+            }).collect()
+        }
+    }
+
+    // the costs of an item are its unit prices at the store times its quantity
+    pub fn convert_to_store_basket_response(&self, basket: EntityStoreBasket, items: &[EntityBasketItem], is_cheapest: bool) -> ResponseStoreBasket {
+        let decimal_service = DecimalService::new();
+        let unit_service = UnitService::new();
+        let minor_units = basket.currency.minor_units;
+        let digits = minor_units + UNIT_PRICE_EXTRA_DIGITS;
+        let mut basket_items = vec![];
+        let mut missing_product_ids = vec![];
+        for item in items {
+            match basket.prices.get(&item.product.id) {
+                Some(stats) => basket_items.push(ResponseStoreBasketItem {
+                    product_id: item.product.id,
+                    product_name: item.product.name.clone(),
+                    quantity: decimal_service.normalize(&item.quantity).to_string(),
+                    unit: item.unit.clone(),
+                    per: item.standard_quantity.unit.name().to_string(),
+                    line_count: stats.line_count,
+                    latest: decimal_service.format(&stats.latest, digits),
+                    average: decimal_service.format(&stats.average(), digits),
+                    latest_cost: decimal_service.format(&unit_service.get_cost(&stats.latest, &item.standard_quantity), minor_units),
+                    average_cost: decimal_service.format(&unit_service.get_cost(&stats.average(), &item.standard_quantity), minor_units),
+                    last_purchased_at: stats.last_purchased_at
+                }),
+                None => missing_product_ids.push(item.product.id)
+            }
+        }
+
+        ResponseStoreBasket {
+            store_id: basket.store.id,
+            store_name: basket.store.name,
+            store_branch: basket.store.branch,
+            currency_id: basket.currency.id,
+            currency: self.convert_to_currency_label(&basket.currency),
+            items: basket_items,
+            missing_product_ids,
+            latest_total: decimal_service.format(&basket.latest_total, minor_units),
+            average_total: decimal_service.format(&basket.average_total, minor_units),
+            is_cheapest
+        }
+    }
+
+    pub fn convert_to_exchange_rate_response(&self, exchange_rate: EntityExchangeRate) -> ResponseExchangeRate {
+        ResponseExchangeRate {
+            id: exchange_rate.id,
+            rate_date: exchange_rate.rate_date,
+            from_currency_id: exchange_rate.from_currency_id,
+            to_currency_id: exchange_rate.to_currency_id,
+            rate: self.convert_to_rate(&exchange_rate.rate),
+            created_at: exchange_rate.created_at,
+            updated_at: exchange_rate.updated_at
+        }
+    }
+
+    pub fn convert_to_all_exchange_rates_response(&self, entity_exchange_rates: Vec<EntityExchangeRate>) -> Vec<ResponseExchangeRate> {
+        entity_exchange_rates.into_iter().map(|ee| self.convert_to_exchange_rate_response(ee)).collect()
+    }
+
+    // a user without any saved settings gets the defaults
+    pub fn convert_to_user_setting_response(&self, username: &str, user_setting: Option<EntityUserSetting>) -> ResponseUserSetting {
+        match user_setting {
+            Some(user_setting) => ResponseUserSetting {
+                username: user_setting.username,
+                base_currency_id: user_setting.base_currency_id,
+                version: user_setting.version,
+                created_at: Some(user_setting.created_at),
+                updated_at: Some(user_setting.updated_at)
+            },
+            None => ResponseUserSetting {
+                username: username.to_string(),
+                base_currency_id: None,
+                version: 0,
+                created_at: None,
+                updated_at: None
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::HashMap;
+use chrono::NaiveDateTime;
 use diesel::{
-    dsl::{count, exists, select}, insert_into, ExpressionMethods, QueryDsl, RunQueryDsl, SaveChangesDsl, SelectableHelper, TextExpressionMethods
+    dsl::{count, exists, select}, insert_into, pg::Pg, update, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, NullableExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, PgTextExpressionMethods, SelectableHelper
 };
+use serde_json::json;
 
-use crate::{models::v1::{collections::service_collection::ServiceCollection, entities::entity_currency::{EntityCurrency, NewEntityCurrency, UpdateEntityCurrency}, errors::api_error::ApiError, forms::patch_payload::PatchCurrencyPayload, parameters::pagination::Pagination, responses::response_currency::ResponseCurrency}, repository::DbRepository, schema::currencies, services::v1::{converters::converters_service::ConverterService, fallbacks::fallbacks_service::FallbacksService}};
+use crate::{models::v1::{collections::service_collection::ServiceCollection, entities::entity_currency::{EntityCurrency, NewEntityCurrency, UpdateEntityCurrency}, errors::api_error::ApiError, forms::patch_payload::PatchCurrencyPayload, parameters::{cursor::CursorDirection, matching::MatchMode, pagination::{Pagination, DEFAULT_LIMIT, MAX_LIMIT}, query_filters::{KeywordFilters, TimestampFilters}, sorting::{CurrencySortField, SortDirection, SortKey, Sorting}}, responses::response_currency::ResponseCurrency}, repository::DbRepository, schema::{currencies, currency_aliases, receipts}, services::v1::{batches::batches_service::BatchService, converters::converters_service::ConverterService, cursors::cursors_service::CursorService, fallbacks::fallbacks_service::FallbacksService, matchers::matchers_service::MatcherService, sortings::sortings_service::SortingService}};
 
 pub struct CurrencyService<'a> {
     repository: &'a DbRepository
@@ -36,30 +39,111 @@ impl<'a> CurrencyService<'a> {
         Ok(currency_response)
     }
 
-    pub async fn get_currencies(&self, pagination: &Pagination) -> Result<ServiceCollection<ResponseCurrency>, ApiError> {
+    pub async fn get_currencies_by_ids(&self, ids: &[i32]) -> Result<(Vec<ResponseCurrency>, Vec<i32>), ApiError> {
+        let converter = ConverterService::new();
+        let batch_service = BatchService::new();
+        let ids = batch_service.get_unique_ids(ids)?;
+        let conn = &mut self.repository.pool.get().or_else(|e| {
+            tracing::error!("database connection broken: {}", e);
+            Err(ApiError::DatabaseConnectionBroken)
+        })?;
+
+        let currencies_query = 
+            currencies::table
+            .filter(currencies::id.eq_any(&ids))
+            .select(<EntityCurrency>::as_select());
+
+        let currencies_list = currencies_query.get_results::<EntityCurrency>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (currencies_list, not_found) = batch_service.arrange(&ids, currencies_list, |c| c.id);
+
+        Ok((converter.convert_to_all_currencies_response(currencies_list), not_found))
+    }
+
+    pub async fn get_currencies(&self, pagination: &Pagination, timestamp_filters: &TimestampFilters, sorting: &Sorting) -> Result<ServiceCollection<ResponseCurrency>, ApiError> {
         let converter: ConverterService = ConverterService::new();
         let fallbacks_service = FallbacksService::new();
+        let sorting_service = SortingService::new();
+        let sort_keys = sorting_service.parse_sort::<CurrencySortField>(sorting)?;
+        let primary_sort_key = sort_keys.first().copied().unwrap_or(SortKey { field: CurrencySortField::Id, direction: SortDirection::Asc });
+        let cursor_service = CursorService::new();
+        let cursor = cursor_service.get_cursor(pagination, &sort_keys, &primary_sort_key)?;
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let count: i64 = currencies::table.select(count(currencies::columns::id)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let build_query = || {
+            let mut query = currencies::table.into_boxed::<Pg>();
+            if let Some(created_from) = timestamp_filters.created_from {
+                query = query.filter(currencies::created_at.ge(created_from));
+            }
+            if let Some(created_to) = timestamp_filters.created_to {
+                query = query.filter(currencies::created_at.le(created_to));
+            }
+            if let Some(updated_from) = timestamp_filters.updated_from {
+                query = query.filter(currencies::updated_at.ge(updated_from));
+            }
+            if let Some(updated_to) = timestamp_filters.updated_to {
+                query = query.filter(currencies::updated_at.le(updated_to));
+            }
+            query
+        };
+
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(build_query().select(count(currencies::columns::id)).first::<i64>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
         
         let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
-        let currencies_in_this_page_query = 
-            currencies::table
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<EntityCurrency>::as_select());
+        let mut currencies_in_this_page_query = build_query();
+        if let Some((cursor, cursor_direction)) = &cursor {
+            let greater = cursor_service.is_seeking_greater(*cursor_direction, primary_sort_key.direction);
+            currencies_in_this_page_query = match primary_sort_key.field {
+                CurrencySortField::Id => cursor_service.seek(currencies_in_this_page_query, currencies::id, cursor_service.get_cursor_value::<i32>(cursor)?, currencies::id, cursor.id, greater),
+                CurrencySortField::Name => cursor_service.seek(currencies_in_this_page_query, currencies::name, cursor_service.get_cursor_value::<String>(cursor)?, currencies::id, cursor.id, greater),
+                CurrencySortField::CreatedAt => cursor_service.seek(currencies_in_this_page_query, currencies::created_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, currencies::id, cursor.id, greater),
+                CurrencySortField::UpdatedAt => cursor_service.seek(currencies_in_this_page_query, currencies::updated_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, currencies::id, cursor.id, greater)
+            };
+        }
+
+        // rows before the cursor are read in the reversed order and flipped back by paginate
+        let is_before = matches!(cursor, Some((_, CursorDirection::Before)));
+        for sort_key in &sort_keys {
+            let direction = if is_before { sort_key.direction.reverse() } else { sort_key.direction };
+            currencies_in_this_page_query = match sort_key.field {
+                CurrencySortField::Id => sorting_service.order_by(currencies_in_this_page_query, currencies::id, direction),
+                CurrencySortField::Name => sorting_service.order_by(currencies_in_this_page_query, currencies::name, direction),
+                CurrencySortField::CreatedAt => sorting_service.order_by(currencies_in_this_page_query, currencies::created_at, direction),
+                CurrencySortField::UpdatedAt => sorting_service.order_by(currencies_in_this_page_query, currencies::updated_at, direction)
+            };
+        }
+        currencies_in_this_page_query = sorting_service.order_by_id(currencies_in_this_page_query, currencies::id, primary_sort_key.direction, is_before);
 
-        let currencies_in_this_page = currencies_in_this_page_query.get_results::<EntityCurrency>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        // one extra row tells whether there are more rows after this page
+        let rows = currencies_in_this_page_query
+            .limit(per_page + 1)
+            .offset(if cursor.is_some() { 0 } else { page_offset })
+            .select(<EntityCurrency>::as_select())
+            .get_results::<EntityCurrency>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (currencies_in_this_page, next_cursor, prev_cursor) = cursor_service.paginate(rows, per_page, page_offset, cursor.as_ref().map(|c| c.1), cursor_service.get_cursor_signature(&sort_keys, &primary_sort_key), |row| {
+            let value = match primary_sort_key.field {
+                CurrencySortField::Id => json!(row.id),
+                CurrencySortField::Name => json!(row.name),
+                CurrencySortField::CreatedAt => json!(row.created_at),
+                CurrencySortField::UpdatedAt => json!(row.updated_at)
+            };
+            (value, row.id)
+        });
 
         Ok({
             ServiceCollection { 
                 partial_collection: converter.convert_to_all_currencies_response(currencies_in_this_page),
-                total_count: count
+                total_count: count,
+                next_cursor,
+                prev_cursor
             }
         })
     }
@@ -86,6 +170,72 @@ impl<'a> CurrencyService<'a> {
         })
     }
 
+    // a code is matched first, then a known alias, and then the name or the display name of an existing currency, so "TWD", "ntd" and "新台幣" are the same currency
+    pub async fn resolve_currency_id(&self, name: &str) -> Result<Option<i32>, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        self.find_currency_id(conn, name)
+    }
+
+    pub fn find_currency_id(&self, conn: &mut PgConnection, name: &str) -> Result<Option<i32>, ApiError> {
+        let name_pattern = MatcherService::new().get_pattern(name, MatchMode::Exact);
+        let currency_id = currencies::table.filter(currencies::code.eq(name.trim().to_uppercase())).select(currencies::id).first::<i32>(conn).optional().map_err(|e| {
+            tracing::error!("Unable to look up currency {} by code: {}", name, e);
+            ApiError::NoRecord
+        })?;
+        if currency_id.is_some() {
+            return Ok(currency_id);
+        }
+
+        let currency_id = currency_aliases::table.filter(currency_aliases::alias.ilike(&name_pattern)).select(currency_aliases::currency_id).first::<i32>(conn).optional().map_err(|e| {
+            tracing::error!("Unable to look up currency {} by alias: {}", name, e);
+            ApiError::NoRecord
+        })?;
+        if currency_id.is_some() {
+            return Ok(currency_id);
+        }
+
+        currencies::table
+            .filter(currencies::name.ilike(&name_pattern).or(currencies::display_name.ilike(&name_pattern)))
+            .order((currencies::code.is_null(), currencies::id.asc()))
+            .select(currencies::id)
+            .first::<i32>(conn).optional().map_err(|e| {
+                tracing::error!("Unable to look up currency {} by name: {}", name, e);
+                ApiError::NoRecord
+            })
+    }
+
+    // the minor units of the currency of each receipt, used to format the amounts of the receipts and their inventories
+    pub fn get_minor_units_by_receipt_ids(&self, conn: &mut PgConnection, receipt_ids: &[i32]) -> Result<HashMap<i32, i32>, ApiError> {
+        let minor_units = receipts::table
+            .inner_join(currencies::table)
+            .filter(receipts::id.eq_any(receipt_ids))
+            .select((receipts::id, currencies::minor_units))
+            .get_results::<(i32, i32)>(conn).map_err(|e| {
+                tracing::error!("Unable to get the minor units of receipts: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        Ok(minor_units.into_iter().collect())
+    }
+
+    // the minor units of the settlement currency of each settled receipt
+    pub fn get_settlement_minor_units_by_receipt_ids(&self, conn: &mut PgConnection, receipt_ids: &[i32]) -> Result<HashMap<i32, i32>, ApiError> {
+        let minor_units = receipts::table
+            .inner_join(currencies::table.on(receipts::settlement_currency_id.eq(currencies::id.nullable())))
+            .filter(receipts::id.eq_any(receipt_ids))
+            .select((receipts::id, currencies::minor_units))
+            .get_results::<(i32, i32)>(conn).map_err(|e| {
+                tracing::error!("Unable to get the settlement minor units of receipts: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        Ok(minor_units.into_iter().collect())
+    }
+
     pub async fn new_currency(&self, currency: &NewEntityCurrency) -> Result<i32, ApiError> {
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
@@ -102,7 +252,19 @@ impl<'a> CurrencyService<'a> {
         Ok(entity_currency.id)
     }
 
-    pub async fn patch_currency(&self, id: i32, patch_payload: &PatchCurrencyPayload) -> Result<(), ApiError> {
+    pub async fn get_currency_version(&self, id: i32) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        currencies::table.filter(currencies::id.eq(id)).select(currencies::version).get_result::<i32>(conn).map_err(|e| {
+            tracing::warn!("try to get version of a non existed currency ({}): {}", id, e);
+            ApiError::NoRecord
+        })
+    }
+
+    pub async fn patch_currency(&self, id: i32, patch_payload: &PatchCurrencyPayload, expected_version: i32) -> Result<(), ApiError> {
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
@@ -113,27 +275,37 @@ impl<'a> CurrencyService<'a> {
             name: &patch_payload.name
         };
 
-        update_currency.save_changes::<EntityCurrency>(conn).or_else(|e| {
-            tracing::error!("update currency entity failed: {}", e);
-            Err(ApiError::UpdateCurrencyFailed)
-        })?;
+        let affected_rows = update(currencies::table.filter(currencies::id.eq(id)).filter(currencies::version.eq(expected_version)))
+            .set((&update_currency, currencies::version.eq(currencies::version + 1)))
+            .execute(conn).map_err(|e| {
+                tracing::error!("update currency entity failed: {}", e);
+                ApiError::UpdateCurrencyFailed
+            })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("currency {} is not existed or its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
 
         tracing::debug!("patch currency {} successfully", id);
         Ok(())
     }
 
-    pub async fn autocomplete_currencies(&self, keyword: &Option<String>) -> Result<ServiceCollection<ResponseCurrency>, ApiError> {
+    pub async fn autocomplete_currencies(&self, keyword_filters: &KeywordFilters) -> Result<ServiceCollection<ResponseCurrency>, ApiError> {
         let converter: ConverterService = ConverterService::new();
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
+        let limit = keyword_filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let matcher_service = MatcherService::new();
+        let match_mode = keyword_filters.match_mode.unwrap_or_default();
         let build_query = || {
-            let mut sql_filters = currencies::table.limit(20).into_boxed();
-            if let Some(kw) = &keyword {
-                let currency_name_pattern = format!("%{}%", kw);
-                sql_filters = sql_filters.filter(currencies::name.like(currency_name_pattern))
+            let mut sql_filters = currencies::table.into_boxed();
+            if let Some(kw) = &keyword_filters.keyword {
+                let currency_name_pattern = matcher_service.get_pattern(kw, match_mode);
+                sql_filters = sql_filters.filter(currencies::name.ilike(currency_name_pattern.clone()).or(currencies::code.ilike(currency_name_pattern.clone())).or(currencies::display_name.ilike(currency_name_pattern)))
             }
 
             sql_filters
@@ -141,14 +313,16 @@ impl<'a> CurrencyService<'a> {
 
         let count: i64 = build_query().select(count(currencies::columns::name)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
 
-        let currencies_query = build_query().select(<EntityCurrency>::as_select());
+        let currencies_query = build_query().order((currencies::name.asc(), currencies::id.asc())).limit(limit).select(<EntityCurrency>::as_select());
 
         let currencies_list = currencies_query.get_results::<EntityCurrency>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
 
         Ok({
             ServiceCollection { 
                 partial_collection: converter.convert_to_all_currencies_response(currencies_list),
-                total_count: count
+                total_count: Some(count),
+                next_cursor: None,
+                prev_cursor: None
             }
         })
     }
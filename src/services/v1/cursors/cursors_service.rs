@@ -0,0 +1,147 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use diesel::{dsl, expression::AsExpression, query_dsl::methods::FilterDsl, sql_types::{is_nullable, SqlType}, BoolExpressionMethods, ExpressionMethods};
+use serde::de::DeserializeOwned;
+
+use crate::models::v1::{
+    errors::api_error::ApiError, 
+    parameters::{cursor::{Cursor, CursorDirection}, pagination::Pagination, sorting::{SortDirection, SortField, SortKey}}
+};
+
+pub type SeekGreater<C, V, I> = dsl::Or<dsl::Gt<C, V>, dsl::And<dsl::Eq<C, V>, dsl::Gt<I, i32>>>;
+pub type SeekLess<C, V, I> = dsl::Or<dsl::Lt<C, V>, dsl::And<dsl::Eq<C, V>, dsl::Lt<I, i32>>>;
+
+pub struct CursorService {
+}
+
+impl CursorService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    pub fn encode_cursor(&self, cursor: &Cursor) -> String {
+        let json = serde_json::to_vec(cursor).expect("cursor should be serialized without error");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode_cursor(&self, token: &str) -> Result<Cursor, ApiError> {
+        let json = URL_SAFE_NO_PAD.decode(token).map_err(|e| {
+            tracing::warn!("cursor is not valid base64: {}", e);
+            ApiError::InvalidCursor
+        })?;
+
+        serde_json::from_slice::<Cursor>(&json).map_err(|e| {
+            tracing::warn!("cursor is not a valid json: {}", e);
+            ApiError::InvalidCursor
+        })
+    }
+
+    fn get_sort_signature<F: SortField>(&self, sort_key: &SortKey<F>) -> String {
+        match sort_key.direction {
+            SortDirection::Asc => sort_key.field.name().to_string(),
+            SortDirection::Desc => format!("-{}", sort_key.field.name())
+        }
+    }
+
+    // the cursor only encodes the primary sort key and the id tiebreaker
+    fn is_seekable<F: SortField>(&self, sort_keys: &[SortKey<F>], primary_sort_key: &SortKey<F>) -> bool {
+        sort_keys.len() <= 1 && primary_sort_key.field.is_seekable()
+    }
+
+    // none means the sort cannot be resumed from a cursor
+    pub fn get_cursor_signature<F: SortField>(&self, sort_keys: &[SortKey<F>], primary_sort_key: &SortKey<F>) -> Option<String> {
+        if self.is_seekable(sort_keys, primary_sort_key) {
+            Some(self.get_sort_signature(primary_sort_key))
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn get_cursor<F: SortField>(&self, pagination: &Pagination, sort_keys: &[SortKey<F>], primary_sort_key: &SortKey<F>) -> Result<Option<(Cursor, CursorDirection)>, ApiError> {
+        let (token, direction) = match (&pagination.after, &pagination.before) {
+            (Some(_), Some(_)) => {
+                tracing::warn!("after and before cursors are given at the same time");
+                return Err(ApiError::InvalidCursor);
+            },
+            (Some(after), None) => (after, CursorDirection::After),
+            (None, Some(before)) => (before, CursorDirection::Before),
+            (None, None) => return Ok(None)
+        };
+
+        if !self.is_seekable(sort_keys, primary_sort_key) {
+            tracing::warn!("cursor pagination is not supported by this sort");
+            return Err(ApiError::InvalidCursor);
+        }
+
+        let cursor = self.decode_cursor(token)?;
+        if cursor.sort != self.get_sort_signature(primary_sort_key) {
+            tracing::warn!("cursor is issued for another sort: {}", cursor.sort);
+            return Err(ApiError::InvalidCursor);
+        }
+
+        Ok(Some((cursor, direction)))
+    }
+
+    pub fn get_cursor_value<T: DeserializeOwned>(&self, cursor: &Cursor) -> Result<T, ApiError> {
+        serde_json::from_value::<T>(cursor.value.clone()).map_err(|e| {
+            tracing::warn!("cursor value does not match the sort field: {}", e);
+            ApiError::InvalidCursor
+        })
+    }
+
+    pub fn is_seeking_greater(&self, cursor_direction: CursorDirection, sort_direction: SortDirection) -> bool {
+        matches!((cursor_direction, sort_direction), (CursorDirection::After, SortDirection::Asc) | (CursorDirection::Before, SortDirection::Desc))
+    }
+
+    // (column, id) > (value, cursor id) or (column, id) < (value, cursor id), the id tiebreaker follows the primary sort direction
+    pub fn seek<Q, C, V, I>(&self, query: Q, column: C, value: V, id_column: I, id: i32, greater: bool) -> Q
+    where
+        C: ExpressionMethods + Copy,
+        C::SqlType: SqlType<IsNull = is_nullable::NotNull>,
+        I: ExpressionMethods + Copy,
+        I::SqlType: SqlType<IsNull = is_nullable::NotNull>,
+        V: AsExpression<C::SqlType> + Clone,
+        i32: AsExpression<I::SqlType>,
+        Q: FilterDsl<SeekGreater<C, V, I>, Output = Q> + FilterDsl<SeekLess<C, V, I>, Output = Q>
+    {
+        if greater {
+            query.filter(column.gt(value.clone()).or(column.eq(value).and(id_column.gt(id))))
+        }
+        else {
+            query.filter(column.lt(value.clone()).or(column.eq(value).and(id_column.lt(id))))
+        }
+    }
+
+    pub fn paginate<T>(&self, mut rows: Vec<T>, per_page: i64, page_offset: i64, cursor_direction: Option<CursorDirection>, cursor_signature: Option<String>, get_key: impl Fn(&T) -> (serde_json::Value, i32)) -> (Vec<T>, Option<String>, Option<String>) {
+        let has_more = rows.len() as i64 > per_page;
+        rows.truncate(per_page as usize);
+        if cursor_direction == Some(CursorDirection::Before) {
+            rows.reverse();
+        }
+
+        let sort = match cursor_signature {
+            Some(sort) => sort,
+            None => return (rows, None, None)
+        };
+
+        let to_cursor = |row: &T| {
+            let (value, id) = get_key(row);
+            self.encode_cursor(&Cursor {
+                sort: sort.clone(),
+                value,
+                id
+            })
+        };
+
+        let (has_next, has_prev) = match cursor_direction {
+            None => (has_more, page_offset > 0),
+            Some(CursorDirection::After) => (has_more, true),
+            Some(CursorDirection::Before) => (true, has_more)
+        };
+
+        let next_cursor = rows.last().filter(|_| has_next).map(to_cursor);
+        let prev_cursor = rows.first().filter(|_| has_prev).map(to_cursor);
+        (rows, next_cursor, prev_cursor)
+    }
+}
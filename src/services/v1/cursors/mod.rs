@@ -0,0 +1 @@
+pub mod cursors_service;
\ No newline at end of file
@@ -0,0 +1,60 @@
+use bigdecimal::{BigDecimal, RoundingMode};
+
+use crate::models::v1::errors::api_error::ApiError;
+
+// a currency created without any minor units takes the ones of most currencies
+pub const DEFAULT_MINOR_UNITS: i32 = 2;
+// money columns are NUMERIC(19, 4), which leaves 15 digits before the decimal point
+const MAX_INTEGER_DIGITS: u32 = 15;
+
+pub struct DecimalService {
+}
+
+impl DecimalService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    // trailing zeros do not count, so "1.50" is fine for a currency with 1 minor unit
+    pub fn validate_amount(&self, amount: &BigDecimal, minor_units: i32) -> Result<BigDecimal, ApiError> {
+        let normalized = self.normalize(amount);
+        if normalized.fractional_digit_count() > minor_units as i64 {
+            tracing::warn!("amount {} has more than {} decimal places", amount, minor_units);
+            return Err(ApiError::InvalidAmountPrecision);
+        }
+
+        if normalized.abs() >= 10_u64.pow(MAX_INTEGER_DIGITS) {
+            tracing::warn!("amount {} is out of range", amount);
+            return Err(ApiError::InvalidParameter);
+        }
+
+        Ok(normalized)
+    }
+
+    // drops the trailing zeros of a NUMERIC(19, 4) value, 12.3000 is returned as "12.3" and 100 stays "100"
+    pub fn normalize(&self, amount: &BigDecimal) -> BigDecimal {
+        let normalized = amount.normalized();
+        if normalized.fractional_digit_count() < 0 {
+            normalized.with_scale(0)
+        }
+        else {
+            normalized
+        }
+    }
+
+    // the smallest amount of a currency, 0.01 for TWD and 1 for JPY
+    pub fn get_minor_unit(&self, minor_units: i32) -> BigDecimal {
+        BigDecimal::new(1.into(), minor_units as i64)
+    }
+
+    pub fn round(&self, amount: &BigDecimal, minor_units: i32) -> BigDecimal {
+        self.normalize(&amount.round(minor_units as i64))
+    }
+
+    // pads an amount to the minor units of its currency, 12.3 is returned as "12.30" for TWD, 0 as "0.00" and 1200 as "1200" for JPY
+    pub fn format(&self, amount: &BigDecimal, minor_units: i32) -> String {
+        let digits = minor_units.max(0);
+        format!("{:.*}", digits as usize, amount.with_scale_round(digits as i64, RoundingMode::HalfEven))
+    }
+}
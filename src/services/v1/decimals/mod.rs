@@ -0,0 +1 @@
+pub mod decimals_service;
\ No newline at end of file
@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use diesel::{dsl::{count, sql}, insert_into, pg::Pg, sql_types::{Int4, Nullable, Numeric}, upsert::excluded, Connection, ExpressionMethods, NullableExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+use serde::Deserialize;
+
+use crate::{models::v1::{collections::service_collection::ServiceCollection, entities::{entity_currency::EntityCurrency, entity_exchange_rate::{EntityConversion, EntityExchangeRate, NewEntityExchangeRate}}, errors::api_error::ApiError, parameters::{conversion::Conversion, pagination::Pagination, query_filters::{ExchangeRateFilters, ExchangeRateImportFilters}}, responses::{response_exchange_rate::{ResponseExchangeRate, ResponseSkippedExchangeRate}}}, repository::DbRepository, schema::{currencies, exchange_rates, receipts}, services::v1::{converters::converters_service::ConverterService, currencies::currencies_service::CurrencyService, fallbacks::fallbacks_service::FallbacksService, totals::totals_service::RECEIPT_TOTAL_SQL, user_settings::user_settings_service::UserSettingService}};
+
+// the rate column is NUMERIC(24, 12)
+const RATE_SCALE: i64 = 12;
+const MAX_RATE_INTEGER_DIGITS: u32 = 12;
+const IMPORT_CHUNK_SIZE: usize = 1000;
+const DEFAULT_ECB_BASE: &str = "EUR";
+const BASE_CURRENCY_KEYWORD: &str = "base";
+// the rate effective on the local date of the transaction
+const RECEIPT_EXCHANGE_RATE_SQL: &str = "exchange_rate(receipts.currency_id, ";
+const RECEIPT_LOCAL_DATE_SQL: &str = ", ((receipts.transaction_date AT TIME ZONE 'UTC') + receipts.transaction_offset * INTERVAL '1 second')::DATE)";
+
+#[derive(Deserialize, Debug)]
+struct ExchangeRateRecord {
+    date: NaiveDate,
+    from: String,
+    to: String,
+    rate: BigDecimal
+}
+
+// each record is kept with its line, so a row which cannot be read is reported instead
+type ExchangeRateRecords = Vec<(usize, Result<ExchangeRateRecord, String>)>;
+
+pub struct ExchangeRateService<'a> {
+    repository: &'a DbRepository
+}
+
+impl<'a> ExchangeRateService<'a> {
+    pub fn new(repository: &'a DbRepository) -> Self {
+        Self {
+            repository
+        }
+    }
+
+    pub async fn get_exchange_rates(&self, pagination: &Pagination, exchange_rate_filters: &ExchangeRateFilters) -> Result<ServiceCollection<ResponseExchangeRate>, ApiError> {
+        let converter = ConverterService::new();
+        let fallbacks_service = FallbacksService::new();
+        if let (Some(start_date), Some(end_date)) = (exchange_rate_filters.start_date, exchange_rate_filters.end_date) {
+            if start_date > end_date {
+                tracing::warn!("start date {} is after end date {}", start_date, end_date);
+                return Err(ApiError::InvalidDateRange);
+            }
+        }
+
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let from_currency_id = exchange_rate_filters.from.as_ref().map(|from| self.get_currency_id(conn, from)).transpose()?;
+        let to_currency_id = exchange_rate_filters.to.as_ref().map(|to| self.get_currency_id(conn, to)).transpose()?;
+        let build_query = || {
+            let mut query = exchange_rates::table.into_boxed::<Pg>();
+            if let Some(from_currency_id) = from_currency_id {
+                query = query.filter(exchange_rates::from_currency_id.eq(from_currency_id));
+            }
+            if let Some(to_currency_id) = to_currency_id {
+                query = query.filter(exchange_rates::to_currency_id.eq(to_currency_id));
+            }
+            if let Some(start_date) = exchange_rate_filters.start_date {
+                query = query.filter(exchange_rates::rate_date.ge(start_date));
+            }
+            if let Some(end_date) = exchange_rate_filters.end_date {
+                query = query.filter(exchange_rates::rate_date.le(end_date));
+            }
+
+            query
+        };
+
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(build_query().select(count(exchange_rates::id)).first::<i64>(conn).map_err(|_e| ApiError::NoRecord)?)
+        }
+        else {
+            None
+        };
+
+        let (page_offset, per_page) = fallbacks_service.fallback_pagination(pagination);
+        let exchange_rates_in_this_page = build_query()
+            .order((exchange_rates::rate_date.desc(), exchange_rates::id.asc()))
+            .limit(per_page)
+            .offset(page_offset)
+            .select(<EntityExchangeRate>::as_select())
+            .get_results::<EntityExchangeRate>(conn).map_err(|_e| ApiError::NoRecord)?;
+
+        Ok(ServiceCollection {
+            partial_collection: converter.convert_to_all_exchange_rates_response(exchange_rates_in_this_page),
+            total_count: count,
+            next_cursor: None,
+            prev_cursor: None
+        })
+    }
+
+    // an ECB file is XML, anything else is read as a CSV file with a date,from,to,rate header
+    // rows which cannot be imported are reported by their line and skipped, a later row of the same date and pair wins
+    pub async fn parse_exchange_rates(&self, file: &str, import_filters: &ExchangeRateImportFilters) -> Result<(Vec<NewEntityExchangeRate>, Vec<ResponseSkippedExchangeRate>), ApiError> {
+        let records = if file.trim_start().starts_with('<') {
+            self.read_ecb_records(file, import_filters.base.as_deref().unwrap_or(DEFAULT_ECB_BASE))?
+        }
+        else {
+            self.read_csv_records(file)?
+        };
+
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let currency_service = CurrencyService::new(self.repository);
+        let mut currency_ids: HashMap<String, Option<i32>> = HashMap::new();
+        let mut positions: HashMap<(NaiveDate, i32, i32), usize> = HashMap::new();
+        let mut new_exchange_rates = vec![];
+        let mut skipped = vec![];
+        for (line, record) in records {
+            let record = match record {
+                Ok(record) => record,
+                Err(reason) => {
+                    skipped.push(ResponseSkippedExchangeRate { line, reason });
+                    continue;
+                }
+            };
+
+            let mut resolve = |name: &str| -> Result<Option<i32>, ApiError> {
+                let key = name.trim().to_uppercase();
+                if let Some(currency_id) = currency_ids.get(&key) {
+                    return Ok(*currency_id);
+                }
+                let currency_id = currency_service.find_currency_id(conn, name.trim())?;
+                currency_ids.insert(key, currency_id);
+                Ok(currency_id)
+            };
+            let (from_currency_id, to_currency_id) = match (resolve(&record.from)?, resolve(&record.to)?) {
+                (Some(from_currency_id), Some(to_currency_id)) => (from_currency_id, to_currency_id),
+                (None, _) => {
+                    skipped.push(ResponseSkippedExchangeRate { line, reason: format!("unknown currency {}", record.from) });
+                    continue;
+                },
+                (_, None) => {
+                    skipped.push(ResponseSkippedExchangeRate { line, reason: format!("unknown currency {}", record.to) });
+                    continue;
+                }
+            };
+
+            if from_currency_id == to_currency_id {
+                skipped.push(ResponseSkippedExchangeRate { line, reason: format!("{} and {} are the same currency", record.from, record.to) });
+                continue;
+            }
+
+            let rate = record.rate.with_scale_round(RATE_SCALE, bigdecimal::RoundingMode::HalfEven);
+            if rate <= 0 || rate >= 10_u64.pow(MAX_RATE_INTEGER_DIGITS) {
+                skipped.push(ResponseSkippedExchangeRate { line, reason: format!("rate {} is out of range", record.rate) });
+                continue;
+            }
+
+            let new_exchange_rate = NewEntityExchangeRate {
+                rate_date: record.date,
+                from_currency_id,
+                to_currency_id,
+                rate
+            };
+            match positions.get(&(record.date, from_currency_id, to_currency_id)) {
+                Some(position) => new_exchange_rates[*position] = new_exchange_rate,
+                None => {
+                    positions.insert((record.date, from_currency_id, to_currency_id), new_exchange_rates.len());
+                    new_exchange_rates.push(new_exchange_rate);
+                }
+            }
+        }
+
+        tracing::debug!("{} exchange rates are parsed, {} rows are skipped", new_exchange_rates.len(), skipped.len());
+        Ok((new_exchange_rates, skipped))
+    }
+
+    fn read_csv_records(&self, file: &str) -> Result<ExchangeRateRecords, ApiError> {
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(file.as_bytes());
+        let headers = reader.headers().map_err(|e| {
+            tracing::warn!("unable to read the header of the exchange rate file: {}", e);
+            ApiError::InvalidExchangeRateFile
+        })?.clone();
+        if !["date", "from", "to", "rate"].iter().all(|column| headers.iter().any(|header| header.eq_ignore_ascii_case(column))) {
+            tracing::warn!("the exchange rate file has no date,from,to,rate header: {:?}", headers);
+            return Err(ApiError::InvalidExchangeRateFile);
+        }
+        let headers = csv::StringRecord::from(headers.iter().map(|header| header.to_lowercase()).collect::<Vec<String>>());
+
+        Ok(reader.records().map(|row| match row {
+            Ok(row) => {
+                let line = row.position().map(|p| p.line() as usize).unwrap_or_default();
+                (line, row.deserialize::<ExchangeRateRecord>(Some(&headers)).map_err(|e| e.to_string()))
+            },
+            Err(e) => (e.position().map(|p| p.line() as usize).unwrap_or_default(), Err(e.to_string()))
+        }).collect())
+    }
+
+    // <Cube time="2026-10-16"><Cube currency="USD" rate="1.0845"/></Cube> means 1 base is worth 1.0845 USD on that date
+    fn read_ecb_records(&self, file: &str, base: &str) -> Result<ExchangeRateRecords, ApiError> {
+        let document = roxmltree::Document::parse(file).map_err(|e| {
+            tracing::warn!("unable to parse the exchange rate file: {}", e);
+            ApiError::InvalidExchangeRateFile
+        })?;
+
+        let mut records = vec![];
+        for day in document.descendants().filter(|node| node.tag_name().name() == "Cube" && node.has_attribute("time")) {
+            let date = day.attribute("time").and_then(|time| NaiveDate::parse_from_str(time, "%Y-%m-%d").ok());
+            for cube in day.children().filter(|node| node.tag_name().name() == "Cube") {
+                let line = document.text_pos_at(cube.range().start).row as usize;
+                let record = match (date, cube.attribute("currency"), cube.attribute("rate").map(|rate| rate.parse::<BigDecimal>())) {
+                    (Some(date), Some(currency), Some(Ok(rate))) => Ok(ExchangeRateRecord { date, from: base.to_string(), to: currency.to_string(), rate }),
+                    _ => Err("a rate needs a time, a currency and a decimal rate".to_string())
+                };
+                records.push((line, record));
+            }
+        }
+
+        if records.is_empty() {
+            tracing::warn!("the exchange rate file has no rate");
+            return Err(ApiError::InvalidExchangeRateFile);
+        }
+
+        Ok(records)
+    }
+
+    // a rate imported again replaces the old one
+    pub async fn import_exchange_rates(&self, new_exchange_rates: &[NewEntityExchangeRate]) -> Result<usize, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let affected_rows = conn.transaction::<usize, ApiError, _>(|conn| {
+            let mut affected_rows = 0;
+            for chunk in new_exchange_rates.chunks(IMPORT_CHUNK_SIZE) {
+                affected_rows += insert_into(exchange_rates::table)
+                    .values(chunk)
+                    .on_conflict((exchange_rates::rate_date, exchange_rates::from_currency_id, exchange_rates::to_currency_id))
+                    .do_update()
+                    .set(exchange_rates::rate.eq(excluded(exchange_rates::rate)))
+                    .execute(conn).map_err(|e| {
+                        tracing::error!("import exchange rates failed: {}", e);
+                        ApiError::ImportExchangeRatesFailed
+                    })?;
+            }
+            Ok(affected_rows)
+        })?;
+
+        tracing::debug!("Import {} exchange rates successfully", affected_rows);
+        Ok(affected_rows)
+    }
+
+    // "base" is the base currency of the user, anything else is resolved as a currency code, alias or name
+    pub fn get_conversion_currency(&self, conn: &mut PgConnection, convert_to: &str, username: &str) -> Result<EntityCurrency, ApiError> {
+        let currency_id = if convert_to.trim().eq_ignore_ascii_case(BASE_CURRENCY_KEYWORD) {
+            UserSettingService::new(self.repository).get_base_currency_id(conn, username)?
+        }
+        else {
+            self.get_currency_id(conn, convert_to)?
+        };
+
+        currencies::table.filter(currencies::id.eq(currency_id)).select(<EntityCurrency>::as_select()).get_result::<EntityCurrency>(conn).map_err(|e| {
+            tracing::warn!("currency {} to convert to is not existed: {}", currency_id, e);
+            ApiError::CurrencyIdNotExisted
+        })
+    }
+
+    pub fn get_conversion(&self, conn: &mut PgConnection, conversion: &Conversion, receipt_ids: &[i32]) -> Result<Option<EntityConversion>, ApiError> {
+        let Some(convert_to) = conversion.convert_to.as_deref() else {
+            return Ok(None);
+        };
+
+        let currency = self.get_conversion_currency(conn, convert_to, &conversion.username)?;
+        let rates = receipts::table
+            .filter(receipts::id.eq_any(receipt_ids))
+            .select((receipts::id, sql::<Nullable<Numeric>>(RECEIPT_EXCHANGE_RATE_SQL).bind::<Int4, _>(currency.id).sql(RECEIPT_LOCAL_DATE_SQL)))
+            .get_results::<(i32, Option<BigDecimal>)>(conn).map_err(|e| {
+                tracing::error!("Unable to get the exchange rates of receipts: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        // what the card was actually charged beats a market rate
+        let settlements = receipts::table
+            .filter(receipts::id.eq_any(receipt_ids))
+            .filter(receipts::settlement_currency_id.eq(currency.id))
+            .select((receipts::id, receipts::settled_amount.assume_not_null(), sql::<Numeric>(RECEIPT_TOTAL_SQL)))
+            .get_results::<(i32, BigDecimal, BigDecimal)>(conn).map_err(|e| {
+                tracing::error!("Unable to get the settled amounts of receipts: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        let mut rates = rates.into_iter().filter_map(|(receipt_id, rate)| rate.map(|rate| (receipt_id, rate))).collect::<HashMap<i32, BigDecimal>>();
+        let mut settled_receipt_ids = HashSet::new();
+        for (receipt_id, settled_amount, total) in settlements {
+            if total > 0 {
+                rates.insert(receipt_id, settled_amount / total);
+                settled_receipt_ids.insert(receipt_id);
+            }
+        }
+
+        Ok(Some(EntityConversion {
+            currency,
+            rates,
+            settled_receipt_ids
+        }))
+    }
+
+    fn get_currency_id(&self, conn: &mut PgConnection, name: &str) -> Result<i32, ApiError> {
+        CurrencyService::new(self.repository).find_currency_id(conn, name)?.ok_or_else(|| {
+            tracing::warn!("currency {} is not existed", name);
+            ApiError::CurrencyIdNotExisted
+        })
+    }
+}
@@ -0,0 +1 @@
+pub mod exchange_rates_service;
\ No newline at end of file
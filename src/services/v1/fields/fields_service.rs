@@ -0,0 +1,102 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::models::v1::{
+    errors::api_error::ApiError, 
+    parameters::field_selection::{FieldSelection, ReceiptIncludes}
+};
+
+pub struct FieldService {
+}
+
+impl FieldService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    pub fn parse_fields(&self, field_selection: &FieldSelection) -> Option<Vec<String>> {
+        field_selection.fields.as_ref().map(|fields| self.split_names(fields))
+    }
+
+    // without include/expand every relation is embedded as before, a relation left out of fields= is not read at all
+    pub fn get_receipt_includes(&self, field_selection: &FieldSelection) -> Result<ReceiptIncludes, ApiError> {
+        let mut includes = match (&field_selection.include, &field_selection.expand) {
+            (None, None) => ReceiptIncludes::default(),
+            (include, expand) => {
+                let mut includes = ReceiptIncludes { currency: false, store: false, inventories: false };
+                let names = include.iter().chain(expand.iter()).flat_map(|names| self.split_names(names)).collect::<Vec<String>>();
+                for name in names {
+                    match name.as_str() {
+                        "currency" => includes.currency = true,
+                        "store" => includes.store = true,
+                        "inventories" => includes.inventories = true,
+                        _ => {
+                            tracing::warn!("unknown receipt relation: {}", name);
+                            return Err(ApiError::InvalidField);
+                        }
+                    }
+                }
+                includes
+            }
+        };
+
+        if let Some(fields) = self.parse_fields(field_selection) {
+            let is_selected = |relation: &str| fields.iter().any(|f| f == relation || f.starts_with(&format!("{}.", relation)));
+            includes.currency &= is_selected("currency");
+            includes.store &= is_selected("store");
+            includes.inventories &= is_selected("inventories");
+        }
+
+        Ok(includes)
+    }
+
+    // only receipts have relations to opt into, the other resources take fields= alone
+    pub fn get_fields(&self, field_selection: &FieldSelection) -> Result<Option<Vec<String>>, ApiError> {
+        if field_selection.include.is_some() || field_selection.expand.is_some() {
+            tracing::warn!("include/expand on a resource without relations");
+            return Err(ApiError::InvalidField);
+        }
+        Ok(self.parse_fields(field_selection))
+    }
+
+    // dotted paths select nested fields, in arrays they apply to every element
+    pub fn select_fields<T: Serialize>(&self, response: &T, fields: &Option<Vec<String>>) -> Value {
+        let value = serde_json::to_value(response).unwrap_or(Value::Null);
+        match fields {
+            Some(fields) => {
+                let paths = fields.iter().map(|f| f.split('.').collect::<Vec<&str>>()).collect::<Vec<Vec<&str>>>();
+                self.project(value, &paths)
+            },
+            None => value
+        }
+    }
+
+    fn project(&self, value: Value, paths: &[Vec<&str>]) -> Value {
+        match value {
+            Value::Object(object) => {
+                let mut projected = Map::new();
+                for (key, child) in object {
+                    let child_paths = paths.iter().filter(|p| p.first() == Some(&key.as_str())).map(|p| p[1..].to_vec()).collect::<Vec<Vec<&str>>>();
+                    if child_paths.is_empty() {
+                        continue;
+                    }
+                    // a bare name keeps the whole field
+                    if child_paths.iter().any(|p| p.is_empty()) {
+                        projected.insert(key, child);
+                    }
+                    else {
+                        projected.insert(key, self.project(child, &child_paths));
+                    }
+                }
+                Value::Object(projected)
+            },
+            Value::Array(items) => Value::Array(items.into_iter().map(|item| self.project(item, paths)).collect()),
+            other => other
+        }
+    }
+
+    fn split_names(&self, names: &str) -> Vec<String> {
+        names.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()).map(|n| n.to_string()).collect()
+    }
+}
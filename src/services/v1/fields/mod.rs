@@ -0,0 +1 @@
+pub mod fields_service;
\ No newline at end of file
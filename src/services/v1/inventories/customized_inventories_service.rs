@@ -1,7 +1,9 @@
-use chrono::{NaiveDateTime, NaiveTime};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::{
-    dsl::count, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, TextExpressionMethods
+    dsl::count, ExpressionMethods, QueryDsl, RunQueryDsl, PgTextExpressionMethods, SelectableHelper, BoolExpressionMethods
 };
+use serde_json::json;
 
 use crate::{
     models::v1::{
@@ -10,19 +12,34 @@ use crate::{
             entity_currency::EntityCurrency, entity_inventory::EntityInventory, entity_product::EntityProduct, entity_receipt::EntityReceipt, entity_store::EntityStore
         }, 
         errors::api_error::ApiError, 
-        parameters::{pagination::Pagination, query_filters::QueryFilters}, 
+        parameters::{conversion::Conversion, cursor::CursorDirection, matching::MatchMode, pagination::Pagination, query_filters::{QueryFilters, TimestampFilters}, sorting::{CustomizedInventorySortField, SortDirection, SortKey, Sorting}}, 
         responses::response_inventory::ResponseCustomizedInventory
     }, 
     repository::DbRepository, 
     schema::{
-        currencies, inventories, products, receipts, stores
+        currencies, currency_aliases, inventories, products, receipts, stores
     }, 
     services::v1::{
         converters::converters_service::ConverterService, 
-        fallbacks::fallbacks_service::FallbacksService
+        cursors::cursors_service::CursorService, 
+        exchange_rates::exchange_rates_service::ExchangeRateService, 
+        fallbacks::fallbacks_service::FallbacksService, 
+        matchers::matchers_service::MatcherService, 
+        timezones::timezones_service::TimeZoneService, 
+        sortings::sortings_service::SortingService
     }
 };
 
+// the nested endpoints (/products/:id/customized_inventories, ...) narrow the same query to one parent
+#[derive(Clone, Copy)]
+enum CustomizedInventoryScope {
+    All,
+    Product(i32),
+    Receipt(i32),
+    Store(i32),
+    Currency(i32)
+}
+
 pub struct CustomizedInventoryService<'a> {
     repository: &'a DbRepository
 }
@@ -34,7 +51,7 @@ impl<'a> CustomizedInventoryService<'a> {
         }
     }
 
-    pub async fn get_customized_inventory(&self, id: i32) -> Result<ResponseCustomizedInventory, ApiError> {
+    pub async fn get_customized_inventory(&self, id: i32, conversion: &Conversion) -> Result<ResponseCustomizedInventory, ApiError> {
         let converter = ConverterService::new();
         let conn = &mut self.repository.pool.get().or_else(
             |e| {
@@ -61,6 +78,7 @@ impl<'a> CustomizedInventoryService<'a> {
                 .inner_join(stores::table)
                 .inner_join(currencies::table)
                 .filter(receipts::id.eq(inventory.receipt_id))
+                .filter(receipts::deleted_at.is_null())
                 .select(<(EntityReceipt, EntityStore, EntityCurrency)>::as_select());
 
         let (receipt, store, currency) = receipt_store_currency_by_receipt_query.get_result::<(EntityReceipt, EntityStore, EntityCurrency)>(conn).or_else(
@@ -69,263 +87,226 @@ impl<'a> CustomizedInventoryService<'a> {
                 Err(ApiError::NoRecord)
             }
         )?;
-            
-
 
-        let customized_inventory_response = converter.convert_to_customized_inventory_response(inventory, product, receipt, store, currency);
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &[receipt.id])?;
+        let customized_inventory_response = converter.convert_to_customized_inventory_response(inventory, product, receipt, store, currency, conversion.as_ref());
 
         Ok(customized_inventory_response)
     }
 
-    pub async fn get_customized_inventories(&self, pagination: &Pagination, query_filters: &QueryFilters) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
+    pub async fn get_customized_inventories(&self, pagination: &Pagination, query_filters: &QueryFilters, timestamp_filters: &TimestampFilters, sorting: &Sorting, conversion: &Conversion) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
+        self.get_scoped_customized_inventories(CustomizedInventoryScope::All, pagination, query_filters, timestamp_filters, sorting, conversion).await
+    }
+
+    pub async fn get_customized_inventories_by_product_id(&self, product_id: i32, pagination: &Pagination, query_filters: &QueryFilters, timestamp_filters: &TimestampFilters, sorting: &Sorting, conversion: &Conversion) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
+        self.get_scoped_customized_inventories(CustomizedInventoryScope::Product(product_id), pagination, query_filters, timestamp_filters, sorting, conversion).await
+    }
+
+    pub async fn get_customized_inventories_by_receipt_id(&self, receipt_id: i32, pagination: &Pagination, query_filters: &QueryFilters, timestamp_filters: &TimestampFilters, sorting: &Sorting, conversion: &Conversion) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
+        self.get_scoped_customized_inventories(CustomizedInventoryScope::Receipt(receipt_id), pagination, query_filters, timestamp_filters, sorting, conversion).await
+    }
+
+    pub async fn get_customized_inventories_by_store_id(&self, store_id: i32, pagination: &Pagination, query_filters: &QueryFilters, timestamp_filters: &TimestampFilters, sorting: &Sorting, conversion: &Conversion) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
+        self.get_scoped_customized_inventories(CustomizedInventoryScope::Store(store_id), pagination, query_filters, timestamp_filters, sorting, conversion).await
+    }
+
+    pub async fn get_customized_inventories_by_currency_id(&self, currency_id: i32, pagination: &Pagination, query_filters: &QueryFilters, timestamp_filters: &TimestampFilters, sorting: &Sorting, conversion: &Conversion) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
+        self.get_scoped_customized_inventories(CustomizedInventoryScope::Currency(currency_id), pagination, query_filters, timestamp_filters, sorting, conversion).await
+    }
+
+    async fn get_scoped_customized_inventories(&self, scope: CustomizedInventoryScope, pagination: &Pagination, query_filters: &QueryFilters, timestamp_filters: &TimestampFilters, sorting: &Sorting, conversion: &Conversion) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
         let converter = ConverterService::new();
         let fallbacks_service = FallbacksService::new();
+        let sorting_service = SortingService::new();
+        let sort_keys = sorting_service.parse_sort::<CustomizedInventorySortField>(sorting)?;
+        let primary_sort_key = sort_keys.first().copied().unwrap_or(SortKey { field: CustomizedInventorySortField::Id, direction: SortDirection::Asc });
+        let cursor_service = CursorService::new();
+        let cursor = cursor_service.get_cursor(pagination, &sort_keys, &primary_sort_key)?;
         let conn = &mut self.repository.pool.get().or_else(
             |e| {
                 tracing::error!("database connection broken: {}", e);
                 Err(ApiError::DatabaseConnectionBroken)
             }
         )?;
-        
-        let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
+        let matcher_service = MatcherService::new();
+        let match_mode = query_filters.match_mode.unwrap_or_default();
+        let (transaction_from, transaction_to) = TimeZoneService::new().get_date_range(query_filters.start_date, query_filters.end_date, query_filters.preset, query_filters.tz)?;
         let build_query = || {
             let mut sql_filters = inventories::table
                 .inner_join(products::table)
                 .inner_join(receipts::table.inner_join(stores::table).inner_join(currencies::table))
+                .filter(receipts::deleted_at.is_null())
                 .into_boxed();
+            if let Some(created_from) = timestamp_filters.created_from {
+                sql_filters = sql_filters.filter(inventories::created_at.ge(created_from));
+            }
+            if let Some(created_to) = timestamp_filters.created_to {
+                sql_filters = sql_filters.filter(inventories::created_at.le(created_to));
+            }
+            if let Some(updated_from) = timestamp_filters.updated_from {
+                sql_filters = sql_filters.filter(inventories::updated_at.ge(updated_from));
+            }
+            if let Some(updated_to) = timestamp_filters.updated_to {
+                sql_filters = sql_filters.filter(inventories::updated_at.le(updated_to));
+            }
+
             if let Some(product_name) = &query_filters.product_name {
-                let product_name_pattern = format!("%{}%", product_name);
-                sql_filters = sql_filters.filter(products::name.like(product_name_pattern))
+                let product_name_pattern = matcher_service.get_pattern(product_name, match_mode);
+                sql_filters = sql_filters.filter(products::name.ilike(product_name_pattern))
             }
             if let Some(product_brand) = &query_filters.product_brand {
-                let product_brand_pattern = format!("%{}%", product_brand);
-                sql_filters = sql_filters.filter(products::brand.like(product_brand_pattern))
+                let product_brand_pattern = matcher_service.get_pattern(product_brand, match_mode);
+                sql_filters = sql_filters.filter(products::brand.ilike(product_brand_pattern))
             }
             if let Some(product_alias) = &&query_filters.product_alias {
-                let product_alias_pattern = format!("%{}%", product_alias);
-                sql_filters = sql_filters.filter(products::alias.like(product_alias_pattern))
+                let product_alias_pattern = matcher_service.get_pattern(product_alias, match_mode);
+                sql_filters = sql_filters.filter(products::alias.ilike(product_alias_pattern))
             }
 
-            if query_filters.start_date.is_some() && query_filters.end_date.is_some() {
-                let start = NaiveDateTime::new(query_filters.start_date.expect("start date should not be none"), NaiveTime::MIN);
-                let end = NaiveDateTime::new(query_filters.end_date.expect("end date should not be none"), NaiveTime::from_hms_milli_opt(23, 59, 59, 999).expect("23:59:59.999 should be unwraped without error"));
-                sql_filters = sql_filters.filter(receipts::transaction_date.ge(start));
-                sql_filters = sql_filters.filter(receipts::transaction_date.le(end));
+            if let Some(transaction_from) = transaction_from {
+                sql_filters = sql_filters.filter(receipts::transaction_date.ge(transaction_from));
+            }
+            if let Some(transaction_to) = transaction_to {
+                sql_filters = sql_filters.filter(receipts::transaction_date.lt(transaction_to));
             }
 
             if let Some(currency_name) = &query_filters.currency {
-                sql_filters = sql_filters.filter(currencies::name.eq(currency_name))
+                let currency_pattern = matcher_service.get_pattern(currency_name, MatchMode::Exact);
+                let currency_ids_of_alias = currency_aliases::table.filter(currency_aliases::alias.ilike(currency_pattern.clone())).select(currency_aliases::currency_id);
+                sql_filters = sql_filters.filter(currencies::name.ilike(currency_pattern.clone()).or(currencies::code.ilike(currency_pattern)).or(currencies::id.eq_any(currency_ids_of_alias)))
             }
 
             if let Some(store_name) = &query_filters.store_name {
-                let store_name_pattern = format!("%{}%", store_name);
-                sql_filters = sql_filters.filter(stores::name.like(store_name_pattern));
+                let store_name_pattern = matcher_service.get_pattern(store_name, match_mode);
+                sql_filters = sql_filters.filter(stores::name.ilike(store_name_pattern));
             }
 
             if let Some(store_alias) = &query_filters.store_alias {
-                let store_alias_pattern = format!("%{}%", store_alias);
-                sql_filters = sql_filters.filter(stores::alias.like(store_alias_pattern));
+                let store_alias_pattern = matcher_service.get_pattern(store_alias, match_mode);
+                sql_filters = sql_filters.filter(stores::alias.ilike(store_alias_pattern));
             }
 
-            sql_filters
-        };
-
-        let count: i64 = build_query().select(count(inventories::columns::id)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-        let all_compound_inventories_in_this_page_query = 
-            build_query()
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<(EntityInventory, EntityProduct, EntityReceipt, EntityStore, EntityCurrency)>::as_select());
-
-        let all_compound_inventories_in_this_page = all_compound_inventories_in_this_page_query.get_results::<(EntityInventory, EntityProduct, EntityReceipt, EntityStore, EntityCurrency)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-        Ok(ServiceCollection {
-            partial_collection: all_compound_inventories_in_this_page.into_iter().map(|inv| converter.convert_to_customized_inventory_response(inv.0, inv.1, inv.2, inv.3, inv.4)).collect(),
-            total_count: count
-        })
-    }
-
-    pub async fn get_customized_inventories_by_product_id(&self, product_id: i32, pagination: &Pagination) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
-        let converter = ConverterService::new();
-        let fallbacks_service = FallbacksService::new();
-        let conn = &mut self.repository.pool.get().or_else(
-            |e| {
-                tracing::error!("database connection broken: {}", e);
-                Err(ApiError::DatabaseConnectionBroken)
+            if let Some(specification_unit) = &query_filters.specification_unit {
+                sql_filters = sql_filters.filter(products::specification_unit.ilike(matcher_service.get_pattern(specification_unit, MatchMode::Exact)));
             }
-        )?;
-
-        let count: i64 = inventories::table
-            .filter(inventories::columns::product_id.eq(product_id))
-            .select(count(inventories::columns::id))
-            .first(conn)
-            .or_else(|_e| Err(ApiError::NoRecord))?;
-        
-        let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
-
-        let all_compound_inventories_by_product_id_in_this_page_query = 
-            inventories::table
-                .inner_join(products::table)
-                .filter(inventories::columns::product_id.eq(product_id))
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<(EntityInventory, EntityProduct)>::as_select());
-
-        let all_compound_inventories_by_product_id_in_this_page = all_compound_inventories_by_product_id_in_this_page_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-        let receipt_ids_by_product_id = AsRef::<Vec<(EntityInventory, EntityProduct)>>::as_ref(&all_compound_inventories_by_product_id_in_this_page).into_iter().map(|x| x.0.receipt_id as i32).collect::<Vec<i32>>();
-
-        let all_related_receipts_store_currency_by_product_id_in_this_page_query = 
-            receipts::table
-                .inner_join(stores::table)
-                .inner_join(currencies::table)
-                .filter(receipts::columns::id.eq_any(receipt_ids_by_product_id))
-                .select(<(EntityReceipt, EntityStore, EntityCurrency)>::as_select());
-
-        let all_related_receipts_store_currency_by_product_id_in_this_page = all_related_receipts_store_currency_by_product_id_in_this_page_query.get_results::<(EntityReceipt, EntityStore, EntityCurrency)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-
-        Ok(ServiceCollection {
-            partial_collection: converter.convert_to_customized_inventories_response(all_compound_inventories_by_product_id_in_this_page, all_related_receipts_store_currency_by_product_id_in_this_page),
-            total_count: count
-        })
-    }
-
-    pub async fn get_customized_inventories_by_receipt_id(&self, receipt_id: i32, pagination: &Pagination) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
-        let converter = ConverterService::new();
-        let fallbacks_service = FallbacksService::new();
-        let conn = &mut self.repository.pool.get().or_else(
-            |e| {
-                tracing::error!("database connection broken: {}", e);
-                Err(ApiError::DatabaseConnectionBroken)
+            match query_filters.has_alias {
+                Some(true) => sql_filters = sql_filters.filter(products::alias.is_not_null().and(products::alias.ne(""))),
+                Some(false) => sql_filters = sql_filters.filter(products::alias.is_null().or(products::alias.eq(""))),
+                None => {}
             }
-        )?;
-
-        let count: i64 = inventories::table
-            .filter(inventories::columns::receipt_id.eq(receipt_id))
-            .select(count(inventories::columns::id))
-            .first(conn)
-            .or_else(|_e| Err(ApiError::NoRecord))?;
-        
-        let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
-
-        let all_compound_inventories_by_receipt_id_in_this_page_query = 
-            inventories::table
-                .inner_join(products::table)
-                .filter(inventories::columns::receipt_id.eq(receipt_id))
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<(EntityInventory, EntityProduct)>::as_select());
-
-        let all_compound_inventories_by_receipt_id_in_this_page = all_compound_inventories_by_receipt_id_in_this_page_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-        let receipt_store_currency_query = 
-            receipts::table
-                .inner_join(stores::table)
-                .inner_join(currencies::table)
-                .filter(receipts::columns::id.eq(receipt_id))
-                .select(<(EntityReceipt, EntityStore, EntityCurrency)>::as_select());
-
-        let receipt_store_currency = receipt_store_currency_query.get_results::<(EntityReceipt, EntityStore, EntityCurrency)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-
-        Ok(ServiceCollection {
-            partial_collection: converter.convert_to_customized_inventories_response(all_compound_inventories_by_receipt_id_in_this_page, receipt_store_currency),
-            total_count: count
-        })
-    }
-
-    pub async fn get_customized_inventories_by_store_id(&self, store_id: i32, pagination: &Pagination) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
-        let converter = ConverterService::new();
-        let fallbacks_service = FallbacksService::new();
-        let conn = &mut self.repository.pool.get().or_else(
-            |e| {
-                tracing::error!("database connection broken: {}", e);
-                Err(ApiError::DatabaseConnectionBroken)
+            // a taxed line has a tax amount, an exempt line or a zero rate has none
+            match query_filters.taxed {
+                Some(true) => sql_filters = sql_filters.filter(inventories::post_tax_amount.ne(inventories::pre_tax_amount)),
+                Some(false) => sql_filters = sql_filters.filter(inventories::post_tax_amount.eq(inventories::pre_tax_amount)),
+                None => {}
             }
-        )?;
-
-        let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
-
-        let all_related_receipts_by_store_id_query = 
-            receipts::table
-                .inner_join(stores::table)
-                .inner_join(currencies::table)
-                .filter(receipts::columns::store_id.eq(store_id))
-                .select(<(EntityReceipt, EntityStore, EntityCurrency)>::as_select());
-
-        let receipts_store_currency = all_related_receipts_by_store_id_query.get_results::<(EntityReceipt, EntityStore, EntityCurrency)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
 
-        let receipt_ids = AsRef::<Vec<(EntityReceipt, EntityStore, EntityCurrency)>>::as_ref(&receipts_store_currency).into_iter().map(|t| t.0.id).collect::<Vec<i32>>();
-
-        let count: i64 = inventories::table
-            .filter(inventories::columns::receipt_id.eq_any(&receipt_ids))
-            .select(count(inventories::columns::id))
-            .first(conn)
-            .or_else(|_e| Err(ApiError::NoRecord))?;
-        
-
-        let all_compound_inventories_by_receipt_ids_in_this_page_query = 
-            inventories::table
-                .inner_join(products::table)
-                .filter(inventories::columns::receipt_id.eq_any(&receipt_ids))
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<(EntityInventory, EntityProduct)>::as_select());
-
-        let all_compound_inventories_by_receipt_ids_in_this_page = all_compound_inventories_by_receipt_ids_in_this_page_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-        Ok(ServiceCollection {
-            partial_collection: converter.convert_to_customized_inventories_response(all_compound_inventories_by_receipt_ids_in_this_page, receipts_store_currency),
-            total_count: count
-        })
-    }
-
-    pub async fn get_customized_inventories_by_currency_id(&self, currency_id: i32, pagination: &Pagination) -> Result<ServiceCollection<ResponseCustomizedInventory>, ApiError> {
-        let converter = ConverterService::new();
-        let fallbacks_service = FallbacksService::new();
-        let conn = &mut self.repository.pool.get().or_else(
-            |e| {
-                tracing::error!("database connection broken: {}", e);
-                Err(ApiError::DatabaseConnectionBroken)
+            if let Some(min_price) = query_filters.min_price.clone() {
+                sql_filters = sql_filters.filter(inventories::price.ge(min_price));
+            }
+            if let Some(max_price) = query_filters.max_price.clone() {
+                sql_filters = sql_filters.filter(inventories::price.le(max_price));
+            }
+            if let Some(min_quantity) = query_filters.min_quantity.clone() {
+                sql_filters = sql_filters.filter(inventories::quantity.ge(min_quantity));
+            }
+            if let Some(max_quantity) = query_filters.max_quantity.clone() {
+                sql_filters = sql_filters.filter(inventories::quantity.le(max_quantity));
             }
-        )?;
-
-        let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
-        let all_related_receipts_by_currency_id_query = 
-            receipts::table
-                .inner_join(stores::table)
-                .inner_join(currencies::table)
-                .filter(receipts::columns::currency_id.eq(currency_id))
-                .select(<(EntityReceipt, EntityStore, EntityCurrency)>::as_select());
+            if let Some(product_ids) = &query_filters.product_ids {
+                sql_filters = sql_filters.filter(inventories::product_id.eq_any(product_ids));
+            }
+            if let Some(store_ids) = &query_filters.store_ids {
+                sql_filters = sql_filters.filter(receipts::store_id.eq_any(store_ids));
+            }
+            if let Some(currency_ids) = &query_filters.currency_ids {
+                sql_filters = sql_filters.filter(receipts::currency_id.eq_any(currency_ids));
+            }
 
-        let receipts_store_currency = all_related_receipts_by_currency_id_query.get_results::<(EntityReceipt, EntityStore, EntityCurrency)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+            sql_filters = match scope {
+                CustomizedInventoryScope::All => sql_filters,
+                CustomizedInventoryScope::Product(product_id) => sql_filters.filter(inventories::product_id.eq(product_id)),
+                CustomizedInventoryScope::Receipt(receipt_id) => sql_filters.filter(inventories::receipt_id.eq(receipt_id)),
+                CustomizedInventoryScope::Store(store_id) => sql_filters.filter(receipts::store_id.eq(store_id)),
+                CustomizedInventoryScope::Currency(currency_id) => sql_filters.filter(receipts::currency_id.eq(currency_id))
+            };
 
-        let receipt_ids = AsRef::<Vec<(EntityReceipt, EntityStore, EntityCurrency)>>::as_ref(&receipts_store_currency).into_iter().map(|t| t.0.id).collect::<Vec<i32>>();
+            sql_filters
+        };
 
-        let count: i64 = inventories::table
-            .filter(inventories::columns::receipt_id.eq_any(&receipt_ids))
-            .select(count(inventories::columns::id))
-            .first(conn)
-            .or_else(|_e| Err(ApiError::NoRecord))?;
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(build_query().select(count(inventories::columns::id)).first::<i64>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
         
+        let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
-        let all_compound_inventories_by_currency_ids_in_this_page_query = 
-            inventories::table
-                .inner_join(products::table)
-                .filter(inventories::columns::receipt_id.eq_any(&receipt_ids))
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<(EntityInventory, EntityProduct)>::as_select());
+        let mut sorted_query = build_query();
+        if let Some((cursor, cursor_direction)) = &cursor {
+            let greater = cursor_service.is_seeking_greater(*cursor_direction, primary_sort_key.direction);
+            sorted_query = match primary_sort_key.field {
+                CustomizedInventorySortField::Id => cursor_service.seek(sorted_query, inventories::id, cursor_service.get_cursor_value::<i32>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::Price => cursor_service.seek(sorted_query, inventories::price, cursor_service.get_cursor_value::<BigDecimal>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::Quantity => cursor_service.seek(sorted_query, inventories::quantity, cursor_service.get_cursor_value::<BigDecimal>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::ProductName => cursor_service.seek(sorted_query, products::name, cursor_service.get_cursor_value::<String>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::TransactionDate => cursor_service.seek(sorted_query, receipts::transaction_date, cursor_service.get_cursor_value::<DateTime<Utc>>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::StoreName => cursor_service.seek(sorted_query, stores::name, cursor_service.get_cursor_value::<String>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::Currency => cursor_service.seek(sorted_query, currencies::name, cursor_service.get_cursor_value::<String>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::CreatedAt => cursor_service.seek(sorted_query, inventories::created_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, inventories::id, cursor.id, greater),
+                CustomizedInventorySortField::UpdatedAt => cursor_service.seek(sorted_query, inventories::updated_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, inventories::id, cursor.id, greater)
+            };
+        }
 
-        let all_compound_inventories_by_currency_ids_in_this_page = all_compound_inventories_by_currency_ids_in_this_page_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        // rows before the cursor are read in the reversed order and flipped back by paginate
+        let is_before = matches!(cursor, Some((_, CursorDirection::Before)));
+        for sort_key in &sort_keys {
+            let direction = if is_before { sort_key.direction.reverse() } else { sort_key.direction };
+            sorted_query = match sort_key.field {
+                CustomizedInventorySortField::Id => sorting_service.order_by(sorted_query, inventories::id, direction),
+                CustomizedInventorySortField::Price => sorting_service.order_by(sorted_query, inventories::price, direction),
+                CustomizedInventorySortField::Quantity => sorting_service.order_by(sorted_query, inventories::quantity, direction),
+                CustomizedInventorySortField::ProductName => sorting_service.order_by(sorted_query, products::name, direction),
+                CustomizedInventorySortField::TransactionDate => sorting_service.order_by(sorted_query, receipts::transaction_date, direction),
+                CustomizedInventorySortField::StoreName => sorting_service.order_by(sorted_query, stores::name, direction),
+                CustomizedInventorySortField::Currency => sorting_service.order_by(sorted_query, currencies::name, direction),
+                CustomizedInventorySortField::CreatedAt => sorting_service.order_by(sorted_query, inventories::created_at, direction),
+                CustomizedInventorySortField::UpdatedAt => sorting_service.order_by(sorted_query, inventories::updated_at, direction)
+            };
+        }
+        sorted_query = sorting_service.order_by_id(sorted_query, inventories::id, primary_sort_key.direction, is_before);
+
+        // one extra row tells whether there are more rows after this page
+        let rows = sorted_query
+            .limit(per_page + 1)
+            .offset(if cursor.is_some() { 0 } else { page_offset })
+            .select(<(EntityInventory, EntityProduct, EntityReceipt, EntityStore, EntityCurrency)>::as_select())
+            .get_results::<(EntityInventory, EntityProduct, EntityReceipt, EntityStore, EntityCurrency)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (all_compound_inventories_in_this_page, next_cursor, prev_cursor) = cursor_service.paginate(rows, per_page, page_offset, cursor.as_ref().map(|c| c.1), cursor_service.get_cursor_signature(&sort_keys, &primary_sort_key), |row| {
+            let value = match primary_sort_key.field {
+                CustomizedInventorySortField::Id => json!(row.0.id),
+                CustomizedInventorySortField::Price => json!(row.0.price),
+                CustomizedInventorySortField::Quantity => json!(row.0.quantity),
+                CustomizedInventorySortField::ProductName => json!(row.1.name),
+                CustomizedInventorySortField::TransactionDate => json!(row.2.transaction_date),
+                CustomizedInventorySortField::StoreName => json!(row.3.name),
+                CustomizedInventorySortField::Currency => json!(row.4.name),
+                CustomizedInventorySortField::CreatedAt => json!(row.0.created_at),
+                CustomizedInventorySortField::UpdatedAt => json!(row.0.updated_at)
+            };
+            (value, row.0.id)
+        });
+        let receipt_ids = all_compound_inventories_in_this_page.iter().map(|inv| inv.2.id).collect::<Vec<i32>>();
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &receipt_ids)?;
 
         Ok(ServiceCollection {
-            partial_collection: converter.convert_to_customized_inventories_response(all_compound_inventories_by_currency_ids_in_this_page, receipts_store_currency),
-            total_count: count
+            partial_collection: all_compound_inventories_in_this_page.into_iter().map(|inv| converter.convert_to_customized_inventory_response(inv.0, inv.1, inv.2, inv.3, inv.4, conversion.as_ref())).collect(),
+            total_count: count,
+            next_cursor,
+            prev_cursor
         })
     }
 }
\ No newline at end of file
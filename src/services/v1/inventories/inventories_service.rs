@@ -1,9 +1,11 @@
-use bigdecimal::{BigDecimal, FromPrimitive};
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
 use diesel::{
-    dsl::count, insert_into, update, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper
+    dsl::count, insert_into, pg::Pg, update, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper
 };
+use serde_json::json;
 
-use crate::{models::v1::{collections::service_collection::ServiceCollection, entities::{entity_inventory::{EntityInventory, NewEntityInventory}, entity_product::EntityProduct}, errors::api_error::ApiError, forms::patch_payload::PatchInventoryPayload, parameters::pagination::Pagination, responses::response_inventory::ResponseInventory}, repository::DbRepository, schema::{inventories, products}, services::v1::{converters::converters_service::ConverterService, fallbacks::fallbacks_service::FallbacksService}};
+use crate::{models::v1::{collections::service_collection::ServiceCollection, entities::{entity_inventory::{EntityInventory, NewEntityInventory}, entity_product::EntityProduct}, errors::api_error::ApiError, forms::patch_payload::PatchInventoryPayload, parameters::{conversion::Conversion, cursor::CursorDirection, pagination::Pagination, query_filters::{TimestampFilters}, sorting::{InventorySortField, SortDirection, SortKey, Sorting}}, responses::response_inventory::ResponseInventory}, repository::DbRepository, schema::{currencies, inventories, products, receipts}, services::v1::{batches::batches_service::BatchService, converters::converters_service::ConverterService, currencies::currencies_service::CurrencyService, cursors::cursors_service::CursorService, decimals::decimals_service::DEFAULT_MINOR_UNITS, exchange_rates::exchange_rates_service::ExchangeRateService, fallbacks::fallbacks_service::FallbacksService, sortings::sortings_service::SortingService, tax_rates::tax_rates_service::TaxRateService, totals::totals_service::TotalService}};
 
 pub struct InventoryService<'a> {
     repository: &'a DbRepository
@@ -16,7 +18,7 @@ impl<'a> InventoryService<'a> {
         }
     }
 
-    pub async fn get_inventory(&self, id: i32) -> Result<ResponseInventory, ApiError> {
+    pub async fn get_inventory(&self, id: i32, conversion: &Conversion) -> Result<ResponseInventory, ApiError> {
         let converter = ConverterService::new();
         let conn = &mut self.repository.pool.get().or_else(
             |e| {
@@ -37,36 +39,134 @@ impl<'a> InventoryService<'a> {
         })?;
 
 
-        let inventory_response = converter.convert_to_inventory_response(inventory, product);
+        let minor_units = CurrencyService::new(self.repository).get_minor_units_by_receipt_ids(conn, &[inventory.receipt_id])?.get(&inventory.receipt_id).copied().unwrap_or(DEFAULT_MINOR_UNITS);
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &[inventory.receipt_id])?;
+        let inventory_response = converter.convert_to_inventory_response(inventory, product, minor_units, conversion.as_ref());
 
         Ok(inventory_response)
     }
 
-    pub async fn get_inventories(&self, pagination: &Pagination) -> Result<ServiceCollection<ResponseInventory>, ApiError> {
+    pub async fn get_inventories_by_ids(&self, ids: &[i32], conversion: &Conversion) -> Result<(Vec<ResponseInventory>, Vec<i32>), ApiError> {
+        let converter = ConverterService::new();
+        let batch_service = BatchService::new();
+        let ids = batch_service.get_unique_ids(ids)?;
+        let conn = &mut self.repository.pool.get().or_else(|e| {
+            tracing::error!("database connection broken: {}", e);
+            Err(ApiError::DatabaseConnectionBroken)
+        })?;
+
+        let inventories_query = 
+            inventories::table
+                .inner_join(products::table)
+                .filter(inventories::id.eq_any(&ids))
+                .select(<(EntityInventory, EntityProduct)>::as_select());
+
+        let inventories_list = inventories_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (inventories_list, not_found) = batch_service.arrange(&ids, inventories_list, |i| i.0.id);
+        let receipt_ids = inventories_list.iter().map(|i| i.0.receipt_id).collect::<Vec<i32>>();
+        let minor_units = CurrencyService::new(self.repository).get_minor_units_by_receipt_ids(conn, &receipt_ids)?;
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &receipt_ids)?;
+
+        Ok((converter.convert_to_all_inventories_response(inventories_list, &minor_units, conversion.as_ref()), not_found))
+    }
+
+    pub async fn get_inventories(&self, pagination: &Pagination, timestamp_filters: &TimestampFilters, sorting: &Sorting, conversion: &Conversion) -> Result<ServiceCollection<ResponseInventory>, ApiError> {
         let converter = ConverterService::new();
         let fallbacks_service = FallbacksService::new();
+        let sorting_service = SortingService::new();
+        let sort_keys = sorting_service.parse_sort::<InventorySortField>(sorting)?;
+        let primary_sort_key = sort_keys.first().copied().unwrap_or(SortKey { field: InventorySortField::Id, direction: SortDirection::Asc });
+        let cursor_service = CursorService::new();
+        let cursor = cursor_service.get_cursor(pagination, &sort_keys, &primary_sort_key)?;
         let conn = &mut self.repository.pool.get().or_else(
             |e| {
                 tracing::error!("database connection broken: {}", e);
                 Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let count: i64 = inventories::table.select(count(inventories::columns::id)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let build_query = || {
+            let mut query = 
+                inventories::table
+                    .inner_join(products::table)
+                    .into_boxed::<Pg>();
+            if let Some(created_from) = timestamp_filters.created_from {
+                query = query.filter(inventories::created_at.ge(created_from));
+            }
+            if let Some(created_to) = timestamp_filters.created_to {
+                query = query.filter(inventories::created_at.le(created_to));
+            }
+            if let Some(updated_from) = timestamp_filters.updated_from {
+                query = query.filter(inventories::updated_at.ge(updated_from));
+            }
+            if let Some(updated_to) = timestamp_filters.updated_to {
+                query = query.filter(inventories::updated_at.le(updated_to));
+            }
+            query
+        };
+
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(build_query().select(count(inventories::columns::id)).first::<i64>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
         
         let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
-        let all_compound_inventories_in_this_page_query = 
-            inventories::table
-                .inner_join(products::table)
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<(EntityInventory, EntityProduct)>::as_select());
+        let mut all_compound_inventories_in_this_page_query = build_query();
+        if let Some((cursor, cursor_direction)) = &cursor {
+            let greater = cursor_service.is_seeking_greater(*cursor_direction, primary_sort_key.direction);
+            all_compound_inventories_in_this_page_query = match primary_sort_key.field {
+                InventorySortField::Id => cursor_service.seek(all_compound_inventories_in_this_page_query, inventories::id, cursor_service.get_cursor_value::<i32>(cursor)?, inventories::id, cursor.id, greater),
+                InventorySortField::Price => cursor_service.seek(all_compound_inventories_in_this_page_query, inventories::price, cursor_service.get_cursor_value::<BigDecimal>(cursor)?, inventories::id, cursor.id, greater),
+                InventorySortField::Quantity => cursor_service.seek(all_compound_inventories_in_this_page_query, inventories::quantity, cursor_service.get_cursor_value::<BigDecimal>(cursor)?, inventories::id, cursor.id, greater),
+                InventorySortField::ProductName => cursor_service.seek(all_compound_inventories_in_this_page_query, products::name, cursor_service.get_cursor_value::<String>(cursor)?, inventories::id, cursor.id, greater),
+                InventorySortField::CreatedAt => cursor_service.seek(all_compound_inventories_in_this_page_query, inventories::created_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, inventories::id, cursor.id, greater),
+                InventorySortField::UpdatedAt => cursor_service.seek(all_compound_inventories_in_this_page_query, inventories::updated_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, inventories::id, cursor.id, greater)
+            };
+        }
 
-        let all_compound_inventories_in_this_page = all_compound_inventories_in_this_page_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        // rows before the cursor are read in the reversed order and flipped back by paginate
+        let is_before = matches!(cursor, Some((_, CursorDirection::Before)));
+        for sort_key in &sort_keys {
+            let direction = if is_before { sort_key.direction.reverse() } else { sort_key.direction };
+            all_compound_inventories_in_this_page_query = match sort_key.field {
+                InventorySortField::Id => sorting_service.order_by(all_compound_inventories_in_this_page_query, inventories::id, direction),
+                InventorySortField::Price => sorting_service.order_by(all_compound_inventories_in_this_page_query, inventories::price, direction),
+                InventorySortField::Quantity => sorting_service.order_by(all_compound_inventories_in_this_page_query, inventories::quantity, direction),
+                InventorySortField::ProductName => sorting_service.order_by(all_compound_inventories_in_this_page_query, products::name, direction),
+                InventorySortField::CreatedAt => sorting_service.order_by(all_compound_inventories_in_this_page_query, inventories::created_at, direction),
+                InventorySortField::UpdatedAt => sorting_service.order_by(all_compound_inventories_in_this_page_query, inventories::updated_at, direction)
+            };
+        }
+        all_compound_inventories_in_this_page_query = sorting_service.order_by_id(all_compound_inventories_in_this_page_query, inventories::id, primary_sort_key.direction, is_before);
+
+        // one extra row tells whether there are more rows after this page
+        let rows = all_compound_inventories_in_this_page_query
+            .limit(per_page + 1)
+            .offset(if cursor.is_some() { 0 } else { page_offset })
+            .select(<(EntityInventory, EntityProduct)>::as_select())
+            .get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (all_compound_inventories_in_this_page, next_cursor, prev_cursor) = cursor_service.paginate(rows, per_page, page_offset, cursor.as_ref().map(|c| c.1), cursor_service.get_cursor_signature(&sort_keys, &primary_sort_key), |row| {
+            let value = match primary_sort_key.field {
+                InventorySortField::Id => json!(row.0.id),
+                InventorySortField::Price => json!(row.0.price),
+                InventorySortField::Quantity => json!(row.0.quantity),
+                InventorySortField::ProductName => json!(row.1.name),
+                InventorySortField::CreatedAt => json!(row.0.created_at),
+                InventorySortField::UpdatedAt => json!(row.0.updated_at)
+            };
+            (value, row.0.id)
+        });
+        let receipt_ids = all_compound_inventories_in_this_page.iter().map(|i| i.0.receipt_id).collect::<Vec<i32>>();
+        let minor_units = CurrencyService::new(self.repository).get_minor_units_by_receipt_ids(conn, &receipt_ids)?;
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &receipt_ids)?;
 
         Ok(ServiceCollection {
-            partial_collection: converter.convert_to_all_inventories_response(all_compound_inventories_in_this_page),
-            total_count: count
+            partial_collection: converter.convert_to_all_inventories_response(all_compound_inventories_in_this_page, &minor_units, conversion.as_ref()),
+            total_count: count,
+            next_cursor,
+            prev_cursor
         })
     }
 
@@ -87,39 +187,106 @@ impl<'a> InventoryService<'a> {
         Ok(entity_inventory.id)
     }
 
-    pub async fn patch_inventory(&self, id: i32, inventory: &PatchInventoryPayload) -> Result<(), ApiError> {
+    pub async fn get_inventory_version(&self, id: i32) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        inventories::table.filter(inventories::id.eq(id)).select(inventories::version).get_result::<i32>(conn).map_err(|e| {
+            tracing::warn!("try to get version of a non existed inventory ({}): {}", id, e);
+            ApiError::NoRecord
+        })
+    }
+
+    // checked before the patch command is queued, so the client gets the error instead of the writer
+    pub async fn validate_patch_inventory(&self, id: i32, inventory: &PatchInventoryPayload) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        self.get_patched_inventory(conn, id, inventory).map(|_| ())
+    }
+
+    pub async fn patch_inventory(&self, id: i32, inventory: &PatchInventoryPayload, expected_version: i32) -> Result<(), ApiError> {
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
+        let mut entity_inventory = self.get_patched_inventory(conn, id, inventory)?;
+        entity_inventory.version = expected_version + 1;
+        let affected_rows = update(inventories::table).filter(inventories::id.eq(id)).filter(inventories::version.eq(expected_version)).set(&entity_inventory).execute(conn).map_err(|e| {
+            tracing::error!("update inventory entity failed: {}", e);
+            ApiError::UpdateInventoryFailed
+        })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("inventory {} is modified by others, its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
+
+        tracing::debug!("patch inventory {} successfully", id);
+        Ok(())
+    }
+
+    fn get_patched_inventory(&self, conn: &mut PgConnection, id: i32, inventory: &PatchInventoryPayload) -> Result<EntityInventory, ApiError> {
         let mut entity_inventory: EntityInventory = inventories::table.filter(inventories::id.eq(id)).select(<EntityInventory>::as_select()).get_result::<EntityInventory>(conn).or_else(|e| {
             tracing::error!("try to uodate a non existed inventory ({}): {}", id, e);
             Err(ApiError::NoRecord)
         })?;
 
-        if inventory.price.is_some() && inventory.quantity.is_some() {
-            if let Some(be_price) = BigDecimal::from_f64(inventory.price.expect("price should not be none")) {
-                entity_inventory.price = be_price;
-            }
+        let minor_units = inventories::table.inner_join(receipts::table.inner_join(currencies::table)).filter(inventories::id.eq(id)).select(currencies::minor_units).get_result::<i32>(conn).map_err(|e| {
+            tracing::error!("Unable to get the minor units of inventory {}: {}", id, e);
+            ApiError::NoRecord
+        })?;
 
-            entity_inventory.quantity = inventory.quantity.expect("quantity should not be none");
+        let total_service = TotalService::new();
+        let old_line_total = total_service.get_line_total(&entity_inventory.line_price, &entity_inventory.discount);
+        let old_tax_rate_id = entity_inventory.tax_rate_id;
+        if let Some(price) = &inventory.price {
+            entity_inventory.price = total_service.get_amount(price, minor_units)?;
         }
-        else if inventory.price.is_some() {
-            if let Some(be_price) = BigDecimal::from_f64(inventory.price.expect("price should not be none")) {
-                entity_inventory.price = be_price;
-            }
+
+        if let Some(quantity) = &inventory.quantity {
+            entity_inventory.quantity = total_service.get_quantity(quantity)?;
         }
-        else if inventory.quantity.is_some() {
-            entity_inventory.quantity = inventory.quantity.expect("quantity should not be none");
+
+        if let Some(unit) = inventory.unit {
+            entity_inventory.unit = unit.name().to_string();
         }
 
-        update(inventories::table).filter(inventories::id.eq(id)).set(&entity_inventory).execute(conn).or_else(|e| {
-            tracing::error!("update inventory entity failed: {}", e);
-            Err(ApiError::UpdateInventoryFailed)
-        })?;
+        // a printed line price is kept unless the price or the quantity behind it changes
+        if let Some(line_price) = &inventory.line_price {
+            entity_inventory.line_price = total_service.get_amount(line_price, minor_units)?;
+        }
+        else if inventory.price.is_some() || inventory.quantity.is_some() {
+            entity_inventory.line_price = total_service.get_line_price(&entity_inventory.price, &entity_inventory.quantity, minor_units);
+        }
 
-        tracing::debug!("patch inventory {} successfully", id);
-        Ok(())
+        // a kept discount is checked as well, the line price under it may have dropped
+        let discount = inventory.discount.as_ref().unwrap_or(&entity_inventory.discount);
+        entity_inventory.discount = total_service.get_discount(discount, &entity_inventory.line_price, minor_units)?;
+
+        if let Some(tax_rate_id) = inventory.tax_rate_id {
+            entity_inventory.tax_rate_id = tax_rate_id;
+        }
+
+        // a new rate sets the tax amounts again, otherwise the stored ones are kept or scaled to a new line total
+        let line_total = total_service.get_line_total(&entity_inventory.line_price, &entity_inventory.discount);
+        let is_tax_rate_changed = entity_inventory.tax_rate_id != old_tax_rate_id;
+        if is_tax_rate_changed || line_total != old_line_total {
+            let scaled_tax_amounts = if is_tax_rate_changed { None } else { total_service.get_scaled_tax_amounts(&entity_inventory.pre_tax_amount, &entity_inventory.post_tax_amount, &old_line_total, &line_total, minor_units) };
+            (entity_inventory.pre_tax_amount, entity_inventory.post_tax_amount) = match scaled_tax_amounts {
+                Some(tax_amounts) => tax_amounts,
+                None => {
+                    let tax_rate = TaxRateService::new(self.repository).find_tax_rate(conn, entity_inventory.tax_rate_id)?;
+                    total_service.get_tax_amounts(&line_total, tax_rate.as_ref(), minor_units)
+                }
+            };
+        }
+
+        Ok(entity_inventory)
     }
 }
\ No newline at end of file
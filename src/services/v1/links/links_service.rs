@@ -0,0 +1,100 @@
+use axum::http::{header::LINK, HeaderMap, HeaderValue, Uri};
+
+use crate::{
+    models::v1::{
+        collections::service_collection::ServiceCollection, 
+        parameters::pagination::Pagination, 
+        responses::response_collection::{ResponsePagination, ResponsePaginationLinks}
+    }, 
+    services::v1::fallbacks::fallbacks_service::FallbacksService
+};
+
+const PAGING_PARAMETERS: [&str; 4] = ["limit", "offset", "after", "before"];
+
+pub struct LinkService {
+}
+
+impl LinkService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    pub fn get_pagination<T>(&self, uri: &Uri, pagination: &Pagination, collection: &ServiceCollection<T>) -> ResponsePagination {
+        let fallbacks_service = FallbacksService::new();
+        let (page_offset, per_page) = fallbacks_service.fallback_pagination(pagination);
+        let is_cursor_pagination = pagination.after.is_some() || pagination.before.is_some();
+        let first = Some(self.get_page_link(uri, per_page, vec![("offset", "0".to_string())]));
+
+        let links = if is_cursor_pagination {
+            ResponsePaginationLinks {
+                first,
+                prev: collection.prev_cursor.as_ref().map(|c| self.get_page_link(uri, per_page, vec![("before", c.clone())])),
+                next: collection.next_cursor.as_ref().map(|c| self.get_page_link(uri, per_page, vec![("after", c.clone())])),
+                last: None
+            }
+        }
+        else {
+            // without a total, a full page is the only hint that more rows may follow
+            let has_next = match collection.total_count {
+                Some(total) => page_offset + per_page < total,
+                None => collection.partial_collection.len() as i64 == per_page
+            };
+            let last_offset = collection.total_count.map(|total| ((total - 1).max(0) / per_page) * per_page);
+
+            ResponsePaginationLinks {
+                first,
+                prev: (page_offset > 0).then(|| self.get_page_link(uri, per_page, vec![("offset", (page_offset - per_page).max(0).to_string())])),
+                next: has_next.then(|| self.get_page_link(uri, per_page, vec![("offset", (page_offset + per_page).to_string())])),
+                last: last_offset.map(|offset| self.get_page_link(uri, per_page, vec![("offset", offset.to_string())]))
+            }
+        };
+
+        ResponsePagination {
+            limit: per_page,
+            offset: (!is_cursor_pagination).then_some(page_offset),
+            after: pagination.after.clone(),
+            before: pagination.before.clone(),
+            next_cursor: collection.next_cursor.clone(),
+            prev_cursor: collection.prev_cursor.clone(),
+            links
+        }
+    }
+
+    // keeps filters and sorting of the request, only the paging parameters are replaced
+    fn get_page_link(&self, uri: &Uri, per_page: i64, page_parameters: Vec<(&str, String)>) -> String {
+        let mut parameters = uri.query()
+            .and_then(|q| serde_urlencoded::from_str::<Vec<(String, String)>>(q).ok())
+            .unwrap_or_default();
+        parameters.retain(|(k, _)| !PAGING_PARAMETERS.contains(&k.as_str()));
+        parameters.push(("limit".to_string(), per_page.to_string()));
+        parameters.extend(page_parameters.into_iter().map(|(k, v)| (k.to_string(), v)));
+
+        let query = serde_urlencoded::to_string(parameters).unwrap_or_default();
+        format!("{}?{}", uri.path(), query)
+    }
+
+    // RFC 8288 web linking
+    pub fn get_link_headers(&self, pagination: &ResponsePagination) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let links = [
+            ("first", &pagination.links.first),
+            ("prev", &pagination.links.prev),
+            ("next", &pagination.links.next),
+            ("last", &pagination.links.last)
+        ];
+
+        let link = links.iter()
+            .filter_map(|(rel, url)| url.as_ref().map(|u| format!("<{}>; rel=\"{}\"", u, rel)))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        if let Ok(link_value) = HeaderValue::from_str(&link) {
+            if !link.is_empty() {
+                headers.insert(LINK, link_value);
+            }
+        }
+
+        headers
+    }
+}
@@ -0,0 +1 @@
+pub mod links_service;
\ No newline at end of file
@@ -0,0 +1,48 @@
+use crate::models::v1::parameters::matching::MatchMode;
+
+// backslash is the default ESCAPE character of LIKE/ILIKE in postgres
+const LIKE_ESCAPE: char = '\\';
+
+pub struct MatcherService {
+}
+
+impl MatcherService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    // the pattern is meant for ILIKE, so every mode is case-insensitive and user input never acts as a wildcard
+    pub fn get_pattern(&self, value: &str, mode: MatchMode) -> String {
+        let escaped = self.escape(value.trim());
+        match mode {
+            MatchMode::Exact => escaped,
+            MatchMode::Prefix => format!("{}%", escaped),
+            MatchMode::Contains => format!("%{}%", escaped),
+            // characters have to appear in order but may have anything in between, e.g. "cke" matches "coke"
+            MatchMode::Fuzzy => {
+                let mut pattern = String::from("%");
+                for c in value.chars().filter(|c| !c.is_whitespace()) {
+                    pattern.push_str(&self.escape(&c.to_string()));
+                    pattern.push('%');
+                }
+                pattern
+            }
+        }
+    }
+
+    pub fn get_optional_pattern(&self, value: &Option<String>, mode: MatchMode) -> Option<String> {
+        value.as_ref().map(|v| self.get_pattern(v, mode))
+    }
+
+    fn escape(&self, value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if c == '%' || c == '_' || c == LIKE_ESCAPE {
+                escaped.push(LIKE_ESCAPE);
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+}
@@ -0,0 +1 @@
+pub mod matchers_service;
\ No newline at end of file
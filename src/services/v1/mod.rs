@@ -1,9 +1,26 @@
 pub mod receipts;
 pub mod stores;
 pub mod converters;
+pub mod cursors;
 pub mod fallbacks;
 pub mod currencies;
 pub mod products;
 pub mod inventories;
 pub mod validators;
-pub mod commands;
\ No newline at end of file
+pub mod commands;
+pub mod trash;
+pub mod preconditions;
+pub mod sortings;
+pub mod links;
+pub mod search;
+pub mod matchers;
+pub mod timezones;
+pub mod fields;
+pub mod batches;
+pub mod totals;
+pub mod tax_rates;
+pub mod reports;
+pub mod decimals;
+pub mod exchange_rates;
+pub mod user_settings;
+pub mod units;
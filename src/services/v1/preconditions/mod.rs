@@ -0,0 +1 @@
+pub mod preconditions_service;
\ No newline at end of file
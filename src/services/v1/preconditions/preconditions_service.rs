@@ -0,0 +1,36 @@
+use axum::http::{header::{ETAG, IF_MATCH}, HeaderMap, HeaderValue};
+
+use crate::models::v1::errors::api_error::ApiError;
+
+pub struct PreconditionService {
+}
+
+impl PreconditionService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    pub fn get_etag_headers(&self, version: i32) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let etag = HeaderValue::from_str(&format!("\"{}\"", version)).expect("a quoted number should be a valid header value");
+        headers.insert(ETAG, etag);
+        headers
+    }
+
+    // Returns the version which the writer should compare with, "*" matches whatever the current version is.
+    pub fn check_if_match(&self, headers: &HeaderMap, current_version: i32) -> Result<i32, ApiError> {
+        let if_match = headers.get(IF_MATCH).ok_or(ApiError::PreconditionRequired)?;
+        let if_match = if_match.to_str().map_err(|_e| ApiError::InvalidParameter)?;
+        let current_etag = format!("\"{}\"", current_version);
+
+        // If-Match uses the strong comparison, so a weak entity tag never matches
+        let is_matched = if_match.split(',').map(|t| t.trim()).any(|t| t == "*" || t == current_etag);
+        if !is_matched {
+            tracing::warn!("stale entity tag {}, current version is {}", if_match, current_version);
+            return Err(ApiError::PreconditionFailed);
+        }
+
+        Ok(current_version)
+    }
+}
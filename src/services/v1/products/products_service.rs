@@ -1,16 +1,45 @@
+use std::collections::HashMap;
+use chrono::NaiveDateTime;
 use diesel::{
-    dsl::{count, exists, select}, insert_into, ExpressionMethods, QueryDsl, RunQueryDsl, SaveChangesDsl, SelectableHelper, TextExpressionMethods
+    dsl::{count, exists, select}, insert_into, pg::Pg, sql_query, sql_types::{Int4, Int8, Nullable, Text}, update, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper
 };
+use serde_json::json;
 
 use crate::{
     models::v1::{
-        collections::service_collection::ServiceCollection, entities::entity_product::{EntityProduct, NewEntityProduct, UpdateEntityProduct}, errors::api_error::ApiError, forms::patch_payload::PatchProductPayload, parameters::pagination::Pagination, responses::response_product::ResponseProduct
+        collections::service_collection::ServiceCollection, entities::{entity_product::{EntityProduct, NewEntityProduct, UpdateEntityProduct}, entity_usage::EntityProductUsage}, errors::api_error::ApiError, forms::patch_payload::PatchProductPayload, parameters::{cursor::CursorDirection, pagination::{Pagination, DEFAULT_LIMIT, MAX_LIMIT}, query_filters::{KeywordFilters, TimestampFilters}, sorting::{ProductSortField, SortDirection, SortKey, Sorting}}, responses::response_product::{ResponseProduct, ResponseProductSuggestion}
     }, 
     repository::DbRepository, 
     schema::products, 
-    services::v1::{converters::converters_service::ConverterService, fallbacks::fallbacks_service::FallbacksService}
+    services::v1::{batches::batches_service::BatchService, converters::converters_service::ConverterService, cursors::cursors_service::CursorService, fallbacks::fallbacks_service::FallbacksService, matchers::matchers_service::MatcherService, sortings::sortings_service::SortingService}
 };
 
+const PRODUCT_SUGGESTIONS_SQL: &str = r#"
+SELECT p.id,
+       COUNT(r.id) AS purchase_count,
+       COALESCE(SUM(EXP(-EXTRACT(EPOCH FROM (NOW() - r.transaction_date)) / 86400.0 / 90.0) * CASE WHEN r.store_id = $2 THEN 3.0 ELSE 1.0 END), 0)::FLOAT8 AS score,
+       last_purchase.price AS last_price,
+       last_purchase.currency AS last_currency,
+       last_purchase.minor_units AS last_minor_units,
+       last_purchase.transaction_date AS last_purchased_at
+FROM products p
+LEFT JOIN inventories i ON i.product_id = p.id
+LEFT JOIN receipts r ON r.id = i.receipt_id AND r.deleted_at IS NULL
+LEFT JOIN LATERAL (
+    SELECT li.price, c.name AS currency, c.minor_units, lr.transaction_date
+    FROM inventories li
+    JOIN receipts lr ON lr.id = li.receipt_id AND lr.deleted_at IS NULL
+    JOIN currencies c ON c.id = lr.currency_id
+    WHERE li.product_id = p.id
+    ORDER BY (lr.store_id IS NOT DISTINCT FROM $2) DESC, lr.transaction_date DESC, li.id DESC
+    LIMIT 1
+) last_purchase ON TRUE
+WHERE $1::TEXT IS NULL OR p.name ILIKE $1
+GROUP BY p.id, p.name, last_purchase.price, last_purchase.currency, last_purchase.minor_units, last_purchase.transaction_date
+ORDER BY score DESC, purchase_count DESC, p.name, p.id
+LIMIT $3
+"#;
+
 pub struct ProductService<'a> {
     repository: &'a DbRepository
 }
@@ -43,30 +72,117 @@ impl<'a> ProductService<'a> {
         Ok(product_response)
     }
 
-    pub async fn get_products(&self, pagination: &Pagination) -> Result<ServiceCollection<ResponseProduct>, ApiError> {
+    pub async fn get_products_by_ids(&self, ids: &[i32]) -> Result<(Vec<ResponseProduct>, Vec<i32>), ApiError> {
+        let converter = ConverterService::new();
+        let batch_service = BatchService::new();
+        let ids = batch_service.get_unique_ids(ids)?;
+        let conn = &mut self.repository.pool.get().or_else(|e| {
+            tracing::error!("database connection broken: {}", e);
+            Err(ApiError::DatabaseConnectionBroken)
+        })?;
+
+        let products_query = 
+            products::table
+            .filter(products::id.eq_any(&ids))
+            .select(<EntityProduct>::as_select());
+
+        let products_list = products_query.get_results::<EntityProduct>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (products_list, not_found) = batch_service.arrange(&ids, products_list, |p| p.id);
+
+        Ok((converter.convert_to_all_products_response(products_list), not_found))
+    }
+
+    pub async fn get_products(&self, pagination: &Pagination, timestamp_filters: &TimestampFilters, sorting: &Sorting) -> Result<ServiceCollection<ResponseProduct>, ApiError> {
         let converter: ConverterService = ConverterService::new();
         let fallbacks_service = FallbacksService::new();
+        let sorting_service = SortingService::new();
+        let sort_keys = sorting_service.parse_sort::<ProductSortField>(sorting)?;
+        let primary_sort_key = sort_keys.first().copied().unwrap_or(SortKey { field: ProductSortField::Id, direction: SortDirection::Asc });
+        let cursor_service = CursorService::new();
+        let cursor = cursor_service.get_cursor(pagination, &sort_keys, &primary_sort_key)?;
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let count: i64 = products::table.select(count(products::columns::id)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let build_query = || {
+            let mut query = products::table.into_boxed::<Pg>();
+            if let Some(created_from) = timestamp_filters.created_from {
+                query = query.filter(products::created_at.ge(created_from));
+            }
+            if let Some(created_to) = timestamp_filters.created_to {
+                query = query.filter(products::created_at.le(created_to));
+            }
+            if let Some(updated_from) = timestamp_filters.updated_from {
+                query = query.filter(products::updated_at.ge(updated_from));
+            }
+            if let Some(updated_to) = timestamp_filters.updated_to {
+                query = query.filter(products::updated_at.le(updated_to));
+            }
+            query
+        };
+
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(build_query().select(count(products::columns::id)).first::<i64>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
         
         let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
-        let products_in_this_page_query = 
-            products::table
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<EntityProduct>::as_select());
+        let mut products_in_this_page_query = build_query();
+        if let Some((cursor, cursor_direction)) = &cursor {
+            let greater = cursor_service.is_seeking_greater(*cursor_direction, primary_sort_key.direction);
+            products_in_this_page_query = match primary_sort_key.field {
+                ProductSortField::Id => cursor_service.seek(products_in_this_page_query, products::id, cursor_service.get_cursor_value::<i32>(cursor)?, products::id, cursor.id, greater),
+                ProductSortField::Name => cursor_service.seek(products_in_this_page_query, products::name, cursor_service.get_cursor_value::<String>(cursor)?, products::id, cursor.id, greater),
+                ProductSortField::Alias => return Err(ApiError::InvalidCursor),
+                ProductSortField::Brand => return Err(ApiError::InvalidCursor),
+                ProductSortField::CreatedAt => cursor_service.seek(products_in_this_page_query, products::created_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, products::id, cursor.id, greater),
+                ProductSortField::UpdatedAt => cursor_service.seek(products_in_this_page_query, products::updated_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, products::id, cursor.id, greater)
+            };
+        }
 
-        let products_in_this_page = products_in_this_page_query.get_results::<EntityProduct>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        // rows before the cursor are read in the reversed order and flipped back by paginate
+        let is_before = matches!(cursor, Some((_, CursorDirection::Before)));
+        for sort_key in &sort_keys {
+            let direction = if is_before { sort_key.direction.reverse() } else { sort_key.direction };
+            products_in_this_page_query = match sort_key.field {
+                ProductSortField::Id => sorting_service.order_by(products_in_this_page_query, products::id, direction),
+                ProductSortField::Name => sorting_service.order_by(products_in_this_page_query, products::name, direction),
+                ProductSortField::Alias => sorting_service.order_by(products_in_this_page_query, products::alias, direction),
+                ProductSortField::Brand => sorting_service.order_by(products_in_this_page_query, products::brand, direction),
+                ProductSortField::CreatedAt => sorting_service.order_by(products_in_this_page_query, products::created_at, direction),
+                ProductSortField::UpdatedAt => sorting_service.order_by(products_in_this_page_query, products::updated_at, direction)
+            };
+        }
+        products_in_this_page_query = sorting_service.order_by_id(products_in_this_page_query, products::id, primary_sort_key.direction, is_before);
+
+        // one extra row tells whether there are more rows after this page
+        let rows = products_in_this_page_query
+            .limit(per_page + 1)
+            .offset(if cursor.is_some() { 0 } else { page_offset })
+            .select(<EntityProduct>::as_select())
+            .get_results::<EntityProduct>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (products_in_this_page, next_cursor, prev_cursor) = cursor_service.paginate(rows, per_page, page_offset, cursor.as_ref().map(|c| c.1), cursor_service.get_cursor_signature(&sort_keys, &primary_sort_key), |row| {
+            let value = match primary_sort_key.field {
+                ProductSortField::Id => json!(row.id),
+                ProductSortField::Name => json!(row.name),
+                ProductSortField::Alias => json!(row.alias),
+                ProductSortField::Brand => json!(row.brand),
+                ProductSortField::CreatedAt => json!(row.created_at),
+                ProductSortField::UpdatedAt => json!(row.updated_at)
+            };
+            (value, row.id)
+        });
 
         Ok({
             ServiceCollection { 
                 partial_collection: converter.convert_to_all_products_response(products_in_this_page),
-                total_count: count
+                total_count: count,
+                next_cursor,
+                prev_cursor
             }
         })
     }
@@ -117,17 +233,30 @@ impl<'a> ProductService<'a> {
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let entity_product = insert_into(products::table)
+        let product_id = insert_into(products::table)
             .values(product)
-            .get_result::<EntityProduct>(conn).or_else(|e| {
+            .returning(products::id)
+            .get_result::<i32>(conn).or_else(|e| {
                 tracing::error!("insert product entity failed: {}", e);
                 Err(ApiError::InsertProductFailed)
         })?;
 
-        Ok(entity_product.id)
+        Ok(product_id)
     }
 
-    pub async fn patch_product(&self, id: i32, product: &PatchProductPayload) -> Result<(), ApiError> {
+    pub async fn get_product_version(&self, id: i32) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        products::table.filter(products::id.eq(id)).select(products::version).get_result::<i32>(conn).map_err(|e| {
+            tracing::warn!("try to get version of a non existed product ({}): {}", id, e);
+            ApiError::NoRecord
+        })
+    }
+
+    pub async fn patch_product(&self, id: i32, product: &PatchProductPayload, expected_version: i32) -> Result<(), ApiError> {
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
@@ -203,42 +332,61 @@ impl<'a> ProductService<'a> {
             }
         }
 
-        updated_product.save_changes::<EntityProduct>(conn).or_else(|e| {
-            tracing::error!("update product entity failed: {}", e);
-            Err(ApiError::UpdateProductFailed)
-        })?;
+        let affected_rows = update(products::table.filter(products::id.eq(id)).filter(products::version.eq(expected_version)))
+            .set((&updated_product, products::version.eq(products::version + 1)))
+            .execute(conn).map_err(|e| {
+                tracing::error!("update product entity failed: {}", e);
+                ApiError::UpdateProductFailed
+            })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("product {} is not existed or its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
 
         tracing::debug!("patch product {} successfully", id);
         Ok(())
     }
 
-    pub async fn autocomplete_products(&self, keyword: &Option<String>) -> Result<ServiceCollection<ResponseProduct>, ApiError> {
+    pub async fn autocomplete_products(&self, keyword_filters: &KeywordFilters) -> Result<ServiceCollection<ResponseProductSuggestion>, ApiError> {
         let converter: ConverterService = ConverterService::new();
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let build_query = || {
-            let mut sql_filters = products::table.limit(20).into_boxed();
-            if let Some(kw) = &keyword {
-                let product_name_pattern = format!("%{}%", kw);
-                sql_filters = sql_filters.filter(products::name.like(product_name_pattern))
-            }
-
-            sql_filters
-        };
-
-        let count: i64 = build_query().select(count(products::columns::name)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-        let products_query = build_query().select(<EntityProduct>::as_select());
-
-        let products_list = products_query.get_results::<EntityProduct>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let matcher_service = MatcherService::new();
+        let keyword_pattern = matcher_service.get_optional_pattern(&keyword_filters.keyword, keyword_filters.match_mode.unwrap_or_default());
+        let limit = keyword_filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let usages = sql_query(PRODUCT_SUGGESTIONS_SQL)
+            .bind::<Nullable<Text>, _>(&keyword_pattern)
+            .bind::<Nullable<Int4>, _>(keyword_filters.store_id)
+            .bind::<Int8, _>(limit)
+            .load::<EntityProductUsage>(conn).or_else(|e| {
+                tracing::error!("rank product suggestions failed: {}", e);
+                Err(ApiError::NoRecord)
+            })?;
+
+        let ids: Vec<i32> = usages.iter().map(|u| u.id).collect();
+        let mut products_map: HashMap<i32, EntityProduct> = products::table
+            .filter(products::id.eq_any(&ids))
+            .select(<EntityProduct>::as_select())
+            .get_results::<EntityProduct>(conn).or_else(|_e| Err(ApiError::NoRecord))?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        // keep the ranking order of the usage query
+        let suggestions: Vec<ResponseProductSuggestion> = usages.into_iter().filter_map(|usage| {
+            products_map.remove(&usage.id).map(|product| converter.convert_to_product_suggestion_response(product, usage))
+        }).collect();
 
         Ok({
             ServiceCollection { 
-                partial_collection: converter.convert_to_all_products_response(products_list),
-                total_count: count
+                total_count: Some(suggestions.len() as i64),
+                partial_collection: suggestions,
+                next_cursor: None,
+                prev_cursor: None
             }
         })
     }
@@ -1,21 +1,31 @@
-use bigdecimal::{BigDecimal, FromPrimitive};
+use std::collections::HashMap;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::{
-    delete, dsl::{count, exists, not}, insert_into, select, update, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper
+    delete, dsl::{count, exists, not, now, sql, IntervalDsl}, insert_into, pg::Pg, select, sql_types::{Bool, Numeric}, update, BoolExpressionMethods, Connection, ExpressionMethods, NullableExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, PgTextExpressionMethods, SelectableHelper
 };
+use serde_json::json;
 use uuid::Uuid;
 
 use crate::{
     models::v1::{
         collections::service_collection::ServiceCollection, entities::{
-            entity_currency::{EntityCurrency, NewEntityCurrency}, entity_inventory::{EntityInventory, NewEntityInventory}, entity_product::{EntityProduct, NewEntityProduct}, entity_receipt::{EntityReceipt, NewEntityReceipt}, entity_store::{EntityStore, NewEntityStore}
-        }, errors::api_error::ApiError, forms::{create_payload::{CreateCurrencyInReceiptPayload, CreateProductInReceiptPayload, CreateReceiptPayload, CreateStoreInReceiptPayload}, patch_payload::PatchReceiptPayload}, parameters::pagination::Pagination, responses::response_receipt::{ResponseCreateReceipt, ResponseReceipt}
+            entity_currency::{EntityCurrency, NewEntityCurrency}, entity_inventory::{EntityInventory, NewEntityInventory}, entity_product::{EntityProduct, NewEntityProduct}, entity_receipt::{EntityReceipt, NewEntityReceipt, UpdateEntityReceipt}, entity_store::{EntityStore, NewEntityStore}, entity_total::{EntityReceiptAmounts, EntityReceiptSubtotal, EntityReceiptsAmounts}
+        }, errors::api_error::ApiError, forms::{create_payload::{CreateCurrencyInReceiptPayload, CreateInventoryInReceiptPayload, CreateProductInReceiptPayload, CreateReceiptPayload, CreateSettlementInReceiptPayload, CreateStoreInReceiptPayload}, patch_payload::PatchReceiptPayload}, parameters::{conversion::Conversion, cursor::CursorDirection, field_selection::ReceiptIncludes, matching::MatchMode, pagination::Pagination, query_filters::{ReceiptFilters, TimestampFilters}, sorting::{ReceiptSortField, SortDirection, SortKey, Sorting}}, responses::response_receipt::{ResponseCreateReceipt, ResponseReceipt}
     }, 
     repository::DbRepository, 
     schema::{
-        currencies, inventories, products, receipts, stores
-    }, services::v1::{converters::converters_service::ConverterService, currencies::currencies_service::CurrencyService, fallbacks::fallbacks_service::FallbacksService, inventories::inventories_service::InventoryService, products::products_service::ProductService, stores::stores_service::StoreService, validators::formdata_validators_service::{FormDataValidatorService, FormRelationshipModelStatus}}
+        currencies, currency_aliases, exchange_rates, inventories, products, receipts, stores, user_settings
+    }, services::v1::{batches::batches_service::BatchService, converters::converters_service::ConverterService, cursors::cursors_service::CursorService, currencies::currencies_service::CurrencyService, decimals::decimals_service::DEFAULT_MINOR_UNITS, exchange_rates::exchange_rates_service::ExchangeRateService, fallbacks::fallbacks_service::FallbacksService, inventories::inventories_service::InventoryService, matchers::matchers_service::MatcherService, products::products_service::ProductService, sortings::sortings_service::SortingService, stores::stores_service::StoreService, tax_rates::tax_rates_service::TaxRateService, timezones::timezones_service::TimeZoneService, totals::totals_service::{TotalService, RECEIPT_TOTAL_SQL}, validators::formdata_validators_service::{FormDataValidatorService, FormRelationshipModelStatus}}
 };
 
+const RECEIPT_SUBTOTAL_SQL: &str = "COALESCE(SUM(inventories.line_price - inventories.discount), 0)";
+const RECEIPT_PRE_TAX_SQL: &str = "COALESCE(SUM(inventories.pre_tax_amount), 0)";
+const RECEIPT_POST_TAX_SQL: &str = "COALESCE(SUM(inventories.post_tax_amount), 0)";
+
+// the settlement currency id, the settled amount and the settlement fee of a receipt
+type Settlement = (Option<i32>, Option<BigDecimal>, Option<BigDecimal>);
+
 pub struct ReceiptService<'a> {
     repository: &'a DbRepository
 }
@@ -27,7 +37,7 @@ impl<'a> ReceiptService<'a> {
         }
     }
 
-    pub async fn get_receipt(&self, id: i32) -> Result<ResponseReceipt, ApiError> {
+    pub async fn get_receipt(&self, id: i32, includes: &ReceiptIncludes, conversion: &Conversion) -> Result<ResponseReceipt, ApiError> {
         let converter = ConverterService::new();
         let conn = &mut self.repository.pool.get().or_else(
             |e| {
@@ -37,40 +47,124 @@ impl<'a> ReceiptService<'a> {
 
         let receipt_query = 
             receipts::table
-                .inner_join(currencies::table)
-                .inner_join(stores::table)
                 .filter(receipts::id.eq(id))
-                .select(<(EntityReceipt, EntityCurrency, EntityStore)>::as_select());
+                .filter(receipts::deleted_at.is_null())
+                .select(<EntityReceipt>::as_select());
 
-        let (receipt, currency, store) = receipt_query.get_result::<(EntityReceipt, EntityCurrency, EntityStore)>(conn).or_else(
+        let receipt = receipt_query.get_result::<EntityReceipt>(conn).or_else(
             |e| {
                 tracing::warn!("try to get a non existed receipt ({}): {}", id, e);
                 Err(ApiError::NoRecord)
             }
         )?;
 
-        let inventories_query = 
-            inventories::table
-                .inner_join(products::table)
-                .filter(inventories::receipt_id.eq(receipt.id))
-                .select(<(EntityInventory, EntityProduct)>::as_select());
-
-        let inventories_products = inventories_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let minor_units = CurrencyService::new(self.repository).get_minor_units_by_receipt_ids(conn, &[receipt.id])?;
+        let settlement_minor_units = CurrencyService::new(self.repository).get_settlement_minor_units_by_receipt_ids(conn, &[receipt.id])?.get(&receipt.id).copied();
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &[receipt.id])?;
+        let currency = if includes.currency {
+            Some(currencies::table.filter(currencies::id.eq(receipt.currency_id)).select(<EntityCurrency>::as_select()).get_result::<EntityCurrency>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
 
-        let mut inventories = vec![];
-        for inventory_product in inventories_products {
-            inventories.push(converter.convert_to_inventory_response(inventory_product.0, inventory_product.1));
+        let store = if includes.store {
+            Some(stores::table.filter(stores::id.eq(receipt.store_id)).select(<EntityStore>::as_select()).get_result::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
         }
+        else {
+            None
+        };
 
+        let inventories = if includes.inventories {
+            let inventories_query = 
+                inventories::table
+                    .inner_join(products::table)
+                    .filter(inventories::receipt_id.eq(receipt.id))
+                    .select(<(EntityInventory, EntityProduct)>::as_select());
 
-        let receipt_response = converter.convert_to_receipt_response(receipt, currency, store, inventories);
+            let inventories_products = inventories_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+            Some(converter.convert_to_all_inventories_response(inventories_products, &minor_units, conversion.as_ref()))
+        }
+        else {
+            None
+        };
+
+        let subtotal = self.get_subtotals(conn, &[receipt.id])?.remove(&receipt.id).unwrap_or(EntityReceiptSubtotal { receipt_id: receipt.id, ..Default::default() });
+        let receipt_minor_units = minor_units.get(&receipt.id).copied().unwrap_or(DEFAULT_MINOR_UNITS);
+        let receipt_response = converter.convert_to_receipt_response(receipt, currency, store, inventories, EntityReceiptAmounts {
+            subtotal: &subtotal,
+            minor_units: receipt_minor_units,
+            settlement_minor_units,
+            conversion: conversion.as_ref()
+        });
 
         Ok(receipt_response)
     }
 
-    pub async fn get_receipts(&self, pagination: &Pagination) -> Result<ServiceCollection<ResponseReceipt>, ApiError> {
+    pub async fn get_receipts_by_ids(&self, ids: &[i32], includes: &ReceiptIncludes, conversion: &Conversion) -> Result<(Vec<ResponseReceipt>, Vec<i32>), ApiError> {
+        let converter = ConverterService::new();
+        let batch_service = BatchService::new();
+        let ids = batch_service.get_unique_ids(ids)?;
+        let conn = &mut self.repository.pool.get().or_else(
+            |e| {
+                tracing::error!("database connection broken: {}", e);
+                Err(ApiError::DatabaseConnectionBroken)
+            })?;
+
+        let receipts_list = receipts::table
+            .filter(receipts::id.eq_any(&ids))
+            .filter(receipts::deleted_at.is_null())
+            .select(<EntityReceipt>::as_select())
+            .get_results::<EntityReceipt>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+
+        let currencies_map = if includes.currency {
+            let currency_ids = receipts_list.iter().map(|r| r.currency_id).collect::<Vec<i32>>();
+            Some(currencies::table.filter(currencies::id.eq_any(&currency_ids)).select(<EntityCurrency>::as_select()).get_results::<EntityCurrency>(conn).or_else(|_e| Err(ApiError::NoRecord))?
+                .into_iter().map(|c| (c.id, c)).collect::<HashMap<i32, EntityCurrency>>())
+        }
+        else {
+            None
+        };
+        let stores_map = if includes.store {
+            let store_ids = receipts_list.iter().map(|r| r.store_id).collect::<Vec<i32>>();
+            Some(stores::table.filter(stores::id.eq_any(&store_ids)).select(<EntityStore>::as_select()).get_results::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?
+                .into_iter().map(|s| (s.id, s)).collect::<HashMap<i32, EntityStore>>())
+        }
+        else {
+            None
+        };
+        let inventories_list = if includes.inventories {
+            Some(inventories::table
+                .inner_join(products::table)
+                .filter(inventories::receipt_id.eq_any(&ids))
+                .select(<(EntityInventory, EntityProduct)>::as_select())
+                .get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
+
+        let subtotals = self.get_subtotals(conn, &ids)?;
+        let minor_units = CurrencyService::new(self.repository).get_minor_units_by_receipt_ids(conn, &ids)?;
+        let settlement_minor_units = CurrencyService::new(self.repository).get_settlement_minor_units_by_receipt_ids(conn, &ids)?;
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &ids)?;
+        let receipts_response = converter.convert_to_included_receipts_response(receipts_list, currencies_map, stores_map, inventories_list, EntityReceiptsAmounts {
+            subtotals,
+            minor_units,
+            settlement_minor_units,
+            conversion: conversion.as_ref()
+        });
+        Ok(batch_service.arrange(&ids, receipts_response, |r| r.id))
+    }
+
+    pub async fn get_receipts(&self, pagination: &Pagination, receipt_filters: &ReceiptFilters, timestamp_filters: &TimestampFilters, sorting: &Sorting, includes: &ReceiptIncludes, conversion: &Conversion) -> Result<ServiceCollection<ResponseReceipt>, ApiError> {
         let converter = ConverterService::new();
         let fallbacks_service = FallbacksService::new();
+        let sorting_service = SortingService::new();
+        let sort_keys = sorting_service.parse_sort::<ReceiptSortField>(sorting)?;
+        let primary_sort_key = sort_keys.first().copied().unwrap_or(SortKey { field: ReceiptSortField::Id, direction: SortDirection::Asc });
+        let cursor_service = CursorService::new();
+        let cursor = cursor_service.get_cursor(pagination, &sort_keys, &primary_sort_key)?;
         let conn = &mut self.repository.pool.get().or_else(
             |e| {
                 tracing::error!("database connection broken: {}", e);
@@ -78,36 +172,211 @@ impl<'a> ReceiptService<'a> {
             }
         )?;
 
-        let count: i64 = receipts::table.select(count(receipts::columns::id)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let matcher_service = MatcherService::new();
+        let match_mode = receipt_filters.match_mode.unwrap_or_default();
+        let (transaction_from, transaction_to) = TimeZoneService::new().get_date_range(receipt_filters.start_date, receipt_filters.end_date, receipt_filters.preset, receipt_filters.tz)?;
+        // a receipt is taxed when any of its lines has a tax amount
+        let taxed_inventories_of_receipt = || exists(
+            inventories::table
+                .filter(inventories::receipt_id.eq(receipts::id))
+                .filter(inventories::post_tax_amount.ne(inventories::pre_tax_amount))
+        );
+        let build_query = || {
+            let mut query = 
+                receipts::table
+                    .inner_join(stores::table)
+                    .inner_join(currencies::table)
+                    .filter(receipts::deleted_at.is_null())
+                    .into_boxed::<Pg>();
+            if let Some(created_from) = timestamp_filters.created_from {
+                query = query.filter(receipts::created_at.ge(created_from));
+            }
+            if let Some(created_to) = timestamp_filters.created_to {
+                query = query.filter(receipts::created_at.le(created_to));
+            }
+            if let Some(updated_from) = timestamp_filters.updated_from {
+                query = query.filter(receipts::updated_at.ge(updated_from));
+            }
+            if let Some(updated_to) = timestamp_filters.updated_to {
+                query = query.filter(receipts::updated_at.le(updated_to));
+            }
+
+            if let Some(transaction_from) = transaction_from {
+                query = query.filter(receipts::transaction_date.ge(transaction_from));
+            }
+            if let Some(transaction_to) = transaction_to {
+                query = query.filter(receipts::transaction_date.lt(transaction_to));
+            }
+
+            if let Some(store_id) = receipt_filters.store_id {
+                query = query.filter(receipts::store_id.eq(store_id));
+            }
+            if let Some(store_name) = &receipt_filters.store_name {
+                let store_name_pattern = matcher_service.get_pattern(store_name, match_mode);
+                query = query.filter(stores::name.ilike(store_name_pattern));
+            }
+
+            if let Some(currency_name) = &receipt_filters.currency {
+                // the currency is matched by its name, its code or one of its aliases
+                let currency_pattern = matcher_service.get_pattern(currency_name, MatchMode::Exact);
+                let currency_ids_of_alias = currency_aliases::table.filter(currency_aliases::alias.ilike(currency_pattern.clone())).select(currency_aliases::currency_id);
+                query = query.filter(currencies::name.ilike(currency_pattern.clone()).or(currencies::code.ilike(currency_pattern)).or(currencies::id.eq_any(currency_ids_of_alias)));
+            }
+
+            if let Some(is_inventory_taxed) = receipt_filters.is_inventory_taxed {
+                if is_inventory_taxed {
+                    query = query.filter(taxed_inventories_of_receipt());
+                }
+                else {
+                    query = query.filter(not(taxed_inventories_of_receipt()));
+                }
+            }
+
+            if let Some(product_id) = receipt_filters.product_id {
+                query = query.filter(exists(
+                    inventories::table
+                        .filter(inventories::receipt_id.eq(receipts::id))
+                        .filter(inventories::product_id.eq(product_id))
+                ));
+            }
+            if let Some(product_name) = &receipt_filters.product_name {
+                let product_name_pattern = matcher_service.get_pattern(product_name, match_mode);
+                query = query.filter(exists(
+                    inventories::table
+                        .inner_join(products::table)
+                        .filter(inventories::receipt_id.eq(receipts::id))
+                        .filter(products::name.ilike(product_name_pattern))
+                ));
+            }
+
+            // the receipt total is the grand total: the post-tax line amounts less the receipt discount plus the service charge
+            if let Some(min_total) = receipt_filters.min_total.clone() {
+                query = query.filter(sql::<Bool>(RECEIPT_TOTAL_SQL).sql(" >= ").bind::<Numeric, _>(min_total));
+            }
+            if let Some(max_total) = receipt_filters.max_total.clone() {
+                query = query.filter(sql::<Bool>(RECEIPT_TOTAL_SQL).sql(" <= ").bind::<Numeric, _>(max_total));
+            }
+
+            if let Some(transaction_id) = receipt_filters.transaction_id {
+                query = query.filter(receipts::transaction_id.eq(transaction_id));
+            }
+
+            query
+        };
+
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(build_query().select(count(receipts::columns::id)).first::<i64>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
         
         let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
-        let all_compound_receipts_in_this_page_query = 
-            receipts::table
-                .inner_join(currencies::table)
-                .inner_join(stores::table)
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<(EntityReceipt, EntityCurrency, EntityStore)>::as_select());
+        let mut all_compound_receipts_in_this_page_query = build_query();
+        if let Some((cursor, cursor_direction)) = &cursor {
+            let greater = cursor_service.is_seeking_greater(*cursor_direction, primary_sort_key.direction);
+            all_compound_receipts_in_this_page_query = match primary_sort_key.field {
+                ReceiptSortField::Id => cursor_service.seek(all_compound_receipts_in_this_page_query, receipts::id, cursor_service.get_cursor_value::<i32>(cursor)?, receipts::id, cursor.id, greater),
+                ReceiptSortField::TransactionDate => cursor_service.seek(all_compound_receipts_in_this_page_query, receipts::transaction_date, cursor_service.get_cursor_value::<DateTime<Utc>>(cursor)?, receipts::id, cursor.id, greater),
+                ReceiptSortField::StoreName => cursor_service.seek(all_compound_receipts_in_this_page_query, stores::name, cursor_service.get_cursor_value::<String>(cursor)?, receipts::id, cursor.id, greater),
+                ReceiptSortField::Currency => cursor_service.seek(all_compound_receipts_in_this_page_query, currencies::name, cursor_service.get_cursor_value::<String>(cursor)?, receipts::id, cursor.id, greater),
+                ReceiptSortField::IsInventoryTaxed => return Err(ApiError::InvalidCursor),
+                ReceiptSortField::CreatedAt => cursor_service.seek(all_compound_receipts_in_this_page_query, receipts::created_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, receipts::id, cursor.id, greater),
+                ReceiptSortField::UpdatedAt => cursor_service.seek(all_compound_receipts_in_this_page_query, receipts::updated_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, receipts::id, cursor.id, greater)
+            };
+        }
 
-        let all_compound_receipts_in_this_page = all_compound_receipts_in_this_page_query.get_results::<(EntityReceipt, EntityCurrency, EntityStore)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-        let receipts_ids = AsRef::<Vec<(EntityReceipt, EntityCurrency, EntityStore)>>::as_ref(&all_compound_receipts_in_this_page).into_iter().map(|x| x.0.id as i32).collect::<Vec<i32>>();
+        // rows before the cursor are read in the reversed order and flipped back by paginate
+        let is_before = matches!(cursor, Some((_, CursorDirection::Before)));
+        for sort_key in &sort_keys {
+            let direction = if is_before { sort_key.direction.reverse() } else { sort_key.direction };
+            all_compound_receipts_in_this_page_query = match sort_key.field {
+                ReceiptSortField::Id => sorting_service.order_by(all_compound_receipts_in_this_page_query, receipts::id, direction),
+                ReceiptSortField::TransactionDate => sorting_service.order_by(all_compound_receipts_in_this_page_query, receipts::transaction_date, direction),
+                ReceiptSortField::StoreName => sorting_service.order_by(all_compound_receipts_in_this_page_query, stores::name, direction),
+                ReceiptSortField::Currency => sorting_service.order_by(all_compound_receipts_in_this_page_query, currencies::name, direction),
+                ReceiptSortField::IsInventoryTaxed => sorting_service.order_by(all_compound_receipts_in_this_page_query, taxed_inventories_of_receipt(), direction),
+                ReceiptSortField::CreatedAt => sorting_service.order_by(all_compound_receipts_in_this_page_query, receipts::created_at, direction),
+                ReceiptSortField::UpdatedAt => sorting_service.order_by(all_compound_receipts_in_this_page_query, receipts::updated_at, direction)
+            };
+        }
+        all_compound_receipts_in_this_page_query = sorting_service.order_by_id(all_compound_receipts_in_this_page_query, receipts::id, primary_sort_key.direction, is_before);
+
+        // one extra row tells whether there are more rows after this page
+        let rows = all_compound_receipts_in_this_page_query
+            .limit(per_page + 1)
+            .offset(if cursor.is_some() { 0 } else { page_offset })
+            .select(<EntityReceipt>::as_select())
+            .get_results::<EntityReceipt>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+
+        // a store or currency name sort needs the names to build the cursor
+        let store_ids = rows.iter().map(|r| r.store_id).collect::<Vec<i32>>();
+        let stores_map = if includes.store || primary_sort_key.field == ReceiptSortField::StoreName {
+            Some(stores::table.filter(stores::id.eq_any(&store_ids)).select(<EntityStore>::as_select()).get_results::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?
+                .into_iter().map(|s| (s.id, s)).collect::<HashMap<i32, EntityStore>>())
+        }
+        else {
+            None
+        };
+        let currency_ids = rows.iter().map(|r| r.currency_id).collect::<Vec<i32>>();
+        let currencies_map = if includes.currency || primary_sort_key.field == ReceiptSortField::Currency {
+            Some(currencies::table.filter(currencies::id.eq_any(&currency_ids)).select(<EntityCurrency>::as_select()).get_results::<EntityCurrency>(conn).or_else(|_e| Err(ApiError::NoRecord))?
+                .into_iter().map(|c| (c.id, c)).collect::<HashMap<i32, EntityCurrency>>())
+        }
+        else {
+            None
+        };
 
-        let all_compound_inventories_in_this_page_query =
-            inventories::table
+        let (receipts_in_this_page, next_cursor, prev_cursor) = cursor_service.paginate(rows, per_page, page_offset, cursor.as_ref().map(|c| c.1), cursor_service.get_cursor_signature(&sort_keys, &primary_sort_key), |row| {
+            let value = match primary_sort_key.field {
+                ReceiptSortField::Id => json!(row.id),
+                ReceiptSortField::TransactionDate => json!(row.transaction_date),
+                ReceiptSortField::StoreName => json!(stores_map.as_ref().and_then(|m| m.get(&row.store_id)).map(|s| &s.name)),
+                ReceiptSortField::Currency => json!(currencies_map.as_ref().and_then(|m| m.get(&row.currency_id)).map(|c| &c.name)),
+                ReceiptSortField::IsInventoryTaxed => json!(null),
+                ReceiptSortField::CreatedAt => json!(row.created_at),
+                ReceiptSortField::UpdatedAt => json!(row.updated_at)
+            };
+            (value, row.id)
+        });
+
+        let receipts_ids = receipts_in_this_page.iter().map(|r| r.id).collect::<Vec<i32>>();
+        let subtotals_in_this_page = self.get_subtotals(conn, &receipts_ids)?;
+        let minor_units_in_this_page = CurrencyService::new(self.repository).get_minor_units_by_receipt_ids(conn, &receipts_ids)?;
+        let settlement_minor_units_in_this_page = CurrencyService::new(self.repository).get_settlement_minor_units_by_receipt_ids(conn, &receipts_ids)?;
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &receipts_ids)?;
+        let inventories_in_this_page = if includes.inventories {
+            Some(inventories::table
                 .inner_join(products::table)
                 .filter(inventories::columns::receipt_id.eq_any(receipts_ids))
-                .select(<(EntityInventory, EntityProduct)>::as_select());
-        
-        let all_compound_inventories_in_this_page = all_compound_inventories_in_this_page_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+                .select(<(EntityInventory, EntityProduct)>::as_select())
+                .get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
 
         Ok(ServiceCollection {
-            partial_collection: converter.convert_to_all_receipt_response(all_compound_receipts_in_this_page, all_compound_inventories_in_this_page),
-            total_count: count
+            partial_collection: converter.convert_to_included_receipts_response(
+                receipts_in_this_page, 
+                if includes.currency { currencies_map } else { None }, 
+                if includes.store { stores_map } else { None }, 
+                inventories_in_this_page,
+                EntityReceiptsAmounts {
+                    subtotals: subtotals_in_this_page,
+                    minor_units: minor_units_in_this_page,
+                    settlement_minor_units: settlement_minor_units_in_this_page,
+                    conversion: conversion.as_ref()
+                }
+            ),
+            total_count: count,
+            next_cursor,
+            prev_cursor
         })
     }
 
-    pub async fn get_receipt_by_transaction_id(&self, id: Uuid) -> Result<ResponseReceipt, ApiError> {
+    pub async fn get_receipt_by_transaction_id(&self, id: Uuid, conversion: &Conversion) -> Result<ResponseReceipt, ApiError> {
         let converter = ConverterService::new();
         let conn = &mut self.repository.pool.get().or_else(
             |e| {
@@ -120,6 +389,7 @@ impl<'a> ReceiptService<'a> {
                 .inner_join(currencies::table)
                 .inner_join(stores::table)
                 .filter(receipts::transaction_id.eq(id))
+                .filter(receipts::deleted_at.is_null())
                 .select(<(EntityReceipt, EntityCurrency, EntityStore)>::as_select());
 
         let (receipt, currency, store) = receipt_query.get_result::<(EntityReceipt, EntityCurrency, EntityStore)>(conn).or_else(
@@ -137,13 +407,22 @@ impl<'a> ReceiptService<'a> {
 
         let inventories_products = inventories_query.get_results::<(EntityInventory, EntityProduct)>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
 
+        let subtotal = TotalService::new().get_subtotal(receipt.id, &inventories_products.iter().map(|t| t.0.clone()).collect::<Vec<EntityInventory>>());
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &[receipt.id])?;
         let mut inventories = vec![];
         for inventory_product in inventories_products {
-            inventories.push(converter.convert_to_inventory_response(inventory_product.0, inventory_product.1));
+            inventories.push(converter.convert_to_inventory_response(inventory_product.0, inventory_product.1, currency.minor_units, conversion.as_ref()));
         }
 
 
-        let receipt_response = converter.convert_to_receipt_response(receipt, currency, store, inventories);
+        let minor_units = currency.minor_units;
+        let settlement_minor_units = CurrencyService::new(self.repository).get_settlement_minor_units_by_receipt_ids(conn, &[receipt.id])?.get(&receipt.id).copied();
+        let receipt_response = converter.convert_to_receipt_response(receipt, Some(currency), Some(store), Some(inventories), EntityReceiptAmounts {
+            subtotal: &subtotal,
+            minor_units,
+            settlement_minor_units,
+            conversion: conversion.as_ref()
+        });
 
         Ok(receipt_response)
     }
@@ -167,7 +446,7 @@ impl<'a> ReceiptService<'a> {
     }
 
     pub async fn create_receipt(&self, form_receipt: &CreateReceiptPayload) -> Result<ResponseCreateReceipt, ApiError> {
-        let currency_status = self.validate_currency(&form_receipt.currency).await.or_else(|e| {
+        let currency_id = self.validate_currency(&form_receipt.currency).await.or_else(|e| {
             tracing::error!("validate_currency failed");
             return Err(e);
         })?;
@@ -186,12 +465,25 @@ impl<'a> ReceiptService<'a> {
             inventories_metadata.push((product_status, inventory));
         }
 
+        let transaction_date = TimeZoneService::new().resolve_transaction_date(&form_receipt.transaction_date, form_receipt.time_zone)?;
+        let (minor_units, discount, service_charge, printed_total, (settlement_currency_id, settled_amount, settlement_fee)) = {
+            let conn = &mut self.repository.pool.get().map_err(|e| {
+                tracing::error!("database connection broken: {}", e);
+                ApiError::DatabaseConnectionBroken
+            })?;
+            let minor_units = self.get_minor_units(conn, currency_id)?;
+            let (discount, service_charge, printed_total) = self.validate_amounts(form_receipt, minor_units)?;
+            self.validate_tax_rates(conn, form_receipt)?;
+            let settlement = self.validate_settlement(conn, form_receipt.settlement.as_ref(), currency_id)?;
+            (minor_units, discount, service_charge, printed_total, settlement)
+        };
+
         let currency_ref_id;
-        if currency_status == FormRelationshipModelStatus::Id {
-            currency_ref_id = form_receipt.currency.id.expect("currency id should not be none after validation");
+        if let Some(currency_id) = currency_id {
+            currency_ref_id = currency_id;
         }
         else  {
-            // None status will not reach here
+            // a name that is neither a code, an alias nor an existing currency becomes a new currency
             let new_currency = NewEntityCurrency {
                 name: form_receipt.currency.name.clone().expect("currency name should not ")
             };
@@ -210,7 +502,8 @@ impl<'a> ReceiptService<'a> {
                 name: form_receipt.store.name.clone().expect("store name should not be none after validation"),
                 alias: form_receipt.store.alias.clone(),
                 branch: form_receipt.store.branch.clone(),
-                address: form_receipt.store.address.clone()
+                address: form_receipt.store.address.clone(),
+                tax_rate_id: form_receipt.store.tax_rate_id
             };
 
             let store_service = StoreService::new(&self.repository);
@@ -218,14 +511,25 @@ impl<'a> ReceiptService<'a> {
         }
 
         let new_receipt = NewEntityReceipt {
-            transaction_date: form_receipt.transaction_date,
-            is_inventory_taxed: form_receipt.is_inventory_taxed,
+            transaction_date: transaction_date.with_timezone(&Utc),
+            transaction_offset: transaction_date.offset().local_minus_utc(),
             transaction_id: form_receipt.transaction_id,
             currency_id: currency_ref_id,
-            store_id: store_ref_id
+            store_id: store_ref_id,
+            discount,
+            service_charge,
+            printed_total,
+            settlement_currency_id,
+            settled_amount,
+            settlement_fee
         };
 
         let receipt_ref_id = self.new_receipt(&new_receipt).await?;
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+        let store_tax_rate_id = self.get_store_tax_rate_id(conn, store_ref_id)?;
         
         for pair in inventories_metadata {
             let product_ref_id;
@@ -247,24 +551,20 @@ impl<'a> ReceiptService<'a> {
                 product_ref_id = product_service.new_product(&new_product).await?;
             }
 
-            let new_inventory = NewEntityInventory {
-                price: BigDecimal::from_f64(pair.1.price.clone()).expect("parse price should be successful after validation"),
-                quantity: pair.1.quantity.clone(),
-                product_id: product_ref_id,
-                receipt_id: receipt_ref_id
-            };
+            let new_inventory = self.get_new_inventory(conn, pair.1, product_ref_id, receipt_ref_id, store_tax_rate_id, minor_units)?;
 
             let inventory_service = InventoryService::new(&self.repository);
             let _inventory_id = inventory_service.new_inventory(&new_inventory).await?;
         }
         
-        tracing::debug!("Create receipt at date {}, id: {} successfully", form_receipt.transaction_date, receipt_ref_id);
+        tracing::debug!("Create receipt at date {}, id: {} successfully", transaction_date, receipt_ref_id);
         Ok(ResponseCreateReceipt {
             id: receipt_ref_id
         })
     }
 
-    async fn validate_currency(&self, currency: &CreateCurrencyInReceiptPayload) -> Result<FormRelationshipModelStatus, ApiError> {
+    // returns the id of the currency, a name is resolved against the ISO codes, the known aliases and the existing currencies, None means a new currency is created from it
+    async fn validate_currency(&self, currency: &CreateCurrencyInReceiptPayload) -> Result<Option<i32>, ApiError> {
         let formdata_validators_service = FormDataValidatorService::new();
         let currency_status = formdata_validators_service.validate_relationship_model(currency);
         if currency_status == FormRelationshipModelStatus::None {
@@ -279,16 +579,13 @@ impl<'a> ReceiptService<'a> {
             if !is_existed {
                 return Err(ApiError::CurrencyIdNotExisted);
             }
+
+            Ok(Some(currency_id))
         }
-        else if currency_status == FormRelationshipModelStatus::ItemName {
+        else {
             let currency_name = currency.name.as_ref().expect("currency name should not be none");
-            let is_existed = currency_service.is_currency_existed_by_name(currency_name).await?;
-            if is_existed {
-                return Err(ApiError::CurrencyNameDuplicated);
-            }
+            currency_service.resolve_currency_id(currency_name).await
         }
-
-        Ok(currency_status)
     }
 
     async fn validate_store(&self, store: &CreateStoreInReceiptPayload) -> Result<FormRelationshipModelStatus, ApiError> {
@@ -351,120 +648,552 @@ impl<'a> ReceiptService<'a> {
         Ok(product_status)
     }
 
-    pub async fn patch_receipt(&self, id: i32, receipt: &PatchReceiptPayload) -> Result<(), ApiError> {
+    pub async fn get_receipt_version(&self, id: i32) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        receipts::table.filter(receipts::id.eq(id)).filter(receipts::deleted_at.is_null()).select(receipts::version).get_result::<i32>(conn).map_err(|e| {
+            tracing::warn!("try to get version of a non existed receipt ({}): {}", id, e);
+            ApiError::NoRecord
+        })
+    }
+
+    pub async fn patch_receipt(&self, id: i32, receipt: &PatchReceiptPayload, expected_version: i32) -> Result<(), ApiError> {
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        if receipt.transaction_date.is_some() && receipt.is_inventory_taxed.is_some() {
-            update(receipts::table.filter(receipts::id.eq(id))).set((receipts::transaction_date.eq(receipt.transaction_date.expect("transaction_date should not be none")), receipts::is_inventory_taxed.eq(receipt.is_inventory_taxed.expect("is_inventory_taxed should nt be none")))).execute(conn).or_else(|e| {
+        let transaction_date = match &receipt.transaction_date {
+            Some(transaction_date) => Some(TimeZoneService::new().resolve_transaction_date(transaction_date, receipt.time_zone)?),
+            None => None
+        };
+
+        let (currency_id, minor_units) = receipts::table.inner_join(currencies::table).filter(receipts::id.eq(id)).select((receipts::currency_id, currencies::minor_units)).get_result::<(i32, i32)>(conn).map_err(|e| {
+            tracing::warn!("try to patch a non existed receipt ({}): {}", id, e);
+            ApiError::NoRecord
+        })?;
+
+        // null removes the settlement as a whole
+        let settlement = match &receipt.settlement {
+            Some(settlement) => Some(self.validate_settlement(conn, settlement.as_ref(), Some(currency_id))?),
+            None => None
+        };
+
+        let total_service = TotalService::new();
+        let updated_receipt = UpdateEntityReceipt {
+            id,
+            transaction_date: transaction_date.map(|d| d.with_timezone(&Utc)),
+            transaction_offset: transaction_date.map(|d| d.offset().local_minus_utc()),
+            discount: receipt.discount.as_ref().map(|discount| total_service.get_amount(discount, minor_units)).transpose()?,
+            service_charge: receipt.service_charge.as_ref().map(|service_charge| total_service.get_amount(service_charge, minor_units)).transpose()?,
+            printed_total: receipt.printed_total.as_ref().map(|printed_total| printed_total.as_ref().map(|p| total_service.get_amount(p, minor_units)).transpose()).transpose()?,
+            settlement_currency_id: settlement.as_ref().map(|s| s.0),
+            settled_amount: settlement.as_ref().map(|s| s.1.clone()),
+            settlement_fee: settlement.map(|s| s.2)
+        };
+
+        let affected_rows = update(receipts::table.filter(receipts::id.eq(id)).filter(receipts::deleted_at.is_null()).filter(receipts::version.eq(expected_version)))
+            .set((&updated_receipt, receipts::version.eq(receipts::version + 1)))
+            .execute(conn).map_err(|e| {
                 tracing::error!("update receipt entity failed: {}", e);
-                Err(ApiError::UpdateReceiptFailed)
+                ApiError::UpdateReceiptFailed
             })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("receipt {} is not existed or its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
         }
-        else if receipt.transaction_date.is_some() {
-            update(receipts::table.filter(receipts::id.eq(id))).set(receipts::transaction_date.eq(receipt.transaction_date.expect("transaction_date should not be none"))).execute(conn).or_else(|e| {
-                tracing::error!("update receipt entity failed: {}", e);
-                Err(ApiError::UpdateReceiptFailed)
+
+        tracing::debug!("patch receipt {} successfully", id);
+        Ok(())
+    }
+
+    pub async fn delete_receipt(&self, id: i32, expected_version: i32) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        // A deleted receipt is moved to the trash, and it is removed permanently by purge_expired_receipts later
+        let affected_rows = update(receipts::table.filter(receipts::id.eq(id)).filter(receipts::deleted_at.is_null()).filter(receipts::version.eq(expected_version)))
+            .set((receipts::deleted_at.eq(now), receipts::version.eq(receipts::version + 1)))
+            .execute(conn).map_err(|e| {
+                tracing::error!("Unable to move receipt to trash: {}", e);
+                ApiError::DeleteReceiptEntryFailed
+            })?;
+
+        if affected_rows == 0 {
+            let receipt_existed = select(exists(receipts::table.filter(receipts::id.eq(id)).filter(receipts::deleted_at.is_null()))).get_result::<bool>(conn).map_err(|e| {
+                tracing::error!("unable to check receipt existence: {}", e);
+                ApiError::DeleteReceiptIdNotExisted
             })?;
+
+            if receipt_existed {
+                tracing::warn!("receipt {} is modified by others, its version is not {}", id, expected_version);
+                return Err(ApiError::PreconditionFailed)
+            }
+
+            return Err(ApiError::DeleteReceiptIdNotExisted)
         }
-        else if receipt.is_inventory_taxed.is_some() {
-            update(receipts::table.filter(receipts::id.eq(id))).set(receipts::is_inventory_taxed.eq(receipt.is_inventory_taxed.expect("is_inventory_taxed should not be none"))).execute(conn).or_else(|e| {
-                tracing::error!("update receipt entity failed: {}", e);
-                Err(ApiError::UpdateReceiptFailed)
+
+        tracing::debug!("Move receipt {} to trash successfully", id);
+        Ok(())
+    }
+
+    pub async fn restore_receipt(&self, id: i32) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let affected_rows = update(receipts::table.filter(receipts::id.eq(id)).filter(receipts::deleted_at.is_not_null()))
+            .set((receipts::deleted_at.eq(None::<NaiveDateTime>), receipts::version.eq(receipts::version + 1)))
+            .execute(conn).map_err(|e| {
+                tracing::error!("Unable to restore receipt from trash: {}", e);
+                ApiError::RestoreReceiptFailed
             })?;
+
+        if affected_rows == 0 {
+            return Err(ApiError::RestoreReceiptIdNotExisted)
         }
 
-        tracing::debug!("patch receipt {} successfully", id);
+        tracing::debug!("Restore receipt {} successfully", id);
         Ok(())
     }
 
-    pub async fn delete_receipt(&self, id: i32) -> Result<(), ApiError> {
-        let conn = &mut self.repository.pool.get().or_else(|e| {
+    pub async fn get_trashed_receipts(&self, pagination: &Pagination) -> Result<ServiceCollection<ResponseReceipt>, ApiError> {
+        let converter = ConverterService::new();
+        let fallbacks_service = FallbacksService::new();
+        let conn = &mut self.repository.pool.get().map_err(|e| {
             tracing::error!("database connection broken: {}", e);
-            Err(ApiError::DatabaseConnectionBroken)
+            ApiError::DatabaseConnectionBroken
         })?;
 
-        let receipt_existed = select(exists(receipts::table.filter(receipts::id.eq(id)))).get_result::<bool>(conn).or_else(|e| {
-            tracing::error!("unable to check receipt existence: {}", e);
-            Err(ApiError::DeleteReceiptIdNotExisted)
-        }).expect("Unwrap existed receipt should not be failed");
+        let count: i64 = receipts::table.filter(receipts::deleted_at.is_not_null()).select(count(receipts::columns::id)).first(conn).map_err(|_e| ApiError::NoRecord)?;
 
-        if !receipt_existed {
-            return Err(ApiError::DeleteReceiptIdNotExisted)
+        let (page_offset, per_page) = fallbacks_service.fallback_pagination(pagination);
+
+        let trashed_compound_receipts_in_this_page = 
+            receipts::table
+                .inner_join(currencies::table)
+                .inner_join(stores::table)
+                .filter(receipts::deleted_at.is_not_null())
+                .order((receipts::deleted_at.desc(), receipts::id.desc()))
+                .limit(per_page)
+                .offset(page_offset)
+                .select(<(EntityReceipt, EntityCurrency, EntityStore)>::as_select())
+                .get_results::<(EntityReceipt, EntityCurrency, EntityStore)>(conn).map_err(|_e| ApiError::NoRecord)?;
+
+        let receipts_ids = trashed_compound_receipts_in_this_page.iter().map(|x| x.0.id).collect::<Vec<i32>>();
+        let settlement_minor_units_in_this_page = CurrencyService::new(self.repository).get_settlement_minor_units_by_receipt_ids(conn, &receipts_ids)?;
+
+        let trashed_compound_inventories_in_this_page = 
+            inventories::table
+                .inner_join(products::table)
+                .filter(inventories::columns::receipt_id.eq_any(receipts_ids))
+                .select(<(EntityInventory, EntityProduct)>::as_select())
+                .get_results::<(EntityInventory, EntityProduct)>(conn).map_err(|_e| ApiError::NoRecord)?;
+
+        Ok(ServiceCollection {
+            partial_collection: converter.convert_to_all_receipt_response(trashed_compound_receipts_in_this_page, trashed_compound_inventories_in_this_page, &settlement_minor_units_in_this_page),
+            total_count: Some(count),
+            next_cursor: None,
+            prev_cursor: None
+        })
+    }
+
+    pub async fn purge_expired_receipts(&self, retention_days: i64) -> Result<(), ApiError> {
+        let expired_receipt_ids = {
+            let conn = &mut self.repository.pool.get().map_err(|e| {
+                tracing::error!("database connection broken: {}", e);
+                ApiError::DatabaseConnectionBroken
+            })?;
+
+            // deleted_at is stamped with the database clock, so the cutoff is taken from the same clock
+            receipts::table
+                .filter(receipts::deleted_at.lt((now - retention_days.days()).nullable()))
+                .select(receipts::id)
+                .get_results::<i32>(conn).map_err(|e| {
+                    tracing::error!("Unable to retrieve expired receipts in trash: {}", e);
+                    ApiError::PurgeReceiptFailed
+                })?
+        };
+
+        for id in expired_receipt_ids {
+            self.purge_receipt(id).await?;
         }
 
-        // query associated inventory_id, product_id pairs
-        let inventory_product_pairs: Vec<(i32, i32)> = inventories::table.filter(inventories::receipt_id.eq(id)).select((inventories::id, inventories::product_id)).get_results::<(i32, i32)>(conn).or_else(|e| {
-            tracing::error!("Unable to retrieve associated inventories: {}", e);
-            Err(ApiError::DeleteReceiptAssociatedEntryFailed)
-        }).expect("Unwrap inventory/product pairs should not be failed.");
+        Ok(())
+    }
+
+    async fn purge_receipt(&self, id: i32) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        // the receipt, its inventories and the orphaned entries are purged as a whole, or not at all
+        conn.transaction::<(), ApiError, _>(|conn| {
+            let receipt_to_be_delete: EntityReceipt = receipts::table.filter(receipts::id.eq(id)).filter(receipts::deleted_at.is_not_null()).get_result::<EntityReceipt>(conn).optional().map_err(|e| {
+                tracing::error!("Unable to retrieve the receipt to be purged: {}", e);
+                ApiError::PurgeReceiptFailed
+            })?.ok_or(ApiError::DeleteReceiptIdNotExisted)?;
+
+            // query associated inventory_id, product_id pairs
+            let inventory_product_pairs: Vec<(i32, i32)> = inventories::table.filter(inventories::receipt_id.eq(id)).select((inventories::id, inventories::product_id)).get_results::<(i32, i32)>(conn).map_err(|e| {
+                tracing::error!("Unable to retrieve associated inventories: {}", e);
+                ApiError::DeleteReceiptAssociatedEntryFailed
+            })?;
+
+            let (inventory_ids, product_ids): (Vec<i32>, Vec<i32>) = inventory_product_pairs.into_iter().unzip();
 
-        let (inventory_ids, product_ids): (Vec<i32>, Vec<i32>) = inventory_product_pairs.into_iter().unzip();
+            // delete associated inventories
+            delete(inventories::table.filter(inventories::id.eq_any(inventory_ids))).execute(conn).map_err(|e| {
+                tracing::error!("Unable to delete associated inventories: {}", e);
+                ApiError::DeleteReceiptAssociatedEntryFailed
+            })?;
+
+            // delete associated products if there is no inventory refers to this product
+            self.delete_unreferenced_products(conn, product_ids)?;
+
+            // delete receipt
+            delete(receipts::table).filter(receipts::id.eq(id)).execute(conn).map_err(|e| {
+                tracing::error!("Unable to delete receipt: {}", e);
+                ApiError::DeleteReceiptEntryFailed
+            })?;
+
+            // delete related store and currency if there is no receipt refers to them
+            self.delete_unreferenced_store(conn, receipt_to_be_delete.store_id)?;
+            self.delete_unreferenced_currency(conn, receipt_to_be_delete.currency_id)?;
 
-        // delete associated inventories
-        delete(inventories::table.filter(inventories::id.eq_any(inventory_ids))).execute(conn).or_else(|e| {
-            tracing::error!("Unable to delete associated inventories: {}", e);
-            Err(ApiError::DeleteReceiptAssociatedEntryFailed)
+            Ok(())
         })?;
 
+        tracing::debug!("Purge receipt {} successfully", id);
+        Ok(())
+    }
+
+    pub async fn replace_receipt(&self, id: i32, form_receipt: &CreateReceiptPayload, expected_version: i32) -> Result<(), ApiError> {
+        let currency_id = self.validate_currency(&form_receipt.currency).await.inspect_err(|e| tracing::error!("validate_currency failed: {:?}", e))?;
+
+        let store_status = self.validate_store(&form_receipt.store).await.inspect_err(|e| tracing::error!("validate_store failed: {:?}", e))?;
+
+        let mut inventories_metadata = vec![];
+        for inventory in &form_receipt.inventories {
+            let product_status = self.validate_product(&inventory.product).await.inspect_err(|e| tracing::error!("validate_product failed: {:?}", e))?;
+            inventories_metadata.push((product_status, inventory));
+        }
+
+        let transaction_date = TimeZoneService::new().resolve_transaction_date(&form_receipt.transaction_date, form_receipt.time_zone)?;
+
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+        let minor_units = self.get_minor_units(conn, currency_id)?;
+        let (discount, service_charge, printed_total) = self.validate_amounts(form_receipt, minor_units)?;
+        self.validate_tax_rates(conn, form_receipt)?;
+        let (settlement_currency_id, settled_amount, settlement_fee) = self.validate_settlement(conn, form_receipt.settlement.as_ref(), currency_id)?;
+
+        let receipt_to_be_replaced = receipts::table.filter(receipts::id.eq(id)).filter(receipts::deleted_at.is_null()).get_result::<EntityReceipt>(conn).map_err(|e| {
+            tracing::warn!("try to replace a non existed receipt ({}): {}", id, e);
+            ApiError::ReplaceReceiptIdNotExisted
+        })?;
+
+        if receipt_to_be_replaced.version != expected_version {
+            tracing::warn!("receipt {} is modified by others, its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
+
+        // The header, the inventories and the clean up of orphaned entries are replaced as a whole, or not at all
+        conn.transaction::<(), ApiError, _>(|conn| {
+            let currency_ref_id = if let Some(currency_id) = currency_id {
+                currency_id
+            }
+            else {
+                let new_currency = NewEntityCurrency {
+                    name: form_receipt.currency.name.clone().expect("currency name should not be none after validation")
+                };
+                insert_into(currencies::table).values(&new_currency).returning(currencies::id).get_result::<i32>(conn).map_err(|e| {
+                    tracing::error!("insert currency entity failed: {}", e);
+                    ApiError::InsertCurrencyFailed
+                })?
+            };
+
+            let store_ref_id = if store_status == FormRelationshipModelStatus::Id {
+                form_receipt.store.id.expect("store id should not be none after validation")
+            }
+            else {
+                let new_store = NewEntityStore {
+                    name: form_receipt.store.name.clone().expect("store name should not be none after validation"),
+                    alias: form_receipt.store.alias.clone(),
+                    branch: form_receipt.store.branch.clone(),
+                    address: form_receipt.store.address.clone(),
+                    tax_rate_id: form_receipt.store.tax_rate_id
+                };
+                insert_into(stores::table).values(&new_store).returning(stores::id).get_result::<i32>(conn).map_err(|e| {
+                    tracing::error!("insert store entity failed: {}", e);
+                    ApiError::InsertStoreFailed
+                })?
+            };
+
+            let affected_rows = update(receipts::table.filter(receipts::id.eq(id)).filter(receipts::version.eq(expected_version)))
+                .set((
+                    receipts::transaction_date.eq(transaction_date.with_timezone(&Utc)),
+                    receipts::transaction_offset.eq(transaction_date.offset().local_minus_utc()),
+                    receipts::discount.eq(&discount),
+                    receipts::service_charge.eq(&service_charge),
+                    receipts::printed_total.eq(&printed_total),
+                    receipts::settlement_currency_id.eq(settlement_currency_id),
+                    receipts::settled_amount.eq(&settled_amount),
+                    receipts::settlement_fee.eq(&settlement_fee),
+                    receipts::currency_id.eq(currency_ref_id),
+                    receipts::store_id.eq(store_ref_id),
+                    receipts::version.eq(receipts::version + 1)
+                ))
+                .execute(conn).map_err(|e| {
+                    tracing::error!("update receipt entity failed: {}", e);
+                    ApiError::UpdateReceiptFailed
+                })?;
+
+            if affected_rows == 0 {
+                return Err(ApiError::PreconditionFailed);
+            }
+
+            let store_tax_rate_id = self.get_store_tax_rate_id(conn, store_ref_id)?;
+            let previous_product_ids = delete(inventories::table.filter(inventories::receipt_id.eq(id))).returning(inventories::product_id).get_results::<i32>(conn).map_err(|e| {
+                tracing::error!("Unable to delete replaced inventories: {}", e);
+                ApiError::ReplaceReceiptInventoriesFailed
+            })?;
+
+            for (product_status, inventory) in &inventories_metadata {
+                let product_ref_id = if *product_status == FormRelationshipModelStatus::Id {
+                    inventory.product.id.expect("product id should not be none after validation")
+                }
+                else {
+                    let new_product = NewEntityProduct {
+                        name: inventory.product.name.clone().expect("product name should not be none after validation"),
+                        alias: inventory.product.alias.clone(),
+                        specification_amount: inventory.product.specification_amount,
+                        specification_unit: inventory.product.specification_unit.clone(),
+                        specification_others: inventory.product.specification_others.clone(),
+                        brand: inventory.product.brand.clone()
+                    };
+                    insert_into(products::table).values(&new_product).returning(products::id).get_result::<i32>(conn).map_err(|e| {
+                        tracing::error!("insert product entity failed: {}", e);
+                        ApiError::InsertProductFailed
+                    })?
+                };
+
+                let new_inventory = self.get_new_inventory(conn, inventory, product_ref_id, id, store_tax_rate_id, minor_units)?;
+                insert_into(inventories::table).values(&new_inventory).execute(conn).map_err(|e| {
+                    tracing::error!("insert inventory entity failed: {}", e);
+                    ApiError::InsertInventoryFailed
+                })?;
+            }
+
+            self.delete_unreferenced_products(conn, previous_product_ids)?;
+
+            if receipt_to_be_replaced.store_id != store_ref_id {
+                self.delete_unreferenced_store(conn, receipt_to_be_replaced.store_id)?;
+            }
+
+            if receipt_to_be_replaced.currency_id != currency_ref_id {
+                self.delete_unreferenced_currency(conn, receipt_to_be_replaced.currency_id)?;
+            }
+
+            Ok(())
+        })?;
+
+        tracing::debug!("Replace receipt {} successfully", id);
+        Ok(())
+    }
+
+    // a new currency is created with the default minor units
+    fn get_minor_units(&self, conn: &mut PgConnection, currency_id: Option<i32>) -> Result<i32, ApiError> {
+        match currency_id {
+            Some(currency_id) => currencies::table.filter(currencies::id.eq(currency_id)).select(currencies::minor_units).get_result::<i32>(conn).map_err(|e| {
+                tracing::error!("Unable to get the minor units of currency {}: {}", currency_id, e);
+                ApiError::CurrencyIdNotExisted
+            }),
+            None => Ok(DEFAULT_MINOR_UNITS)
+        }
+    }
+
+    // the receipt level amounts, and every line price and discount is checked against the minor units of the currency before anything is written
+    fn validate_amounts(&self, form_receipt: &CreateReceiptPayload, minor_units: i32) -> Result<(BigDecimal, BigDecimal, Option<BigDecimal>), ApiError> {
+        let total_service = TotalService::new();
+        for inventory in &form_receipt.inventories {
+            let price = total_service.get_amount(&inventory.price, minor_units)?;
+            let quantity = total_service.get_quantity(&inventory.quantity)?;
+            let line_price = match &inventory.line_price {
+                Some(line_price) => total_service.get_amount(line_price, minor_units)?,
+                None => total_service.get_line_price(&price, &quantity, minor_units)
+            };
+            total_service.get_discount(&inventory.discount, &line_price, minor_units)?;
+        }
+
+        Ok((
+            total_service.get_amount(&form_receipt.discount, minor_units)?,
+            total_service.get_amount(&form_receipt.service_charge, minor_units)?,
+            form_receipt.printed_total.as_ref().map(|p| total_service.get_amount(p, minor_units)).transpose()?
+        ))
+    }
+
+    // the settlement currency has to exist already and differ from the currency of the receipt, a new currency of the receipt differs from all of them
+    fn validate_settlement(&self, conn: &mut PgConnection, settlement: Option<&CreateSettlementInReceiptPayload>, currency_id: Option<i32>) -> Result<Settlement, ApiError> {
+        let Some(settlement) = settlement else {
+            return Ok((None, None, None));
+        };
+
+        let settlement_currency_id = match (settlement.currency.id, settlement.currency.name.as_ref()) {
+            (Some(settlement_currency_id), _) => Some(settlement_currency_id),
+            (None, Some(name)) => CurrencyService::new(self.repository).find_currency_id(conn, name)?,
+            (None, None) => {
+                tracing::warn!("invalid settlement currency");
+                return Err(ApiError::CurrencyInvalid);
+            }
+        };
+        let settlement_currency_id = settlement_currency_id.ok_or_else(|| {
+            tracing::warn!("settlement currency {:?} is not existed", settlement.currency.name);
+            ApiError::CurrencyIdNotExisted
+        })?;
+
+        if currency_id == Some(settlement_currency_id) {
+            tracing::warn!("settlement currency {} is the currency of the receipt", settlement_currency_id);
+            return Err(ApiError::InvalidParameter);
+        }
+
+        let minor_units = self.get_minor_units(conn, Some(settlement_currency_id))?;
+        let total_service = TotalService::new();
+        Ok((
+            Some(settlement_currency_id),
+            Some(total_service.get_amount(&settlement.amount, minor_units)?),
+            Some(total_service.get_amount(&settlement.fee, minor_units)?)
+        ))
+    }
+
+    // every given rate has to exist before anything is written
+    fn validate_tax_rates(&self, conn: &mut PgConnection, form_receipt: &CreateReceiptPayload) -> Result<(), ApiError> {
+        let tax_rate_service = TaxRateService::new(self.repository);
+        tax_rate_service.find_tax_rate(conn, form_receipt.store.tax_rate_id)?;
+        for inventory in &form_receipt.inventories {
+            tax_rate_service.find_tax_rate(conn, inventory.tax_rate_id.flatten())?;
+        }
+
+        Ok(())
+    }
+
+    fn get_store_tax_rate_id(&self, conn: &mut PgConnection, store_id: i32) -> Result<Option<i32>, ApiError> {
+        stores::table.filter(stores::id.eq(store_id)).select(stores::tax_rate_id).get_result::<Option<i32>>(conn).map_err(|e| {
+            tracing::error!("Unable to get the tax rate of store {}: {}", store_id, e);
+            ApiError::StoreIdNotExisted
+        })
+    }
+
+    // a line without its own rate takes the rate of the store, and its tax amounts are fixed at the time it is written
+    fn get_new_inventory(&self, conn: &mut PgConnection, inventory: &CreateInventoryInReceiptPayload, product_id: i32, receipt_id: i32, store_tax_rate_id: Option<i32>, minor_units: i32) -> Result<NewEntityInventory, ApiError> {
+        let total_service = TotalService::new();
+        let price = total_service.get_amount(&inventory.price, minor_units)?;
+        let quantity = total_service.get_quantity(&inventory.quantity)?;
+        let line_price = match &inventory.line_price {
+            Some(line_price) => total_service.get_amount(line_price, minor_units)?,
+            None => total_service.get_line_price(&price, &quantity, minor_units)
+        };
+        let discount = total_service.get_discount(&inventory.discount, &line_price, minor_units)?;
+        let tax_rate_id = inventory.tax_rate_id.unwrap_or(store_tax_rate_id);
+        let tax_rate = TaxRateService::new(self.repository).find_tax_rate(conn, tax_rate_id)?;
+        let line_total = total_service.get_line_total(&line_price, &discount);
+        let (pre_tax_amount, post_tax_amount) = total_service.get_tax_amounts(&line_total, tax_rate.as_ref(), minor_units);
+
+        Ok(NewEntityInventory {
+            price,
+            quantity,
+            product_id,
+            receipt_id,
+            discount,
+            tax_rate_id,
+            pre_tax_amount,
+            post_tax_amount,
+            unit: inventory.unit.name().to_string(),
+            line_price
+        })
+    }
+
+    // sums the line totals per receipt in one query, so a list has its totals without loading the inventories
+    fn get_subtotals(&self, conn: &mut PgConnection, receipt_ids: &[i32]) -> Result<HashMap<i32, EntityReceiptSubtotal>, ApiError> {
+        let subtotals = inventories::table
+            .filter(inventories::receipt_id.eq_any(receipt_ids))
+            .group_by(inventories::receipt_id)
+            .select((inventories::receipt_id, sql::<Numeric>(RECEIPT_SUBTOTAL_SQL), sql::<Numeric>(RECEIPT_PRE_TAX_SQL), sql::<Numeric>(RECEIPT_POST_TAX_SQL), count(inventories::id)))
+            .get_results::<EntityReceiptSubtotal>(conn).map_err(|e| {
+                tracing::error!("Unable to sum up the receipt subtotals: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        Ok(subtotals.into_iter().map(|subtotal| (subtotal.receipt_id, subtotal)).collect())
+    }
+
+    fn delete_unreferenced_products(&self, conn: &mut PgConnection, product_ids: Vec<i32>) -> Result<(), ApiError> {
         // query the ids of product which is needed to be deleted
         let mut product_to_be_delete_ids = vec![];
         for product_id in product_ids {
-            let is_not_referred_product = select(not(exists(inventories::table.filter(inventories::product_id.eq(product_id))))).get_result::<bool>(conn).or_else(|e| {
+            let is_not_referred_product = select(not(exists(inventories::table.filter(inventories::product_id.eq(product_id))))).get_result::<bool>(conn).map_err(|e| {
                 tracing::error!("Unable to retrieve associated product_id in inventories: {}", e);
-                Err(ApiError::DeleteReceiptAssociatedEntryFailed)
-            }).expect("Unwrap non existed product should not be failed");
-            if is_not_referred_product {
+                ApiError::DeleteReceiptAssociatedEntryFailed
+            })?;
+            if is_not_referred_product && !product_to_be_delete_ids.contains(&product_id) {
                 product_to_be_delete_ids.push(product_id);
             }
         }
 
-        // delete associated products if there is no inventory refers to this product
-        delete(products::table.filter(products::id.eq_any(product_to_be_delete_ids))).execute(conn).or_else(|e| {
+        delete(products::table.filter(products::id.eq_any(product_to_be_delete_ids))).execute(conn).map_err(|e| {
             tracing::error!("Unable to delete associated product: {}", e);
-            Err(ApiError::DeleteReceiptAssociatedEntryFailed)
+            ApiError::DeleteReceiptAssociatedEntryFailed
         })?;
 
-        let receipt_to_be_delete: EntityReceipt = receipts::table.filter(receipts::id.eq(id)).get_result::<EntityReceipt>(conn).or_else(|e| {
-            tracing::error!("Unable to retrieve the receipt to be deleted: {}", e);
-            Err(ApiError::DeleteReceiptAssociatedEntryFailed)
-        }).expect("Unwrap receipt should not be failed.");
-
-        // delete receipt
-        delete(receipts::table).filter(receipts::id.eq(id)).execute(conn).or_else(|e| {
-            tracing::error!("Unable to delete receipt: {}", e);
-            Err(ApiError::DeleteReceiptEntryFailed)
-        })?;
+        Ok(())
+    }
 
-        let is_not_referred_store = select(not(exists(receipts::table.filter(receipts::store_id.eq(&receipt_to_be_delete.store_id))))).get_result::<bool>(conn).or_else(|e| {
+    fn delete_unreferenced_store(&self, conn: &mut PgConnection, store_id: i32) -> Result<(), ApiError> {
+        let is_not_referred_store = select(not(exists(receipts::table.filter(receipts::store_id.eq(store_id))))).get_result::<bool>(conn).map_err(|e| {
             tracing::error!("Unable to retrieve related store: {}", e);
-            Err(ApiError::DeleteReceiptRelatedEntryFailed)
-        }).expect("Unwrap non existed store should not be failed");
+            ApiError::DeleteReceiptRelatedEntryFailed
+        })?;
 
-        // delete related store if there is no receipt refers to this store
         if is_not_referred_store {
-            delete(stores::table.filter(stores::id.eq(&receipt_to_be_delete.store_id))).execute(conn).or_else(|e| {
+            delete(stores::table.filter(stores::id.eq(store_id))).execute(conn).map_err(|e| {
                 tracing::error!("Unable to delete related store: {}", e);
-                Err(ApiError::DeleteReceiptEntryFailed)
+                ApiError::DeleteReceiptEntryFailed
             })?;
         }
 
-        let is_not_referred_currency = select(not(exists(receipts::table.filter(receipts::currency_id.eq(&receipt_to_be_delete.currency_id))))).get_result::<bool>(conn).or_else(|e| {
+        Ok(())
+    }
+
+    // only a currency typed in by a user goes with its last receipt, a seeded ISO currency or one with aliases, exchange rates or a user setting stays
+    fn delete_unreferenced_currency(&self, conn: &mut PgConnection, currency_id: i32) -> Result<(), ApiError> {
+        let is_not_referred_currency = select(
+            not(exists(receipts::table.filter(receipts::currency_id.eq(currency_id).or(receipts::settlement_currency_id.eq(currency_id)))))
+                .and(exists(currencies::table.filter(currencies::id.eq(currency_id)).filter(currencies::code.is_null())))
+                .and(not(exists(currency_aliases::table.filter(currency_aliases::currency_id.eq(currency_id)))))
+                .and(not(exists(exchange_rates::table.filter(exchange_rates::from_currency_id.eq(currency_id).or(exchange_rates::to_currency_id.eq(currency_id))))))
+                .and(not(exists(user_settings::table.filter(user_settings::base_currency_id.eq(currency_id)))))
+        ).get_result::<bool>(conn).map_err(|e| {
             tracing::error!("Unable to retrieve related currency: {}", e);
-            Err(ApiError::DeleteReceiptRelatedEntryFailed)
-        }).expect("Unwrap non existed currency should not be failed");
+            ApiError::DeleteReceiptRelatedEntryFailed
+        })?;
 
-        // delete related currency if there is no receipt refers to this currency
         if is_not_referred_currency {
-            delete(currencies::table.filter(currencies::id.eq(&receipt_to_be_delete.currency_id))).execute(conn).or_else(|e| {
+            delete(currencies::table.filter(currencies::id.eq(currency_id))).execute(conn).map_err(|e| {
                 tracing::error!("Unable to delete related currency: {}", e);
-                Err(ApiError::DeleteReceiptEntryFailed)
+                ApiError::DeleteReceiptEntryFailed
             })?;
         }
 
-        tracing::debug!("Delete receipt {} successfully", id);
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod reports_service;
\ No newline at end of file
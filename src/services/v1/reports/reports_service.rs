@@ -0,0 +1,342 @@
+use std::collections::{btree_map, hash_map::Entry, BTreeMap, HashMap};
+use bigdecimal::BigDecimal;
+use diesel::{dsl::{exists, select}, sql_query, sql_types::{Int4, Nullable, Text, Timestamptz}, ExpressionMethods, PgConnection, PgTextExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+
+use crate::{
+    models::v1::{
+        entities::{entity_currency::EntityCurrency, entity_inventory::EntityInventory, entity_product::EntityProduct, entity_receipt::EntityReceipt, entity_store::EntityStore, entity_total::EntityTaxReport, entity_unit::{EntityBasketItem, EntityPriceHistory, EntityPricedLine, EntityStoreBasket, EntityUnitPriceComparison, EntityUnitPriceStats, StandardUnit}}, 
+        errors::api_error::ApiError, 
+        forms::create_payload::QuantityUnit, 
+        parameters::{conversion::Conversion, matching::MatchMode, query_filters::{BasketFilters, ReportFilters, UnitPriceReportFilters}}, 
+        responses::response_report::{ResponsePriceHistory, ResponseStoreBasket, ResponseTaxReport, ResponseUnitPriceComparison}
+    }, 
+    repository::DbRepository, 
+    schema::{currencies, inventories, products, receipts, stores}, 
+    services::v1::{batches::batches_service::BatchService, converters::converters_service::ConverterService, exchange_rates::exchange_rates_service::ExchangeRateService, matchers::matchers_service::MatcherService, timezones::timezones_service::{DateRange, TimeZoneService}, totals::totals_service::{receipt_total_sql, TotalService}, units::units_service::UnitService}
+};
+
+type PurchasedLines = Vec<(EntityInventory, EntityProduct, EntityReceipt, EntityStore, EntityCurrency)>;
+
+// periods start at the local midnight of the time zone, amounts are the tax amounts fixed on the lines
+// with a currency to convert to, every receipt is converted with the rate effective on its local transaction date and a period has a single row
+const TAX_REPORT_SQL: &str = concat!("
+    WITH converted_receipts AS (
+        SELECT receipts.id, receipts.transaction_date, COALESCE($6::INTEGER, receipts.currency_id) AS currency_id,
+            CASE WHEN $6::INTEGER IS NULL THEN 1
+                WHEN receipts.settlement_currency_id = $6 AND t.total > 0 THEN receipts.settled_amount / t.total
+                ELSE exchange_rate(receipts.currency_id, $6, ((receipts.transaction_date AT TIME ZONE 'UTC') + receipts.transaction_offset * INTERVAL '1 second')::DATE) END AS rate
+        FROM receipts
+        CROSS JOIN LATERAL (SELECT ", receipt_total_sql!(), " AS total) t
+        WHERE receipts.deleted_at IS NULL
+            AND ($3::TIMESTAMPTZ IS NULL OR receipts.transaction_date >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR receipts.transaction_date < $4)
+            AND ($5::INTEGER IS NULL OR receipts.store_id = $5)
+    )
+    SELECT DATE_TRUNC($1, r.transaction_date AT TIME ZONE $2)::DATE AS period_start,
+        c.id AS currency_id,
+        c.name AS currency,
+        c.minor_units,
+        COUNT(DISTINCT r.id) AS receipt_count,
+        COUNT(DISTINCT r.id) FILTER (WHERE r.rate IS NULL) AS unconverted_count,
+        COALESCE(SUM(i.pre_tax_amount * r.rate), 0) AS pre_tax,
+        COALESCE(SUM(i.post_tax_amount * r.rate), 0) AS post_tax
+    FROM converted_receipts r
+    INNER JOIN inventories i ON i.receipt_id = r.id
+    INNER JOIN currencies c ON c.id = r.currency_id
+    GROUP BY 1, c.id, c.name, c.minor_units
+    ORDER BY 1, c.name
+");
+
+pub struct ReportService<'a> {
+    repository: &'a DbRepository
+}
+
+impl<'a> ReportService<'a> {
+    pub fn new(repository: &'a DbRepository) -> Self {
+        Self {
+            repository
+        }
+    }
+
+    pub async fn get_tax_report(&self, report_filters: &ReportFilters, conversion: &Conversion) -> Result<Vec<ResponseTaxReport>, ApiError> {
+        let converter = ConverterService::new();
+        let time_zone_service = TimeZoneService::new();
+        let tz = time_zone_service.get_time_zone(report_filters.tz);
+        let (from, to) = time_zone_service.get_date_range(report_filters.start_date, report_filters.end_date, report_filters.preset, Some(tz))?;
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let conversion_currency = conversion.convert_to.as_ref().map(|convert_to| ExchangeRateService::new(self.repository).get_conversion_currency(conn, convert_to, &conversion.username)).transpose()?;
+        let rows = sql_query(TAX_REPORT_SQL)
+            .bind::<Text, _>(report_filters.period.unwrap_or_default().name())
+            .bind::<Text, _>(tz.name())
+            .bind::<Nullable<Timestamptz>, _>(from)
+            .bind::<Nullable<Timestamptz>, _>(to)
+            .bind::<Nullable<Int4>, _>(report_filters.store_id)
+            .bind::<Nullable<Int4>, _>(conversion_currency.map(|c| c.id))
+            .load::<EntityTaxReport>(conn)
+            .map_err(|e| {
+                tracing::error!("tax report failed: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        // a total which leaves out some receipts would look right but be wrong
+        let unconverted_count = rows.iter().map(|row| row.unconverted_count).sum::<i64>();
+        if unconverted_count > 0 {
+            tracing::warn!("{} receipts have no exchange rate effective on their transaction dates", unconverted_count);
+            return Err(ApiError::ExchangeRateNotExisted);
+        }
+
+        Ok(rows.into_iter().map(|row| converter.convert_to_tax_report_response(row)).collect())
+    }
+
+    pub async fn get_unit_price_report(&self, report_filters: &UnitPriceReportFilters, conversion: &Conversion) -> Result<Vec<ResponseUnitPriceComparison>, ApiError> {
+        let converter = ConverterService::new();
+        let keyword = report_filters.keyword.as_deref().map(|keyword| keyword.trim()).unwrap_or_default();
+        if keyword.is_empty() {
+            tracing::warn!("unit price report without any keyword");
+            return Err(ApiError::InvalidParameter);
+        }
+
+        let date_range = TimeZoneService::new().get_date_range(report_filters.start_date, report_filters.end_date, report_filters.preset, report_filters.tz)?;
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let priced_lines = self.get_priced_lines(conn, None, Some(keyword), date_range, report_filters.store_id, conversion)?;
+        let mut comparisons: HashMap<(i32, i32, i32, StandardUnit), EntityUnitPriceComparison> = HashMap::new();
+        for line in priced_lines {
+            match comparisons.entry((line.product.id, line.store.id, line.currency.id, line.unit)) {
+                Entry::Occupied(mut entry) => entry.get_mut().stats.add(&line.unit_price, line.purchased_at),
+                Entry::Vacant(entry) => {
+                    entry.insert(EntityUnitPriceComparison {
+                        stats: EntityUnitPriceStats::new(&line.unit_price, line.purchased_at),
+                        product: line.product,
+                        store: line.store,
+                        currency: line.currency,
+                        unit: line.unit
+                    });
+                }
+            }
+        }
+
+        let mut comparisons = comparisons.into_values().collect::<Vec<EntityUnitPriceComparison>>();
+        comparisons.sort_by(|a, b| {
+            (a.unit, a.currency.id).cmp(&(b.unit, b.currency.id))
+                .then_with(|| a.stats.average().cmp(&b.stats.average()))
+                .then_with(|| a.stats.lowest.cmp(&b.stats.lowest))
+                .then_with(|| (a.product.id, a.store.id).cmp(&(b.product.id, b.store.id)))
+        });
+
+        // the first of each standard unit and currency is the cheapest
+        let mut previous_group = None;
+        Ok(comparisons.into_iter().map(|comparison| {
+            let group = Some((comparison.unit, comparison.currency.id));
+            let is_cheapest = group != previous_group;
+            previous_group = group;
+            converter.convert_to_unit_price_comparison_response(comparison, is_cheapest)
+        }).collect())
+    }
+
+    // a series per store, currency and standard unit, with a point per period in the time zone of the filters
+    pub async fn get_price_history(&self, product_id: i32, report_filters: &ReportFilters, conversion: &Conversion) -> Result<Vec<ResponsePriceHistory>, ApiError> {
+        let converter = ConverterService::new();
+        let time_zone_service = TimeZoneService::new();
+        let tz = time_zone_service.get_time_zone(report_filters.tz);
+        let period = report_filters.period.unwrap_or_default();
+        let date_range = time_zone_service.get_date_range(report_filters.start_date, report_filters.end_date, report_filters.preset, Some(tz))?;
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let is_existed = select(exists(products::table.filter(products::id.eq(product_id)))).get_result::<bool>(conn).map_err(|_e| ApiError::NoRecord)?;
+        if !is_existed {
+            tracing::warn!("try to get the price history of a non existed product ({})", product_id);
+            return Err(ApiError::NoRecord);
+        }
+
+        let priced_lines = self.get_priced_lines(conn, Some(&[product_id]), None, date_range, report_filters.store_id, conversion)?;
+        let mut histories: HashMap<(i32, i32, StandardUnit), EntityPriceHistory> = HashMap::new();
+        for line in priced_lines {
+            let period_start = time_zone_service.get_period_start(line.purchased_at.with_timezone(&tz).date_naive(), period);
+            let history = histories.entry((line.store.id, line.currency.id, line.unit)).or_insert_with(|| EntityPriceHistory {
+                store: line.store,
+                currency: line.currency,
+                unit: line.unit,
+                points: BTreeMap::new()
+            });
+            match history.points.entry(period_start) {
+                btree_map::Entry::Occupied(mut entry) => entry.get_mut().add(&line.unit_price, line.purchased_at),
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(EntityUnitPriceStats::new(&line.unit_price, line.purchased_at));
+                }
+            }
+        }
+
+        let mut histories = histories.into_values().collect::<Vec<EntityPriceHistory>>();
+        histories.sort_by(|a, b| (&a.store.name, a.store.id, a.currency.id, a.unit).cmp(&(&b.store.name, b.store.id, b.currency.id, b.unit)));
+        Ok(histories.into_iter().map(|history| converter.convert_to_price_history_response(history)).collect())
+    }
+
+    // only the lines in the standard unit of an item are compared, stores are ranked by the fewest missing items and then the latest total
+    pub async fn get_cheapest_stores(&self, basket_filters: &BasketFilters, conversion: &Conversion) -> Result<Vec<ResponseStoreBasket>, ApiError> {
+        let converter = ConverterService::new();
+        let unit_service = UnitService::new();
+        let total_service = TotalService::new();
+        let product_ids = basket_filters.product_ids.clone().unwrap_or_default();
+        let quantities = basket_filters.quantities.clone().unwrap_or_else(|| vec![BigDecimal::from(1); product_ids.len()]);
+        let units = basket_filters.units.clone().unwrap_or_else(|| vec![QuantityUnit::Piece.name().to_string(); product_ids.len()]);
+        let unique_product_ids = BatchService::new().get_unique_ids(&product_ids)?;
+        if unique_product_ids.len() != product_ids.len() || quantities.len() != product_ids.len() || units.len() != product_ids.len() {
+            tracing::warn!("basket of {} products with {} quantities and {} units", product_ids.len(), quantities.len(), units.len());
+            return Err(ApiError::InvalidParameter);
+        }
+
+        let date_range = TimeZoneService::new().get_date_range(basket_filters.start_date, basket_filters.end_date, basket_filters.preset, basket_filters.tz)?;
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let mut products_map = products::table.filter(products::id.eq_any(&product_ids)).select(<EntityProduct>::as_select()).get_results::<EntityProduct>(conn).map_err(|_e| ApiError::NoRecord)?
+            .into_iter().map(|p| (p.id, p)).collect::<HashMap<i32, EntityProduct>>();
+        let mut items = vec![];
+        for ((product_id, quantity), unit) in product_ids.iter().zip(quantities).zip(units) {
+            let product = products_map.remove(product_id).ok_or_else(|| {
+                tracing::warn!("product {} in the basket is not existed", product_id);
+                ApiError::ProductIdNotExisted
+            })?;
+            let quantity = total_service.get_quantity(&quantity)?;
+            let standard_quantity = unit_service.get_standard_quantity_of(&quantity, &unit, &product).ok_or_else(|| {
+                tracing::warn!("unit {} of product {} in the basket is not known", unit, product_id);
+                ApiError::InvalidParameter
+            })?;
+            items.push(EntityBasketItem {
+                product,
+                quantity,
+                unit,
+                standard_quantity
+            });
+        }
+
+        let priced_lines = self.get_priced_lines(conn, Some(&product_ids), None, date_range, None, conversion)?;
+        let item_units = items.iter().map(|item| (item.product.id, item.standard_quantity.unit)).collect::<HashMap<i32, StandardUnit>>();
+        let mut baskets: HashMap<(i32, i32), EntityStoreBasket> = HashMap::new();
+        for line in priced_lines {
+            if item_units.get(&line.product.id) != Some(&line.unit) {
+                continue;
+            }
+
+            let basket = baskets.entry((line.store.id, line.currency.id)).or_insert_with(|| EntityStoreBasket {
+                store: line.store,
+                currency: line.currency,
+                prices: HashMap::new(),
+                latest_total: BigDecimal::from(0),
+                average_total: BigDecimal::from(0)
+            });
+            match basket.prices.entry(line.product.id) {
+                Entry::Occupied(mut entry) => entry.get_mut().add(&line.unit_price, line.purchased_at),
+                Entry::Vacant(entry) => {
+                    entry.insert(EntityUnitPriceStats::new(&line.unit_price, line.purchased_at));
+                }
+            }
+        }
+
+        let mut baskets = baskets.into_values().map(|mut basket| {
+            for item in &items {
+                if let Some(stats) = basket.prices.get(&item.product.id) {
+                    basket.latest_total += unit_service.get_cost(&stats.latest, &item.standard_quantity);
+                    basket.average_total += unit_service.get_cost(&stats.average(), &item.standard_quantity);
+                }
+            }
+            basket
+        }).collect::<Vec<EntityStoreBasket>>();
+        baskets.sort_by(|a, b| {
+            (a.currency.id, items.len() - a.prices.len()).cmp(&(b.currency.id, items.len() - b.prices.len()))
+                .then_with(|| a.latest_total.cmp(&b.latest_total))
+                .then_with(|| a.store.id.cmp(&b.store.id))
+        });
+
+        // the first complete basket of each currency is the cheapest
+        let mut previous_currency_id = None;
+        Ok(baskets.into_iter().map(|basket| {
+            let is_complete = basket.prices.len() == items.len();
+            let is_cheapest = is_complete && previous_currency_id != Some(basket.currency.id);
+            if is_complete {
+                previous_currency_id = Some(basket.currency.id);
+            }
+            converter.convert_to_store_basket_response(basket, &items, is_cheapest)
+        }).collect())
+    }
+
+    // a line without a known unit is left out, a line of a receipt without any exchange rate fails the report like the tax report does
+    fn get_priced_lines(&self, conn: &mut PgConnection, product_ids: Option<&[i32]>, keyword: Option<&str>, date_range: DateRange, store_id: Option<i32>, conversion: &Conversion) -> Result<Vec<EntityPricedLine>, ApiError> {
+        let converter = ConverterService::new();
+        let unit_service = UnitService::new();
+        let total_service = TotalService::new();
+        let mut query = inventories::table
+            .inner_join(products::table)
+            .inner_join(receipts::table.inner_join(stores::table).inner_join(currencies::table))
+            .filter(receipts::deleted_at.is_null())
+            .into_boxed();
+        if let Some(product_ids) = product_ids {
+            query = query.filter(inventories::product_id.eq_any(product_ids.to_vec()));
+        }
+        if let Some(keyword) = keyword {
+            query = query.filter(products::search_text.ilike(MatcherService::new().get_pattern(keyword, MatchMode::Contains)));
+        }
+        if let Some(from) = date_range.0 {
+            query = query.filter(receipts::transaction_date.ge(from));
+        }
+        if let Some(to) = date_range.1 {
+            query = query.filter(receipts::transaction_date.lt(to));
+        }
+        if let Some(store_id) = store_id {
+            query = query.filter(receipts::store_id.eq(store_id));
+        }
+
+        let rows: PurchasedLines = query
+            .select(<(EntityInventory, EntityProduct, EntityReceipt, EntityStore, EntityCurrency)>::as_select())
+            .get_results(conn)
+            .map_err(|e| {
+                tracing::error!("Unable to get the purchased lines: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        let receipt_ids = rows.iter().map(|row| row.2.id).collect::<Vec<i32>>();
+        let conversion = ExchangeRateService::new(self.repository).get_conversion(conn, conversion, &receipt_ids)?;
+        let mut priced_lines = vec![];
+        for (inventory, product, receipt, store, currency) in rows {
+            let Some(standard_quantity) = unit_service.get_standard_quantity(&inventory, &product) else {
+                continue;
+            };
+
+            let unit_price = unit_service.get_unit_price(&total_service.get_line_total(&inventory.line_price, &inventory.discount), &standard_quantity);
+            let (unit_price, currency) = match &conversion {
+                Some(conversion) => {
+                    let rate = conversion.rates.get(&receipt.id).ok_or_else(|| {
+                        tracing::warn!("receipt {} has no exchange rate effective on its transaction date", receipt.id);
+                        ApiError::ExchangeRateNotExisted
+                    })?;
+                    (unit_price * rate, conversion.currency.clone())
+                },
+                None => (unit_price, currency)
+            };
+
+            priced_lines.push(EntityPricedLine {
+                purchased_at: converter.convert_to_transaction_date(&receipt),
+                product,
+                store,
+                currency,
+                unit: standard_quantity.unit,
+                unit_price
+            });
+        }
+
+        Ok(priced_lines)
+    }
+}
@@ -0,0 +1,141 @@
+use diesel::{sql_query, sql_types::{Int8, Text}, RunQueryDsl};
+
+use crate::{
+    models::v1::{
+        entities::entity_search::EntitySearchHit, 
+        errors::api_error::ApiError, 
+        parameters::{matching::MatchMode, pagination::{DEFAULT_LIMIT, MAX_LIMIT}, query_filters::SearchFilters}, 
+        responses::response_search::ResponseSearchHit
+    }, 
+    repository::DbRepository, 
+    services::v1::{converters::converters_service::ConverterService, matchers::matchers_service::MatcherService}
+};
+
+// products and stores are matched on their generated search_text column (trigram and tsvector indexed),
+// receipts are hit through their store or any of their products
+const SEARCH_SQL: &str = "
+    WITH term AS (
+        SELECT $1::TEXT AS q, $3::TEXT AS pattern, plainto_tsquery('simple', $1) AS tsq
+    ),
+    product_hits AS (
+        SELECT p.id, p.name, p.brand, p.alias,
+            GREATEST(similarity(p.search_text, term.q), word_similarity(term.q, p.search_text), ts_rank(to_tsvector('simple', p.search_text), term.tsq)) AS score
+        FROM products p, term
+        WHERE p.search_text ILIKE term.pattern OR term.q <% p.search_text OR to_tsvector('simple', p.search_text) @@ term.tsq
+    ),
+    store_hits AS (
+        SELECT s.id, s.name, s.branch,
+            GREATEST(similarity(s.search_text, term.q), word_similarity(term.q, s.search_text), ts_rank(to_tsvector('simple', s.search_text), term.tsq)) AS score
+        FROM stores s, term
+        WHERE s.search_text ILIKE term.pattern OR term.q <% s.search_text OR to_tsvector('simple', s.search_text) @@ term.tsq
+    ),
+    receipt_hits AS (
+        SELECT r.id, s.name, r.transaction_date, r.transaction_offset,
+            GREATEST(COALESCE(MAX(sh.score), 0), COALESCE(MAX(ph.score), 0)) AS score
+        FROM receipts r
+        INNER JOIN stores s ON s.id = r.store_id
+        LEFT JOIN store_hits sh ON sh.id = r.store_id
+        LEFT JOIN inventories i ON i.receipt_id = r.id
+        LEFT JOIN product_hits ph ON ph.id = i.product_id
+        WHERE r.deleted_at IS NULL AND (sh.id IS NOT NULL OR ph.id IS NOT NULL)
+        GROUP BY r.id, s.name, r.transaction_date, r.transaction_offset
+    )
+    SELECT 'product' AS kind, id, name AS title, NULLIF(CONCAT_WS(' ', brand, alias), '') AS subtitle, score::FLOAT4 AS score FROM product_hits
+    UNION ALL
+    SELECT 'store' AS kind, id, name AS title, branch AS subtitle, score::FLOAT4 AS score FROM store_hits
+    UNION ALL
+    SELECT 'receipt' AS kind, id, name AS title, TO_CHAR((transaction_date AT TIME ZONE 'UTC') + transaction_offset * INTERVAL '1 second', 'YYYY-MM-DD HH24:MI') AS subtitle, (score * 0.9)::FLOAT4 AS score FROM receipt_hits
+    ORDER BY score DESC, kind, id
+    LIMIT $2
+";
+
+const HIGHLIGHT_START: &str = "<mark>";
+const HIGHLIGHT_END: &str = "</mark>";
+
+pub struct SearchService<'a> {
+    repository: &'a DbRepository
+}
+
+impl<'a> SearchService<'a> {
+    pub fn new(repository: &'a DbRepository) -> Self {
+        Self {
+            repository
+        }
+    }
+
+    pub async fn search(&self, search_filters: &SearchFilters) -> Result<Vec<ResponseSearchHit>, ApiError> {
+        let converter = ConverterService::new();
+        let matcher_service = MatcherService::new();
+        let q = search_filters.q.as_deref().map(|q| q.trim()).unwrap_or_default();
+        if q.is_empty() {
+            tracing::warn!("search without any keyword");
+            return Err(ApiError::InvalidParameter);
+        }
+
+        let limit = search_filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let hits = sql_query(SEARCH_SQL)
+            .bind::<Text, _>(q)
+            .bind::<Int8, _>(limit)
+            .bind::<Text, _>(matcher_service.get_pattern(q, MatchMode::Contains))
+            .load::<EntitySearchHit>(conn)
+            .map_err(|e| {
+                tracing::error!("search failed: {}", e);
+                ApiError::NoRecord
+            })?;
+
+        Ok(hits.into_iter().map(|hit| {
+            let highlight = self.highlight(&hit.title, q).or_else(|| hit.subtitle.as_ref().and_then(|s| self.highlight(s, q)));
+            converter.convert_to_search_hit_response(hit, highlight)
+        }).collect())
+    }
+
+    // wraps every case-insensitive occurrence of the query terms, none if nothing matches literally (e.g. a fuzzy hit)
+    // the text is escaped so the markers are the only markup a client rendering it as HTML would see
+    fn highlight(&self, text: &str, q: &str) -> Option<String> {
+        let terms = q.split_whitespace().map(|t| t.chars().flat_map(char::to_lowercase).collect::<Vec<char>>()).collect::<Vec<Vec<char>>>();
+        let chars = text.chars().collect::<Vec<char>>();
+        let lowered = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect::<Vec<char>>();
+
+        let mut highlighted = String::new();
+        let mut is_matched = false;
+        let mut i = 0;
+        while i < chars.len() {
+            let matched_len = terms.iter()
+                .filter(|t| !t.is_empty() && lowered[i..].starts_with(t))
+                .map(|t| t.len())
+                .max();
+
+            match matched_len {
+                Some(len) => {
+                    is_matched = true;
+                    highlighted.push_str(HIGHLIGHT_START);
+                    chars[i..i + len].iter().for_each(|c| self.push_escaped(&mut highlighted, *c));
+                    highlighted.push_str(HIGHLIGHT_END);
+                    i += len;
+                },
+                None => {
+                    self.push_escaped(&mut highlighted, chars[i]);
+                    i += 1;
+                }
+            }
+        }
+
+        is_matched.then_some(highlighted)
+    }
+
+    fn push_escaped(&self, highlighted: &mut String, c: char) {
+        match c {
+            '&' => highlighted.push_str("&amp;"),
+            '<' => highlighted.push_str("&lt;"),
+            '>' => highlighted.push_str("&gt;"),
+            '"' => highlighted.push_str("&quot;"),
+            '\'' => highlighted.push_str("&#39;"),
+            _ => highlighted.push(c)
+        }
+    }
+}
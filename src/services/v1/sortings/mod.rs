@@ -0,0 +1 @@
+pub mod sortings_service;
\ No newline at end of file
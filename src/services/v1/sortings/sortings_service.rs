@@ -0,0 +1,61 @@
+use diesel::{dsl::{Asc, Desc}, query_dsl::methods::ThenOrderDsl, ExpressionMethods};
+
+use crate::models::v1::{
+    errors::api_error::ApiError, 
+    parameters::sorting::{SortDirection, SortField, SortKey, Sorting}
+};
+
+pub struct SortingService {
+}
+
+impl SortingService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    // sort=-created_at,updated_at means created_at descending, then updated_at ascending
+    pub fn parse_sort<F: SortField>(&self, sorting: &Sorting) -> Result<Vec<SortKey<F>>, ApiError> {
+        let mut sort_keys = vec![];
+        if let Some(sort) = &sorting.sort {
+            for name in sort.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+                let (field_name, direction) = match name.strip_prefix('-') {
+                    Some(field_name) => (field_name, SortDirection::Desc),
+                    None => (name.strip_prefix('+').unwrap_or(name), SortDirection::Asc)
+                };
+
+                let field = F::from_name(field_name).ok_or_else(|| {
+                    tracing::warn!("unknown sort field: {}", field_name);
+                    ApiError::InvalidSortField
+                })?;
+
+                sort_keys.push(SortKey {
+                    field,
+                    direction
+                });
+            }
+        }
+
+        Ok(sort_keys)
+    }
+
+    pub fn order_by<Q, E>(&self, query: Q, expression: E, direction: SortDirection) -> Q
+    where
+        E: ExpressionMethods,
+        Q: ThenOrderDsl<Asc<E>, Output = Q> + ThenOrderDsl<Desc<E>, Output = Q>
+    {
+        match direction {
+            SortDirection::Asc => query.then_order_by(expression.asc()),
+            SortDirection::Desc => query.then_order_by(expression.desc())
+        }
+    }
+    // the id tiebreaker keeps pages stable when sorted values are equal, rows before the cursor are read reversed
+    pub fn order_by_id<Q, E>(&self, query: Q, id_column: E, primary_direction: SortDirection, is_before: bool) -> Q
+    where
+        E: ExpressionMethods,
+        Q: ThenOrderDsl<Asc<E>, Output = Q> + ThenOrderDsl<Desc<E>, Output = Q>
+    {
+        let direction = if is_before { primary_direction.reverse() } else { primary_direction };
+        self.order_by(query, id_column, direction)
+    }
+}
\ No newline at end of file
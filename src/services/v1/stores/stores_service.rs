@@ -1,16 +1,31 @@
+use std::collections::HashMap;
+use chrono::NaiveDateTime;
 use diesel::{
-    dsl::{count, exists, select}, insert_into, ExpressionMethods, QueryDsl, RunQueryDsl, SaveChangesDsl, SelectableHelper, TextExpressionMethods
+    dsl::{count, exists, select}, insert_into, pg::Pg, sql_query, sql_types::{Int8, Nullable, Text}, update, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper
 };
+use serde_json::json;
 
 use crate::{
     models::v1::{
-        collections::service_collection::ServiceCollection, entities::entity_store::{EntityStore, NewEntityStore, UpdateEntityStore}, errors::api_error::ApiError, forms::patch_payload::PatchStorePayload, parameters::pagination::Pagination, responses::response_store::ResponseStore
+        collections::service_collection::ServiceCollection, entities::{entity_store::{EntityStore, NewEntityStore, UpdateEntityStore}, entity_usage::EntityStoreUsage}, errors::api_error::ApiError, forms::patch_payload::PatchStorePayload, parameters::{cursor::CursorDirection, pagination::{Pagination, DEFAULT_LIMIT, MAX_LIMIT}, query_filters::{KeywordFilters, TimestampFilters}, sorting::{StoreSortField, SortDirection, SortKey, Sorting}}, responses::response_store::{ResponseStore, ResponseStoreSuggestion}
     }, 
     repository::DbRepository, 
     schema::stores, 
-    services::v1::{converters::converters_service::ConverterService, fallbacks::fallbacks_service::FallbacksService}
+    services::v1::{batches::batches_service::BatchService, converters::converters_service::ConverterService, cursors::cursors_service::CursorService, fallbacks::fallbacks_service::FallbacksService, matchers::matchers_service::MatcherService, sortings::sortings_service::SortingService, tax_rates::tax_rates_service::TaxRateService}
 };
 
+const STORE_SUGGESTIONS_SQL: &str = r#"
+SELECT s.id,
+       COUNT(r.id) AS visit_count,
+       MAX(r.transaction_date) AS last_visited_at
+FROM stores s
+LEFT JOIN receipts r ON r.store_id = s.id AND r.deleted_at IS NULL
+WHERE $1::TEXT IS NULL OR s.name ILIKE $1
+GROUP BY s.id, s.name
+ORDER BY visit_count DESC, last_visited_at DESC NULLS LAST, s.name, s.id
+LIMIT $2
+"#;
+
 pub struct StoreService<'a> {
     repository: &'a DbRepository
 }
@@ -43,34 +58,121 @@ impl<'a> StoreService<'a> {
         Ok(store_response)
     }
 
-    pub async fn get_stores(&self, pagination: &Pagination) -> Result<ServiceCollection<ResponseStore>, ApiError> {
+    pub async fn get_stores_by_ids(&self, ids: &[i32]) -> Result<(Vec<ResponseStore>, Vec<i32>), ApiError> {
+        let converter = ConverterService::new();
+        let batch_service = BatchService::new();
+        let ids = batch_service.get_unique_ids(ids)?;
+        let conn = &mut self.repository.pool.get().or_else(|e| {
+            tracing::error!("database connection broken: {}", e);
+            Err(ApiError::DatabaseConnectionBroken)
+        })?;
+
+        let stores_query = 
+            stores::table
+            .filter(stores::id.eq_any(&ids))
+            .select(<EntityStore>::as_select());
+
+        let stores_list = stores_query.get_results::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (stores_list, not_found) = batch_service.arrange(&ids, stores_list, |s| s.id);
+
+        Ok((converter.convert_to_all_stores_response(stores_list), not_found))
+    }
+
+    pub async fn get_stores(&self, pagination: &Pagination, timestamp_filters: &TimestampFilters, sorting: &Sorting) -> Result<ServiceCollection<ResponseStore>, ApiError> {
         let converter: ConverterService = ConverterService::new();
         let fallbacks_service = FallbacksService::new();
+        let sorting_service = SortingService::new();
+        let sort_keys = sorting_service.parse_sort::<StoreSortField>(sorting)?;
+        let primary_sort_key = sort_keys.first().copied().unwrap_or(SortKey { field: StoreSortField::Id, direction: SortDirection::Asc });
+        let cursor_service = CursorService::new();
+        let cursor = cursor_service.get_cursor(pagination, &sort_keys, &primary_sort_key)?;
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let count: i64 = stores::table.select(count(stores::columns::id)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let build_query = || {
+            let mut query = stores::table.into_boxed::<Pg>();
+            if let Some(created_from) = timestamp_filters.created_from {
+                query = query.filter(stores::created_at.ge(created_from));
+            }
+            if let Some(created_to) = timestamp_filters.created_to {
+                query = query.filter(stores::created_at.le(created_to));
+            }
+            if let Some(updated_from) = timestamp_filters.updated_from {
+                query = query.filter(stores::updated_at.ge(updated_from));
+            }
+            if let Some(updated_to) = timestamp_filters.updated_to {
+                query = query.filter(stores::updated_at.le(updated_to));
+            }
+            query
+        };
+
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(build_query().select(count(stores::columns::id)).first::<i64>(conn).or_else(|_e| Err(ApiError::NoRecord))?)
+        }
+        else {
+            None
+        };
         
         let (page_offset, per_page) = fallbacks_service.fallback_pagination(&pagination);
 
-        let stores_in_this_page_query = 
-            stores::table
-                .limit(per_page)
-                .offset(page_offset)
-                .select(<EntityStore>::as_select());
+        let mut stores_in_this_page_query = build_query();
+        if let Some((cursor, cursor_direction)) = &cursor {
+            let greater = cursor_service.is_seeking_greater(*cursor_direction, primary_sort_key.direction);
+            stores_in_this_page_query = match primary_sort_key.field {
+                StoreSortField::Id => cursor_service.seek(stores_in_this_page_query, stores::id, cursor_service.get_cursor_value::<i32>(cursor)?, stores::id, cursor.id, greater),
+                StoreSortField::Name => cursor_service.seek(stores_in_this_page_query, stores::name, cursor_service.get_cursor_value::<String>(cursor)?, stores::id, cursor.id, greater),
+                StoreSortField::Alias => return Err(ApiError::InvalidCursor),
+                StoreSortField::Branch => return Err(ApiError::InvalidCursor),
+                StoreSortField::CreatedAt => cursor_service.seek(stores_in_this_page_query, stores::created_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, stores::id, cursor.id, greater),
+                StoreSortField::UpdatedAt => cursor_service.seek(stores_in_this_page_query, stores::updated_at, cursor_service.get_cursor_value::<NaiveDateTime>(cursor)?, stores::id, cursor.id, greater)
+            };
+        }
 
-        let stores_in_this_page = stores_in_this_page_query.get_results::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        // rows before the cursor are read in the reversed order and flipped back by paginate
+        let is_before = matches!(cursor, Some((_, CursorDirection::Before)));
+        for sort_key in &sort_keys {
+            let direction = if is_before { sort_key.direction.reverse() } else { sort_key.direction };
+            stores_in_this_page_query = match sort_key.field {
+                StoreSortField::Id => sorting_service.order_by(stores_in_this_page_query, stores::id, direction),
+                StoreSortField::Name => sorting_service.order_by(stores_in_this_page_query, stores::name, direction),
+                StoreSortField::Alias => sorting_service.order_by(stores_in_this_page_query, stores::alias, direction),
+                StoreSortField::Branch => sorting_service.order_by(stores_in_this_page_query, stores::branch, direction),
+                StoreSortField::CreatedAt => sorting_service.order_by(stores_in_this_page_query, stores::created_at, direction),
+                StoreSortField::UpdatedAt => sorting_service.order_by(stores_in_this_page_query, stores::updated_at, direction)
+            };
+        }
+        stores_in_this_page_query = sorting_service.order_by_id(stores_in_this_page_query, stores::id, primary_sort_key.direction, is_before);
+
+        // one extra row tells whether there are more rows after this page
+        let rows = stores_in_this_page_query
+            .limit(per_page + 1)
+            .offset(if cursor.is_some() { 0 } else { page_offset })
+            .select(<EntityStore>::as_select())
+            .get_results::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let (stores_in_this_page, next_cursor, prev_cursor) = cursor_service.paginate(rows, per_page, page_offset, cursor.as_ref().map(|c| c.1), cursor_service.get_cursor_signature(&sort_keys, &primary_sort_key), |row| {
+            let value = match primary_sort_key.field {
+                StoreSortField::Id => json!(row.id),
+                StoreSortField::Name => json!(row.name),
+                StoreSortField::Alias => json!(row.alias),
+                StoreSortField::Branch => json!(row.branch),
+                StoreSortField::CreatedAt => json!(row.created_at),
+                StoreSortField::UpdatedAt => json!(row.updated_at)
+            };
+            (value, row.id)
+        });
 
         Ok({
             ServiceCollection { 
                 partial_collection: converter.convert_to_all_stores_response(stores_in_this_page),
-                total_count: count
+                total_count: count,
+                next_cursor,
+                prev_cursor
             }
         })
     }
-    
+
     pub async fn is_store_existed_by_id(&self, id: i32) -> Result<bool, ApiError> {
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
@@ -109,17 +211,41 @@ impl<'a> StoreService<'a> {
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let entity_store = insert_into(stores::table)
+        let store_id = insert_into(stores::table)
             .values(store)
-            .get_result::<EntityStore>(conn).or_else(|e| {
+            .returning(stores::id)
+            .get_result::<i32>(conn).or_else(|e| {
                 tracing::error!("insert store entity failed: {}", e);
                 Err(ApiError::InsertStoreFailed)
         })?;
 
-        Ok(entity_store.id)
+        Ok(store_id)
+    }
+
+    pub async fn get_store_version(&self, id: i32) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        stores::table.filter(stores::id.eq(id)).select(stores::version).get_result::<i32>(conn).map_err(|e| {
+            tracing::warn!("try to get version of a non existed store ({}): {}", id, e);
+            ApiError::NoRecord
+        })
+    }
+
+    // checked before the patch command is queued, so the client gets the error instead of the writer
+    pub async fn validate_patch_store(&self, store: &PatchStorePayload) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        TaxRateService::new(self.repository).find_tax_rate(conn, store.tax_rate_id.flatten())?;
+        Ok(())
     }
 
-    pub async fn patch_store(&self, id: i32, store: &PatchStorePayload) -> Result<(), ApiError> {
+    pub async fn patch_store(&self, id: i32, store: &PatchStorePayload, expected_version: i32) -> Result<(), ApiError> {
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
@@ -130,7 +256,8 @@ impl<'a> StoreService<'a> {
             name: None,
             alias: None,
             branch: None,
-            address: None
+            address: None,
+            tax_rate_id: store.tax_rate_id
         };
 
         if store.name.is_some() {
@@ -171,43 +298,61 @@ impl<'a> StoreService<'a> {
             }
         }
 
-        updated_store.save_changes::<EntityStore>(conn).or_else(|e| {
-            tracing::error!("update store entity failed: {}", e);
-            Err(ApiError::UpdateStoreFailed)
-        })?;
+        let affected_rows = update(stores::table.filter(stores::id.eq(id)).filter(stores::version.eq(expected_version)))
+            .set((&updated_store, stores::version.eq(stores::version + 1)))
+            .execute(conn).map_err(|e| {
+                tracing::error!("update store entity failed: {}", e);
+                ApiError::UpdateStoreFailed
+            })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("store {} is not existed or its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
         
         tracing::debug!("patch store {} successfully", id);
         Ok(())
     }
 
-    pub async fn autocomplete_stores(&self, keyword: &Option<String>) -> Result<ServiceCollection<ResponseStore>, ApiError> {
+    pub async fn autocomplete_stores(&self, keyword_filters: &KeywordFilters) -> Result<ServiceCollection<ResponseStoreSuggestion>, ApiError> {
         let converter: ConverterService = ConverterService::new();
         let conn = &mut self.repository.pool.get().or_else(|e| {
             tracing::error!("database connection broken: {}", e);
             Err(ApiError::DatabaseConnectionBroken)
         })?;
 
-        let build_query = || {
-            let mut sql_filters = stores::table.limit(20).into_boxed();
-            if let Some(kw) = &keyword {
-                let store_name_pattern = format!("%{}%", kw);
-                sql_filters = sql_filters.filter(stores::name.like(store_name_pattern))
-            }
-
-            sql_filters
-        };
-
-        let count: i64 = build_query().select(count(stores::columns::name)).first(conn).or_else(|_e| Err(ApiError::NoRecord))?;
-
-        let stores_query = build_query().select(<EntityStore>::as_select());
-
-        let stores_list = stores_query.get_results::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?;
+        let matcher_service = MatcherService::new();
+        let keyword_pattern = matcher_service.get_optional_pattern(&keyword_filters.keyword, keyword_filters.match_mode.unwrap_or_default());
+        let limit = keyword_filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let usages = sql_query(STORE_SUGGESTIONS_SQL)
+            .bind::<Nullable<Text>, _>(&keyword_pattern)
+            .bind::<Int8, _>(limit)
+            .load::<EntityStoreUsage>(conn).or_else(|e| {
+                tracing::error!("rank store suggestions failed: {}", e);
+                Err(ApiError::NoRecord)
+            })?;
+
+        let ids: Vec<i32> = usages.iter().map(|u| u.id).collect();
+        let mut stores_map: HashMap<i32, EntityStore> = stores::table
+            .filter(stores::id.eq_any(&ids))
+            .select(<EntityStore>::as_select())
+            .get_results::<EntityStore>(conn).or_else(|_e| Err(ApiError::NoRecord))?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect();
+
+        // keep the ranking order of the usage query
+        let suggestions: Vec<ResponseStoreSuggestion> = usages.into_iter().filter_map(|usage| {
+            stores_map.remove(&usage.id).map(|store| converter.convert_to_store_suggestion_response(store, usage))
+        }).collect();
 
         Ok({
             ServiceCollection { 
-                partial_collection: converter.convert_to_all_stores_response(stores_list),
-                total_count: count
+                total_count: Some(suggestions.len() as i64),
+                partial_collection: suggestions,
+                next_cursor: None,
+                prev_cursor: None
             }
         })
     }
-}
+}
\ No newline at end of file
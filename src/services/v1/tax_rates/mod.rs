@@ -0,0 +1 @@
+pub mod tax_rates_service;
\ No newline at end of file
@@ -0,0 +1,230 @@
+use bigdecimal::BigDecimal;
+use diesel::{delete, dsl::{count, exists, select}, insert_into, update, BoolExpressionMethods, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+
+use crate::{models::v1::{collections::service_collection::ServiceCollection, entities::entity_tax_rate::{EntityTaxRate, NewEntityTaxRate, UpdateEntityTaxRate}, errors::api_error::ApiError, forms::{create_payload::CreateTaxRatePayload, patch_payload::PatchTaxRatePayload}, parameters::pagination::Pagination, responses::response_tax_rate::ResponseTaxRate}, repository::DbRepository, schema::{inventories, stores, tax_rates}, services::v1::{converters::converters_service::ConverterService, fallbacks::fallbacks_service::FallbacksService}};
+
+pub struct TaxRateService<'a> {
+    repository: &'a DbRepository
+}
+
+impl<'a> TaxRateService<'a> {
+    pub fn new(repository: &'a DbRepository) -> Self {
+        Self {
+            repository
+        }
+    }
+
+    pub async fn get_tax_rate(&self, id: i32) -> Result<ResponseTaxRate, ApiError> {
+        let converter = ConverterService::new();
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let tax_rate = tax_rates::table.filter(tax_rates::id.eq(id)).select(<EntityTaxRate>::as_select()).get_result::<EntityTaxRate>(conn).map_err(|e| {
+            tracing::warn!("try to get a non existed tax rate ({}): {}", id, e);
+            ApiError::NoRecord
+        })?;
+
+        Ok(converter.convert_to_tax_rate_response(tax_rate))
+    }
+
+    pub async fn get_tax_rates(&self, pagination: &Pagination) -> Result<ServiceCollection<ResponseTaxRate>, ApiError> {
+        let converter = ConverterService::new();
+        let fallbacks_service = FallbacksService::new();
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let count = if pagination.include_total.unwrap_or(true) {
+            Some(tax_rates::table.select(count(tax_rates::id)).first::<i64>(conn).map_err(|_e| ApiError::NoRecord)?)
+        }
+        else {
+            None
+        };
+
+        let (page_offset, per_page) = fallbacks_service.fallback_pagination(pagination);
+        let tax_rates_in_this_page = tax_rates::table
+            .order(tax_rates::id.asc())
+            .limit(per_page)
+            .offset(page_offset)
+            .select(<EntityTaxRate>::as_select())
+            .get_results::<EntityTaxRate>(conn).map_err(|_e| ApiError::NoRecord)?;
+
+        Ok(ServiceCollection {
+            partial_collection: converter.convert_to_all_tax_rates_response(tax_rates_in_this_page),
+            total_count: count,
+            next_cursor: None,
+            prev_cursor: None
+        })
+    }
+
+    // checked before the create command is queued, so the client gets the error instead of the writer
+    pub async fn validate_tax_rate(&self, tax_rate: &CreateTaxRatePayload) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        self.validate_rate(&tax_rate.rate)?;
+        self.validate_name(conn, &tax_rate.name, None)
+    }
+
+    pub async fn validate_patch_tax_rate(&self, id: i32, tax_rate: &PatchTaxRatePayload) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        if let Some(rate) = &tax_rate.rate {
+            self.validate_rate(rate)?;
+        }
+        if let Some(name) = &tax_rate.name {
+            self.validate_name(conn, name, Some(id))?;
+        }
+
+        Ok(())
+    }
+
+    // the rate column is NUMERIC(9, 6)
+    fn validate_rate(&self, rate: &BigDecimal) -> Result<(), ApiError> {
+        if *rate < 0 || *rate >= 1000 || rate.normalized().fractional_digit_count() > 6 {
+            tracing::warn!("invalid tax rate {}", rate);
+            return Err(ApiError::InvalidParameter);
+        }
+
+        Ok(())
+    }
+
+    // a rate keeps its own name when it is renamed
+    fn validate_name(&self, conn: &mut PgConnection, name: &str, id: Option<i32>) -> Result<(), ApiError> {
+        if name.trim().is_empty() {
+            tracing::warn!("tax rate name is empty");
+            return Err(ApiError::InvalidParameter);
+        }
+
+        let is_duplicated = select(exists(tax_rates::table.filter(tax_rates::name.eq(name.trim())).filter(tax_rates::id.ne(id.unwrap_or(0))))).get_result::<bool>(conn).map_err(|_e| ApiError::NoRecord)?;
+        if is_duplicated {
+            tracing::warn!("tax rate name {} is duplicated", name);
+            return Err(ApiError::TaxRateNameDuplicated);
+        }
+
+        Ok(())
+    }
+
+    // a rate used by a store or a line cannot be deleted, the amounts of the lines are kept when a rate is changed
+    pub async fn validate_delete_tax_rate(&self, id: i32) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let is_referenced = select(exists(stores::table.filter(stores::tax_rate_id.eq(id))).or(exists(inventories::table.filter(inventories::tax_rate_id.eq(id))))).get_result::<bool>(conn).map_err(|_e| ApiError::NoRecord)?;
+        if is_referenced {
+            tracing::warn!("tax rate {} is used by stores or inventories", id);
+            return Err(ApiError::TaxRateReferenced);
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_tax_rate_version(&self, id: i32) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        tax_rates::table.filter(tax_rates::id.eq(id)).select(tax_rates::version).get_result::<i32>(conn).map_err(|e| {
+            tracing::warn!("try to get version of a non existed tax rate ({}): {}", id, e);
+            ApiError::NoRecord
+        })
+    }
+
+    pub async fn patch_tax_rate(&self, id: i32, tax_rate: &PatchTaxRatePayload, expected_version: i32) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let updated_tax_rate = UpdateEntityTaxRate {
+            id,
+            name: tax_rate.name.as_ref().map(|name| name.trim().to_string()),
+            rate: tax_rate.rate.as_ref().map(|rate| rate.normalized()),
+            is_inclusive: tax_rate.is_inclusive
+        };
+
+        let affected_rows = update(tax_rates::table.filter(tax_rates::id.eq(id)).filter(tax_rates::version.eq(expected_version)))
+            .set((&updated_tax_rate, tax_rates::version.eq(tax_rates::version + 1)))
+            .execute(conn).map_err(|e| {
+                tracing::error!("update tax rate entity failed: {}", e);
+                ApiError::UpdateTaxRateFailed
+            })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("tax rate {} is not existed or its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
+
+        tracing::debug!("patch tax rate {} successfully", id);
+        Ok(())
+    }
+
+    pub async fn delete_tax_rate(&self, id: i32, expected_version: i32) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let affected_rows = delete(tax_rates::table.filter(tax_rates::id.eq(id)).filter(tax_rates::version.eq(expected_version))).execute(conn).map_err(|e| {
+            tracing::error!("delete tax rate entity failed: {}", e);
+            ApiError::DeleteTaxRateFailed
+        })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("tax rate {} is not existed or its version is not {}", id, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
+
+        tracing::debug!("delete tax rate {} successfully", id);
+        Ok(())
+    }
+
+    pub async fn create_tax_rate(&self, tax_rate: &CreateTaxRatePayload) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let new_tax_rate = NewEntityTaxRate {
+            name: tax_rate.name.trim().to_string(),
+            rate: tax_rate.rate.normalized(),
+            is_inclusive: tax_rate.is_inclusive
+        };
+
+        let tax_rate_id = insert_into(tax_rates::table)
+            .values(&new_tax_rate)
+            .returning(tax_rates::id)
+            .get_result::<i32>(conn).map_err(|e| {
+                tracing::error!("insert tax rate entity failed: {}", e);
+                ApiError::InsertTaxRateFailed
+            })?;
+
+        tracing::debug!("Create tax rate {} successfully", tax_rate_id);
+        Ok(tax_rate_id)
+    }
+
+    // a line without any rate is exempt
+    pub fn find_tax_rate(&self, conn: &mut PgConnection, id: Option<i32>) -> Result<Option<EntityTaxRate>, ApiError> {
+        match id {
+            Some(id) => {
+                let tax_rate = tax_rates::table.filter(tax_rates::id.eq(id)).select(<EntityTaxRate>::as_select()).get_result::<EntityTaxRate>(conn).map_err(|e| {
+                    tracing::warn!("tax rate {} is not existed: {}", id, e);
+                    ApiError::TaxRateIdNotExisted
+                })?;
+                Ok(Some(tax_rate))
+            },
+            None => Ok(None)
+        }
+    }
+}
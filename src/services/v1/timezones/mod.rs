@@ -0,0 +1 @@
+pub mod timezones_service;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Datelike, Days, FixedOffset, LocalResult, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+    configuration::app_config, 
+    models::v1::{errors::api_error::ApiError, forms::create_payload::TransactionDate, parameters::query_filters::{DatePreset, ReportPeriod}}
+};
+
+// [from, to) in UTC, either side may be open
+pub type DateRange = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+pub struct TimeZoneService {
+}
+
+impl TimeZoneService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    pub fn get_time_zone(&self, tz: Option<Tz>) -> Tz {
+        tz.unwrap_or_else(|| app_config().get_default_time_zone())
+    }
+
+    // a local date is placed in the time zone, an ambiguous one (DST fall back) takes the earlier instant
+    pub fn resolve_transaction_date(&self, transaction_date: &TransactionDate, tz: Option<Tz>) -> Result<DateTime<FixedOffset>, ApiError> {
+        match transaction_date {
+            TransactionDate::Zoned(date) => Ok(*date),
+            TransactionDate::Local(date) => {
+                let tz = self.get_time_zone(tz);
+                let zoned = tz.from_local_datetime(date).earliest().ok_or_else(|| {
+                    tracing::warn!("transaction date {} does not exist in time zone {}", date, tz);
+                    ApiError::InvalidParameter
+                })?;
+                Ok(zoned.with_timezone(&zoned.offset().fix()))
+            }
+        }
+    }
+
+    pub fn get_date_range(&self, start_date: Option<NaiveDate>, end_date: Option<NaiveDate>, preset: Option<DatePreset>, tz: Option<Tz>) -> Result<DateRange, ApiError> {
+        let tz = self.get_time_zone(tz);
+        let (start_date, end_date) = match preset {
+            Some(preset) => {
+                if start_date.is_some() || end_date.is_some() {
+                    tracing::warn!("date preset {:?} can not be combined with start_date or end_date", preset);
+                    return Err(ApiError::InvalidDateRange);
+                }
+                let (start, end) = self.get_preset_dates(preset, Utc::now().with_timezone(&tz).date_naive());
+                (Some(start), Some(end))
+            },
+            None => (start_date, end_date)
+        };
+
+        if let (Some(start), Some(end)) = (start_date, end_date) {
+            if start > end {
+                tracing::warn!("start date {} is after end date {}", start, end);
+                return Err(ApiError::InvalidDateRange);
+            }
+        }
+
+        let from = start_date.map(|d| self.get_day_start(d, tz));
+        let to = end_date.and_then(|d| d.succ_opt()).map(|d| self.get_day_start(d, tz));
+        Ok((from, to))
+    }
+
+    // inclusive local dates
+    fn get_preset_dates(&self, preset: DatePreset, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let week_start = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let month_start = today.with_day(1).expect("the first day of a month should always exist");
+        let year_start = NaiveDate::from_ymd_opt(today.year(), 1, 1).expect("the first day of a year should always exist");
+        match preset {
+            DatePreset::Today => (today, today),
+            DatePreset::Yesterday => (today - Days::new(1), today - Days::new(1)),
+            DatePreset::ThisWeek => (week_start, today),
+            DatePreset::LastWeek => (week_start - Days::new(7), week_start - Days::new(1)),
+            DatePreset::ThisMonth => (month_start, today),
+            DatePreset::LastMonth => (month_start - Months::new(1), month_start - Days::new(1)),
+            DatePreset::ThisYear => (year_start, today),
+            DatePreset::LastYear => (year_start - Months::new(12), year_start - Days::new(1)),
+            DatePreset::Last7Days => (today - Days::new(6), today),
+            DatePreset::Last30Days => (today - Days::new(29), today),
+            DatePreset::Last90Days => (today - Days::new(89), today)
+        }
+    }
+
+    // the same buckets as date_trunc, weeks start on Monday
+    pub fn get_period_start(&self, date: NaiveDate, period: ReportPeriod) -> NaiveDate {
+        let month_start = date.with_day(1).expect("the first day of a month should always exist");
+        match period {
+            ReportPeriod::Day => date,
+            ReportPeriod::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            ReportPeriod::Month => month_start,
+            ReportPeriod::Quarter => month_start - Months::new(date.month0() % 3),
+            ReportPeriod::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).expect("the first day of a year should always exist")
+        }
+    }
+
+    // local midnight does not exist in zones that switch DST at midnight, then the day starts at the transition
+    fn get_day_start(&self, date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+        let midnight = NaiveDateTime::new(date, NaiveTime::MIN);
+        match tz.from_local_datetime(&midnight) {
+            LocalResult::Single(start) => start.with_timezone(&Utc),
+            LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+            LocalResult::None => {
+                let mut probe = midnight;
+                loop {
+                    probe += TimeDelta::minutes(15);
+                    if let Some(start) = tz.from_local_datetime(&probe).earliest() {
+                        return start.with_timezone(&Utc);
+                    }
+                }
+            }
+        }
+    }
+}
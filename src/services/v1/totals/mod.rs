@@ -0,0 +1 @@
+pub mod totals_service;
\ No newline at end of file
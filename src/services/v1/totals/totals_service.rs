@@ -0,0 +1,142 @@
+use bigdecimal::BigDecimal;
+
+use crate::{models::v1::{entities::{entity_inventory::EntityInventory, entity_receipt::EntityReceipt, entity_tax_rate::EntityTaxRate, entity_total::EntityReceiptSubtotal}, errors::api_error::ApiError, responses::response_receipt::ResponseReceiptTotals}, services::v1::decimals::decimals_service::DecimalService};
+
+// the grand total of a receipt in SQL, the same as get_grand_total, as a macro so queries kept as constants can splice it in with concat!
+macro_rules! receipt_total_sql {
+    () => {
+        "((SELECT COALESCE(SUM(inventories.post_tax_amount), 0) FROM inventories WHERE inventories.receipt_id = receipts.id) - receipts.discount + receipts.service_charge)"
+    };
+}
+pub(crate) use receipt_total_sql;
+
+pub const RECEIPT_TOTAL_SQL: &str = receipt_total_sql!();
+const QUANTITY_SCALE: i64 = 3;
+const MAX_QUANTITY_INTEGER_DIGITS: u32 = 9;
+
+pub struct TotalService {
+}
+
+impl TotalService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    // amounts entered by the client are never negative, a discount is subtracted by its field
+    pub fn get_amount(&self, amount: &BigDecimal, minor_units: i32) -> Result<BigDecimal, ApiError> {
+        if *amount < 0 {
+            tracing::warn!("amount should not be negative: {}", amount);
+            return Err(ApiError::InvalidParameter);
+        }
+
+        DecimalService::new().validate_amount(amount, minor_units)
+    }
+
+    // quantity columns are NUMERIC(12, 3)
+    pub fn get_quantity(&self, quantity: &BigDecimal) -> Result<BigDecimal, ApiError> {
+        let normalized = DecimalService::new().normalize(quantity);
+        if normalized <= 0 || normalized.fractional_digit_count() > QUANTITY_SCALE || normalized >= 10_u64.pow(MAX_QUANTITY_INTEGER_DIGITS) {
+            tracing::warn!("quantity {} is not positive or out of range", quantity);
+            return Err(ApiError::InvalidQuantity);
+        }
+
+        Ok(normalized)
+    }
+
+    // the line price printed on a receipt is rounded, 0.453 kg at 198 per kg is 90 in JPY
+    pub fn get_line_price(&self, price: &BigDecimal, quantity: &BigDecimal, minor_units: i32) -> BigDecimal {
+        DecimalService::new().round(&(price * quantity), minor_units)
+    }
+
+    // a line never adds to the total through its discount
+    pub fn get_discount(&self, discount: &BigDecimal, line_price: &BigDecimal, minor_units: i32) -> Result<BigDecimal, ApiError> {
+        let discount = self.get_amount(discount, minor_units)?;
+        if discount > *line_price {
+            tracing::warn!("discount {} is larger than the line price {}", discount, line_price);
+            return Err(ApiError::DiscountExceedsLinePrice);
+        }
+
+        Ok(discount)
+    }
+
+    pub fn get_line_total(&self, line_price: &BigDecimal, discount: &BigDecimal) -> BigDecimal {
+        line_price - discount
+    }
+
+    // returns the pre-tax and the post-tax amounts of a line total rounded to the minor units, an exempt line has no rate
+    pub fn get_tax_amounts(&self, line_total: &BigDecimal, tax_rate: Option<&EntityTaxRate>, minor_units: i32) -> (BigDecimal, BigDecimal) {
+        let decimal_service = DecimalService::new();
+        match tax_rate {
+            Some(tax_rate) if tax_rate.is_inclusive => (decimal_service.round(&(line_total / (BigDecimal::from(1) + &tax_rate.rate)), minor_units), line_total.clone()),
+            Some(tax_rate) => (line_total.clone(), decimal_service.round(&(line_total * (BigDecimal::from(1) + &tax_rate.rate)), minor_units)),
+            None => (line_total.clone(), line_total.clone())
+        }
+    }
+
+    // the stored amounts of a line follow a new line total at their own effective rate, which keeps a tax that no rate describes (a migrated receipt tax), none when the old total was zero
+    pub fn get_scaled_tax_amounts(&self, pre_tax_amount: &BigDecimal, post_tax_amount: &BigDecimal, old_line_total: &BigDecimal, new_line_total: &BigDecimal, minor_units: i32) -> Option<(BigDecimal, BigDecimal)> {
+        if old_line_total == &BigDecimal::from(0) {
+            return None;
+        }
+        let decimal_service = DecimalService::new();
+        Some((
+            decimal_service.round(&(pre_tax_amount * new_line_total / old_line_total), minor_units),
+            decimal_service.round(&(post_tax_amount * new_line_total / old_line_total), minor_units)
+        ))
+    }
+
+    pub fn get_subtotal(&self, receipt_id: i32, inventories: &[EntityInventory]) -> EntityReceiptSubtotal {
+        inventories.iter().fold(EntityReceiptSubtotal { receipt_id, ..Default::default() }, |subtotal, inventory| EntityReceiptSubtotal {
+            receipt_id,
+            subtotal: subtotal.subtotal + self.get_line_total(&inventory.line_price, &inventory.discount),
+            pre_tax: subtotal.pre_tax + &inventory.pre_tax_amount,
+            post_tax: subtotal.post_tax + &inventory.post_tax_amount,
+            line_count: subtotal.line_count + 1
+        })
+    }
+
+    pub fn get_grand_total(&self, receipt: &EntityReceipt, subtotal: &EntityReceiptSubtotal) -> BigDecimal {
+        &subtotal.post_tax - &receipt.discount + &receipt.service_charge
+    }
+
+    // the tax of every line is rounded on its own and the store may round it differently,
+    // so the printed total is a mismatch only when it is off by more than one minor unit per line
+    pub fn get_receipt_totals(&self, receipt: &EntityReceipt, subtotal: &EntityReceiptSubtotal, minor_units: i32) -> ResponseReceiptTotals {
+        let decimal_service = DecimalService::new();
+        let grand_total = self.get_grand_total(receipt, subtotal);
+        let tolerance = decimal_service.get_minor_unit(minor_units) * BigDecimal::from(subtotal.line_count);
+        let is_total_mismatched = receipt.printed_total.as_ref().is_some_and(|printed_total| (printed_total - &grand_total).abs() > tolerance);
+
+        ResponseReceiptTotals {
+            subtotal: decimal_service.format(&subtotal.subtotal, minor_units),
+            pre_tax: decimal_service.format(&subtotal.pre_tax, minor_units),
+            tax: decimal_service.format(&(&subtotal.post_tax - &subtotal.pre_tax), minor_units),
+            post_tax: decimal_service.format(&subtotal.post_tax, minor_units),
+            discount: decimal_service.format(&receipt.discount, minor_units),
+            service_charge: decimal_service.format(&receipt.service_charge, minor_units),
+            grand_total: decimal_service.format(&grand_total, minor_units),
+            printed_total: receipt.printed_total.as_ref().map(|p| decimal_service.format(p, minor_units)),
+            is_total_mismatched
+        }
+    }
+
+    // every amount is converted before the totals are added up, so the converted totals are rounded only once
+    pub fn get_converted_receipt_totals(&self, receipt: &EntityReceipt, subtotal: &EntityReceiptSubtotal, rate: &BigDecimal, minor_units: i32) -> ResponseReceiptTotals {
+        let converted_receipt = EntityReceipt {
+            discount: &receipt.discount * rate,
+            service_charge: &receipt.service_charge * rate,
+            printed_total: receipt.printed_total.as_ref().map(|printed_total| printed_total * rate),
+            ..receipt.clone()
+        };
+        let converted_subtotal = EntityReceiptSubtotal {
+            receipt_id: subtotal.receipt_id,
+            subtotal: &subtotal.subtotal * rate,
+            pre_tax: &subtotal.pre_tax * rate,
+            post_tax: &subtotal.post_tax * rate,
+            line_count: subtotal.line_count
+        };
+
+        self.get_receipt_totals(&converted_receipt, &converted_subtotal, minor_units)
+    }
+}
@@ -0,0 +1 @@
+pub mod trash_service;
\ No newline at end of file
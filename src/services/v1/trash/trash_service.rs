@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use crate::models::v1::commands::writer_command::WriterCommand;
+
+pub struct TrashService {
+}
+
+impl TrashService {
+    pub fn run(sender: tokio::sync::mpsc::Sender<WriterCommand>, retention_days: i64, purge_interval: Duration) {
+        tracing::info!("Purge receipts trashed more than {} days every {:?}", retention_days, purge_interval);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(purge_interval);
+            loop {
+                ticker.tick().await;
+                // Purging goes through the writer channel as well, so it never races with the other write commands
+                if let Err(e) = sender.send(WriterCommand::PurgeTrashedReceipts(retention_days)).await {
+                    tracing::error!("writer channel closed, stop purging trash: {}", e);
+                    break;
+                }
+            }
+        });
+    }
+}
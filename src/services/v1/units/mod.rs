@@ -0,0 +1 @@
+pub mod units_service;
\ No newline at end of file
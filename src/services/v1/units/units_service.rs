@@ -0,0 +1,62 @@
+use bigdecimal::BigDecimal;
+
+use crate::models::v1::entities::{entity_inventory::EntityInventory, entity_product::EntityProduct, entity_unit::{EntityStandardQuantity, StandardUnit}};
+
+// unit prices are shown with more precision than the currency, 19.8 per 100 g in JPY is not 20
+pub const UNIT_PRICE_EXTRA_DIGITS: i32 = 2;
+
+pub struct UnitService {
+}
+
+impl UnitService {
+    pub fn new() -> Self {
+        Self {
+        }
+    }
+
+    // the standard unit of a unit and how many of its base unit it holds, the specification unit of a product is free text
+    pub fn get_measure(&self, unit: &str) -> Option<(StandardUnit, i32)> {
+        match unit.trim().to_lowercase().as_str() {
+            "g" | "gr" | "gram" | "grams" | "gramme" | "grammes" => Some((StandardUnit::Mass, 1)),
+            "kg" | "kgs" | "kilo" | "kilos" | "kilogram" | "kilograms" | "kilogramme" | "kilogrammes" => Some((StandardUnit::Mass, 1000)),
+            "ml" | "cc" | "millilitre" | "millilitres" | "milliliter" | "milliliters" => Some((StandardUnit::Volume, 1)),
+            "l" | "litre" | "litres" | "liter" | "liters" => Some((StandardUnit::Volume, 1000)),
+            "piece" | "pieces" | "pc" | "pcs" | "count" | "ct" | "pack" | "packs" | "个" | "個" | "入" | "枚" | "本" => Some((StandardUnit::Count, 1)),
+            _ => None
+        }
+    }
+
+    pub fn get_standard_quantity(&self, inventory: &EntityInventory, product: &EntityProduct) -> Option<EntityStandardQuantity> {
+        self.get_standard_quantity_of(&inventory.quantity, &inventory.unit, product)
+    }
+
+    // a quantity counted in pieces takes the specification of its product when it has one, so a pack of 6 eggs is 6 pieces
+    pub fn get_standard_quantity_of(&self, quantity: &BigDecimal, unit: &str, product: &EntityProduct) -> Option<EntityStandardQuantity> {
+        let (unit, factor) = self.get_measure(unit)?;
+        let specification = product.specification_amount
+            .filter(|amount| *amount > 0)
+            .zip(product.specification_unit.as_deref().and_then(|unit| self.get_measure(unit)));
+
+        let standard_quantity = match (unit, specification) {
+            (StandardUnit::Count, Some((amount, (specification_unit, specification_factor)))) => EntityStandardQuantity {
+                amount: quantity * BigDecimal::from(amount) * BigDecimal::from(specification_factor),
+                unit: specification_unit
+            },
+            _ => EntityStandardQuantity {
+                amount: quantity * BigDecimal::from(factor),
+                unit
+            }
+        };
+
+        (standard_quantity.amount > 0).then_some(standard_quantity)
+    }
+
+    pub fn get_unit_price(&self, line_total: &BigDecimal, standard_quantity: &EntityStandardQuantity) -> BigDecimal {
+        line_total * BigDecimal::from(standard_quantity.unit.size()) / &standard_quantity.amount
+    }
+
+    // the reverse of get_unit_price
+    pub fn get_cost(&self, unit_price: &BigDecimal, standard_quantity: &EntityStandardQuantity) -> BigDecimal {
+        unit_price * &standard_quantity.amount / BigDecimal::from(standard_quantity.unit.size())
+    }
+}
@@ -0,0 +1 @@
+pub mod user_settings_service;
\ No newline at end of file
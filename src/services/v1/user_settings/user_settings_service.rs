@@ -0,0 +1,114 @@
+use diesel::{dsl::{exists, select}, insert_into, update, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+
+use crate::{models::v1::{entities::entity_user_setting::{EntityUserSetting, NewEntityUserSetting, UpdateEntityUserSetting}, errors::api_error::ApiError, forms::patch_payload::PatchUserSettingPayload, responses::response_user_setting::ResponseUserSetting}, repository::DbRepository, schema::{currencies, user_settings}, services::v1::converters::converters_service::ConverterService};
+
+pub struct UserSettingService<'a> {
+    repository: &'a DbRepository
+}
+
+impl<'a> UserSettingService<'a> {
+    pub fn new(repository: &'a DbRepository) -> Self {
+        Self {
+            repository
+        }
+    }
+
+    pub async fn get_user_setting(&self, username: &str) -> Result<ResponseUserSetting, ApiError> {
+        let converter = ConverterService::new();
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let user_setting = self.find_user_setting(conn, username)?;
+        Ok(converter.convert_to_user_setting_response(username, user_setting))
+    }
+
+    // the settings are not saved until the first patch, which is expected at version 0
+    pub async fn get_user_setting_version(&self, username: &str) -> Result<i32, ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        let version = user_settings::table.filter(user_settings::username.eq(username)).select(user_settings::version).get_result::<i32>(conn).optional().map_err(|_e| ApiError::NoRecord)?;
+        Ok(version.unwrap_or(0))
+    }
+
+    pub fn find_user_setting(&self, conn: &mut PgConnection, username: &str) -> Result<Option<EntityUserSetting>, ApiError> {
+        user_settings::table
+            .filter(user_settings::username.eq(username))
+            .select(<EntityUserSetting>::as_select())
+            .get_result::<EntityUserSetting>(conn)
+            .optional()
+            .map_err(|e| {
+                tracing::error!("Unable to get the settings of {}: {}", username, e);
+                ApiError::NoRecord
+            })
+    }
+
+    // checked before the patch command is queued, so the client gets the error instead of the writer
+    pub async fn validate_user_setting(&self, patch_payload: &PatchUserSettingPayload) -> Result<(), ApiError> {
+        if let Some(Some(base_currency_id)) = patch_payload.base_currency_id {
+            let conn = &mut self.repository.pool.get().map_err(|e| {
+                tracing::error!("database connection broken: {}", e);
+                ApiError::DatabaseConnectionBroken
+            })?;
+
+            let is_existed = select(exists(currencies::table.filter(currencies::id.eq(base_currency_id)))).get_result::<bool>(conn).map_err(|_e| ApiError::NoRecord)?;
+            if !is_existed {
+                tracing::warn!("base currency {} is not existed", base_currency_id);
+                return Err(ApiError::CurrencyIdNotExisted);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn patch_user_setting(&self, username: &str, patch_payload: &PatchUserSettingPayload, expected_version: i32) -> Result<(), ApiError> {
+        let conn = &mut self.repository.pool.get().map_err(|e| {
+            tracing::error!("database connection broken: {}", e);
+            ApiError::DatabaseConnectionBroken
+        })?;
+
+        // version 0 means there is no row yet, another writer may have inserted it in the meantime
+        let affected_rows = if expected_version == 0 {
+            let new_user_setting = NewEntityUserSetting {
+                username: username.to_string(),
+                base_currency_id: patch_payload.base_currency_id.flatten()
+            };
+            insert_into(user_settings::table)
+                .values(&new_user_setting)
+                .on_conflict_do_nothing()
+                .execute(conn)
+        }
+        else {
+            let update_user_setting = UpdateEntityUserSetting {
+                base_currency_id: patch_payload.base_currency_id
+            };
+            update(user_settings::table.filter(user_settings::username.eq(username)).filter(user_settings::version.eq(expected_version)))
+                .set((&update_user_setting, user_settings::version.eq(user_settings::version + 1)))
+                .execute(conn)
+        }.map_err(|e| {
+            tracing::error!("update user setting entity failed: {}", e);
+            ApiError::UpdateUserSettingFailed
+        })?;
+
+        if affected_rows == 0 {
+            tracing::warn!("settings of {} are not at version {}", username, expected_version);
+            return Err(ApiError::PreconditionFailed);
+        }
+
+        tracing::debug!("patch settings of {} successfully", username);
+        Ok(())
+    }
+
+    pub fn get_base_currency_id(&self, conn: &mut PgConnection, username: &str) -> Result<i32, ApiError> {
+        self.find_user_setting(conn, username)?
+            .and_then(|user_setting| user_setting.base_currency_id)
+            .ok_or_else(|| {
+                tracing::warn!("base currency of {} is not set", username);
+                ApiError::BaseCurrencyNotSet
+            })
+    }
+}